@@ -0,0 +1,119 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::code::value::Value;
+
+pub type ArrayRef = Rc<RefCell<Array>>;
+
+/// Before GMS2.3, `arr[i, j]` is compiled to a single index `i * 32000 + j`
+pub const LEGACY_ROW_SIZE: i64 = 32000;
+
+/// A GameMaker array. 1D arrays (and every GMS2.3 array) only use the first row;
+/// legacy 2D arrays use one row per first index. GMS2.3 nested arrays are arrays of `Value::Array`.
+#[derive(Debug, Clone)]
+pub struct Array {
+    pub rows: Vec<Vec<Value>>,
+}
+impl Array {
+    pub fn new() -> Self {
+        Self { rows: vec![Vec::new()] }
+    }
+    pub fn from_items(items: Vec<Value>) -> Self {
+        Self { rows: vec![items] }
+    }
+    pub fn new_ref(array: Array) -> ArrayRef {
+        Rc::new(RefCell::new(array))
+    }
+    pub fn len(&self) -> usize {
+        self.rows[0].len()
+    }
+    pub fn get(&self, row: usize, column: usize) -> Option<&Value> {
+        self.rows.get(row)?.get(column)
+    }
+    /// Grows the array as needed; new elements are 0, like in GameMaker
+    pub fn set(&mut self, row: usize, column: usize, value: Value) {
+        if self.rows.len() <= row {
+            self.rows.resize_with(row + 1, Vec::new);
+        }
+        let items: &mut Vec<Value> = &mut self.rows[row];
+        if items.len() <= column {
+            items.resize(column + 1, Value::Real(0.0));
+        }
+        items[column] = value;
+    }
+}
+
+
+/// Splits an index into row and column; legacy arrays encode 2D indices into one number.
+fn split_index(index: &Value, legacy: bool, name: &str) -> Result<(usize, usize), String> {
+    let index: i64 = index.to_int64()
+        .map_err(|e| format!("{e} for index of array {name}"))?;
+    if index < 0 {
+        return Err(format!("Negative array index {index} for variable {name}"))
+    }
+    if legacy {
+        Ok(((index / LEGACY_ROW_SIZE) as usize, (index % LEGACY_ROW_SIZE) as usize))
+    } else {
+        Ok((0, index as usize))
+    }
+}
+
+/// Reads `name[index]` from the value of variable `name`.
+pub fn get_element(array: &Value, index: &Value, legacy: bool, name: &str) -> Result<Value, String> {
+    let Value::Array(array) = array else {
+        return Err(format!("Variable {name} is not an array but {} (accessed with index {index})", array.type_name()))
+    };
+    let (row, column) = split_index(index, legacy, name)?;
+    let array = array.borrow();
+    array.get(row, column).cloned().ok_or_else(|| {
+        let length: usize = array.rows.get(row).map_or(0, |i| i.len());
+        if legacy && row > 0 {
+            format!("Array index [{row},{column}] out of range for variable {name} (row length {length})")
+        } else {
+            format!("Array index {column} out of range for variable {name} (length {length})")
+        }
+    })
+}
+
+/// Writes `name[index] = value` into the variable slot, turning it into an array if it is none.
+/// With copy-on-write (pre-GMS2.3 semantics) a shared array is copied before being modified.
+pub fn set_element(slot: &mut Value, index: &Value, value: Value, legacy: bool, name: &str) -> Result<(), String> {
+    let (row, column) = split_index(index, legacy, name)?;
+    let needs_new_array: bool = match slot {
+        Value::Array(array) => legacy && Rc::strong_count(array) > 1,
+        _ => true,
+    };
+    if needs_new_array {
+        let array: Array = match slot {
+            Value::Array(array) => array.borrow().clone(),
+            _ => Array::new(),
+        };
+        *slot = Value::Array(Array::new_ref(array));
+    }
+    let Value::Array(array) = slot else {
+        return Err(format!("Variable {name} is not an array"))
+    };
+    array.borrow_mut().set(row, column, value);
+    Ok(())
+}
+
+/// Returns the array stored at `index` of the array in the slot, creating both if they do not exist yet.
+/// Used for GMS2.3 multidimensional assignments like `a[i][j] = value`.
+pub fn container_element(slot: &mut Value, index: &Value, name: &str) -> Result<Value, String> {
+    let (_, column) = split_index(index, false, name)?;
+    if !matches!(slot, Value::Array(_)) {
+        *slot = Value::Array(Array::new_ref(Array::new()));
+    }
+    let Value::Array(array) = slot else {
+        return Err(format!("Variable {name} is not an array"))
+    };
+    let mut array = array.borrow_mut();
+    match array.get(0, column) {
+        Some(Value::Array(container)) => Ok(Value::Array(container.clone())),
+        _ => {
+            let container: Value = Value::Array(Array::new_ref(Array::new()));
+            array.set(0, column, container.clone());
+            Ok(container)
+        }
+    }
+}
+
@@ -0,0 +1,572 @@
+use libgm::GMData;
+use crate::App;
+use crate::code::instructions::other::Scope;
+use crate::code::value::Value;
+use crate::event::ASYNC_LOAD_MAP;
+use crate::graphics::view::{View, Views};
+use crate::instance::{Instance, InstanceBuiltins, ALARM_COUNT, NOONE};
+
+/// `argument0` to `argument15`
+const ARGUMENT_VARIABLES: usize = 16;
+
+/// A variable provided by the runner instead of the game itself
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BuiltinVariable {
+    /// `argument0` … `argument15`
+    Argument(usize),
+    /// `argument[i]`
+    ArgumentArray,
+    Global(GlobalVariable),
+    View(ViewVariable),
+    Instance(InstanceVariable),
+}
+
+/// Built-in variables which are the same no matter which instance reads them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GlobalVariable {
+    ArgumentCount,
+    Room,
+    RoomSpeed,
+    RoomWidth,
+    RoomHeight,
+    Fps,
+    CurrentTime,
+    InstanceCount,
+    ViewCurrent,
+    ViewEnabled,
+    KeyboardKey,
+    KeyboardLastkey,
+    KeyboardLastchar,
+    KeyboardString,
+    MouseX,
+    MouseY,
+    MouseButton,
+    MouseLastbutton,
+    AsyncLoad,
+}
+
+/// `view_*` arrays, indexed by view
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ViewVariable {
+    Visible,
+    XView,
+    YView,
+    WView,
+    HView,
+    XPort,
+    YPort,
+    WPort,
+    HPort,
+    HBorder,
+    VBorder,
+    HSpeed,
+    VSpeed,
+    Object,
+    Camera,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InstanceVariable {
+    Id,
+    ObjectIndex,
+    BboxLeft,
+    BboxTop,
+    BboxRight,
+    BboxBottom,
+    X,
+    Y,
+    XPrevious,
+    YPrevious,
+    XStart,
+    YStart,
+    HSpeed,
+    VSpeed,
+    Speed,
+    Direction,
+    Friction,
+    Gravity,
+    GravityDirection,
+    SpriteIndex,
+    ImageIndex,
+    ImageSpeed,
+    ImageXscale,
+    ImageYscale,
+    ImageAngle,
+    ImageAlpha,
+    ImageBlend,
+    MaskIndex,
+    Depth,
+    Visible,
+    Solid,
+    Persistent,
+    Alarm,
+}
+
+const GLOBAL_VARIABLES: &[(&str, GlobalVariable)] = &[
+    ("argument_count", GlobalVariable::ArgumentCount),
+    ("room", GlobalVariable::Room),
+    ("room_speed", GlobalVariable::RoomSpeed),
+    ("room_width", GlobalVariable::RoomWidth),
+    ("room_height", GlobalVariable::RoomHeight),
+    ("fps", GlobalVariable::Fps),
+    ("current_time", GlobalVariable::CurrentTime),
+    ("instance_count", GlobalVariable::InstanceCount),
+    ("view_current", GlobalVariable::ViewCurrent),
+    ("view_enabled", GlobalVariable::ViewEnabled),
+    ("keyboard_key", GlobalVariable::KeyboardKey),
+    ("keyboard_lastkey", GlobalVariable::KeyboardLastkey),
+    ("keyboard_lastchar", GlobalVariable::KeyboardLastchar),
+    ("keyboard_string", GlobalVariable::KeyboardString),
+    ("mouse_x", GlobalVariable::MouseX),
+    ("mouse_y", GlobalVariable::MouseY),
+    ("mouse_button", GlobalVariable::MouseButton),
+    ("mouse_lastbutton", GlobalVariable::MouseLastbutton),
+    ("async_load", GlobalVariable::AsyncLoad),
+];
+
+const VIEW_VARIABLES: &[(&str, ViewVariable)] = &[
+    ("view_visible", ViewVariable::Visible),
+    ("view_xview", ViewVariable::XView),
+    ("view_yview", ViewVariable::YView),
+    ("view_wview", ViewVariable::WView),
+    ("view_hview", ViewVariable::HView),
+    ("view_xport", ViewVariable::XPort),
+    ("view_yport", ViewVariable::YPort),
+    ("view_wport", ViewVariable::WPort),
+    ("view_hport", ViewVariable::HPort),
+    ("view_hborder", ViewVariable::HBorder),
+    ("view_vborder", ViewVariable::VBorder),
+    ("view_hspeed", ViewVariable::HSpeed),
+    ("view_vspeed", ViewVariable::VSpeed),
+    ("view_object", ViewVariable::Object),
+    ("view_camera", ViewVariable::Camera),
+];
+
+const INSTANCE_VARIABLES: &[(&str, InstanceVariable)] = &[
+    ("id", InstanceVariable::Id),
+    ("object_index", InstanceVariable::ObjectIndex),
+    ("bbox_left", InstanceVariable::BboxLeft),
+    ("bbox_top", InstanceVariable::BboxTop),
+    ("bbox_right", InstanceVariable::BboxRight),
+    ("bbox_bottom", InstanceVariable::BboxBottom),
+    ("x", InstanceVariable::X),
+    ("y", InstanceVariable::Y),
+    ("xprevious", InstanceVariable::XPrevious),
+    ("yprevious", InstanceVariable::YPrevious),
+    ("xstart", InstanceVariable::XStart),
+    ("ystart", InstanceVariable::YStart),
+    ("hspeed", InstanceVariable::HSpeed),
+    ("vspeed", InstanceVariable::VSpeed),
+    ("speed", InstanceVariable::Speed),
+    ("direction", InstanceVariable::Direction),
+    ("friction", InstanceVariable::Friction),
+    ("gravity", InstanceVariable::Gravity),
+    ("gravity_direction", InstanceVariable::GravityDirection),
+    ("sprite_index", InstanceVariable::SpriteIndex),
+    ("image_index", InstanceVariable::ImageIndex),
+    ("image_speed", InstanceVariable::ImageSpeed),
+    ("image_xscale", InstanceVariable::ImageXscale),
+    ("image_yscale", InstanceVariable::ImageYscale),
+    ("image_angle", InstanceVariable::ImageAngle),
+    ("image_alpha", InstanceVariable::ImageAlpha),
+    ("image_blend", InstanceVariable::ImageBlend),
+    ("mask_index", InstanceVariable::MaskIndex),
+    ("depth", InstanceVariable::Depth),
+    ("visible", InstanceVariable::Visible),
+    ("solid", InstanceVariable::Solid),
+    ("persistent", InstanceVariable::Persistent),
+    ("alarm", InstanceVariable::Alarm),
+];
+
+fn lookup<T: Copy>(table: &[(&str, T)], name: &str) -> Option<T> {
+    table.iter().find(|(variable, _)| *variable == name).map(|(_, variable)| *variable)
+}
+
+impl BuiltinVariable {
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name == "argument" {
+            return Some(BuiltinVariable::ArgumentArray)
+        }
+        if let Some(Ok(number)) = name.strip_prefix("argument").map(str::parse::<usize>) {
+            return (number < ARGUMENT_VARIABLES).then_some(BuiltinVariable::Argument(number))
+        }
+        lookup(GLOBAL_VARIABLES, name).map(BuiltinVariable::Global)
+            .or_else(|| lookup(VIEW_VARIABLES, name).map(BuiltinVariable::View))
+            .or_else(|| lookup(INSTANCE_VARIABLES, name).map(BuiltinVariable::Instance))
+    }
+
+    /// Built-in variables belong to instances and the runner, so globals, locals and struct variables
+    /// of the same name are ordinary variables. Code running in a struct reaches the runner's variables
+    /// through the built-in scope.
+    pub fn applies_to(self, scope: &Scope, builtin_scope: bool) -> bool {
+        match scope {
+            Scope::Instance(_) | Scope::Object(_) => true,
+            Scope::Struct(_) => builtin_scope && !matches!(self, BuiltinVariable::Instance(_)),
+            Scope::Global | Scope::Local => false,
+        }
+    }
+}
+
+impl InstanceVariable {
+    /// Whether the variable moves or changes the instance's bounding box
+    fn moves_mask(self) -> bool {
+        matches!(self, InstanceVariable::X | InstanceVariable::Y | InstanceVariable::SpriteIndex | InstanceVariable::MaskIndex
+            | InstanceVariable::ImageXscale | InstanceVariable::ImageYscale | InstanceVariable::ImageAngle)
+    }
+}
+
+/// The built-in variable every variable of the VARI chunk refers to, if any; indexed by variable index
+pub fn resolve_builtin_variables(data: &GMData) -> Result<Vec<Option<BuiltinVariable>>, String> {
+    let strings: &Vec<String> = &data.strings.strings_by_index;
+    data.variables.variables.iter()
+        .map(|variable| Ok(BuiltinVariable::from_name(variable.name.resolve(strings)?)))
+        .collect()
+}
+
+
+/// Index of `argument[i]`
+fn argument_index(index: &Value) -> Result<usize, String> {
+    let index: i64 = index.to_int64().map_err(|e| format!("{e} for index of argument array"))?;
+    usize::try_from(index).map_err(|_| format!("Negative argument index {index}"))
+}
+
+fn alarm_index(index: Option<&Value>) -> Result<usize, String> {
+    let index: i64 = match index {
+        Some(index) => index.to_int64().map_err(|e| format!("{e} for alarm index"))?,
+        None => 0,
+    };
+    usize::try_from(index).ok().filter(|i| *i < ALARM_COUNT)
+        .ok_or_else(|| format!("Alarm index {index} out of range (there are {ALARM_COUNT} alarms)"))
+}
+
+fn read_only() -> String {
+    "It is read-only".to_string()
+}
+
+fn set_instance_builtin(builtins: &mut InstanceBuiltins, variable: InstanceVariable, index: Option<&Value>, value: &Value) -> Result<(), String> {
+    match variable {
+        InstanceVariable::Id | InstanceVariable::ObjectIndex | InstanceVariable::BboxLeft
+        | InstanceVariable::BboxTop | InstanceVariable::BboxRight | InstanceVariable::BboxBottom => return Err(read_only()),
+        InstanceVariable::X => builtins.x = value.to_real()?,
+        InstanceVariable::Y => builtins.y = value.to_real()?,
+        InstanceVariable::XPrevious => builtins.xprevious = value.to_real()?,
+        InstanceVariable::YPrevious => builtins.yprevious = value.to_real()?,
+        InstanceVariable::XStart => builtins.xstart = value.to_real()?,
+        InstanceVariable::YStart => builtins.ystart = value.to_real()?,
+        InstanceVariable::HSpeed => builtins.set_hspeed(value.to_real()?),
+        InstanceVariable::VSpeed => builtins.set_vspeed(value.to_real()?),
+        InstanceVariable::Speed => builtins.set_speed(value.to_real()?),
+        InstanceVariable::Direction => builtins.set_direction(value.to_real()?),
+        InstanceVariable::Friction => builtins.friction = value.to_real()?,
+        InstanceVariable::Gravity => builtins.gravity = value.to_real()?,
+        InstanceVariable::GravityDirection => builtins.gravity_direction = value.to_real()?,
+        InstanceVariable::SpriteIndex => builtins.sprite_index = value.to_int32()?,
+        InstanceVariable::ImageIndex => builtins.image_index = value.to_real()?,
+        InstanceVariable::ImageSpeed => builtins.image_speed = value.to_real()?,
+        InstanceVariable::ImageXscale => builtins.image_xscale = value.to_real()?,
+        InstanceVariable::ImageYscale => builtins.image_yscale = value.to_real()?,
+        InstanceVariable::ImageAngle => builtins.image_angle = value.to_real()?,
+        InstanceVariable::ImageAlpha => builtins.image_alpha = value.to_real()?,
+        InstanceVariable::ImageBlend => builtins.image_blend = value.to_int32()? as u32,
+        InstanceVariable::MaskIndex => builtins.mask_index = value.to_int32()?,
+        InstanceVariable::Depth => builtins.depth = value.to_real()?,
+        InstanceVariable::Visible => builtins.visible = value.to_bool()?,
+        InstanceVariable::Solid => builtins.solid = value.to_bool()?,
+        InstanceVariable::Persistent => builtins.persistent = value.to_bool()?,
+        InstanceVariable::Alarm => builtins.alarm[alarm_index(index)?] = value.to_int32()?,
+    }
+    Ok(())
+}
+
+/// Index of `view_xview[i]` and the like; 0 without an index
+fn view_index(index: Option<&Value>) -> Result<i64, String> {
+    match index {
+        Some(index) => index.to_int64().map_err(|e| format!("{e} for view index")),
+        None => Ok(0),
+    }
+}
+
+/// `view_camera[i]` is just `i` since every view has its own camera
+fn get_view_variable(views: &Views, variable: ViewVariable, index: Option<&Value>) -> Result<Value, String> {
+    let view_index: i64 = view_index(index)?;
+    let view: &View = views.get(view_index)?;
+    Ok(match variable {
+        ViewVariable::Visible => Value::Bool(view.visible),
+        ViewVariable::XView => Value::Real(view.x),
+        ViewVariable::YView => Value::Real(view.y),
+        ViewVariable::WView => Value::Real(view.width),
+        ViewVariable::HView => Value::Real(view.height),
+        ViewVariable::XPort => Value::Real(view.port_x),
+        ViewVariable::YPort => Value::Real(view.port_y),
+        ViewVariable::WPort => Value::Real(view.port_width),
+        ViewVariable::HPort => Value::Real(view.port_height),
+        ViewVariable::HBorder => Value::Real(view.border_x),
+        ViewVariable::VBorder => Value::Real(view.border_y),
+        ViewVariable::HSpeed => Value::Real(view.speed_x),
+        ViewVariable::VSpeed => Value::Real(view.speed_y),
+        ViewVariable::Object => Value::Real(view.target.map_or(NOONE as f64, |target| target as f64)),
+        ViewVariable::Camera => Value::Real(view_index as f64),
+    })
+}
+
+fn set_view_variable(views: &mut Views, variable: ViewVariable, index: Option<&Value>, value: &Value) -> Result<(), String> {
+    let view: &mut View = views.get_mut(view_index(index)?)?;
+    match variable {
+        ViewVariable::Visible => view.visible = value.to_bool()?,
+        ViewVariable::XView => view.x = value.to_real()?,
+        ViewVariable::YView => view.y = value.to_real()?,
+        ViewVariable::WView => view.width = value.to_real()?,
+        ViewVariable::HView => view.height = value.to_real()?,
+        ViewVariable::XPort => view.port_x = value.to_real()?,
+        ViewVariable::YPort => view.port_y = value.to_real()?,
+        ViewVariable::WPort => view.port_width = value.to_real()?,
+        ViewVariable::HPort => view.port_height = value.to_real()?,
+        ViewVariable::HBorder => view.border_x = value.to_real()?,
+        ViewVariable::VBorder => view.border_y = value.to_real()?,
+        ViewVariable::HSpeed => view.speed_x = value.to_real()?,
+        ViewVariable::VSpeed => view.speed_y = value.to_real()?,
+        ViewVariable::Object => view.target = usize::try_from(value.to_int64()?).ok(),
+        ViewVariable::Camera => return Err(read_only()),
+    }
+    Ok(())
+}
+
+/// Variables provided by the runner instead of the game itself.
+impl App {
+    /// The built-in variable a variable refers to when accessed through `scope`, if any
+    fn builtin_variable(&self, variable_index: usize, scope: &Scope, builtin_scope: bool) -> Option<BuiltinVariable> {
+        self.builtin_variables.get(variable_index).copied().flatten()
+            .filter(|variable| variable.applies_to(scope, builtin_scope))
+    }
+
+    /// Returns `None` if the variable is read like any other variable,
+    /// which includes `argument` without an index and `x` of an object without instances.
+    pub fn read_builtin_variable(&self, variable_index: usize, scope: &Scope, index: Option<&Value>, builtin_scope: bool) -> Result<Option<Value>, String> {
+        let Some(variable) = self.builtin_variable(variable_index, scope, builtin_scope) else { return Ok(None) };
+        Ok(Some(match variable {
+            BuiltinVariable::Argument(argument) => self.read_argument(argument)?,
+            BuiltinVariable::ArgumentArray => match index {
+                Some(index) => self.read_argument(argument_index(index)?)?,
+                None => return Ok(None),
+            },
+            BuiltinVariable::Global(variable) => self.read_global_builtin(variable)?,
+            BuiltinVariable::View(variable) => get_view_variable(&self.views, variable, index)?,
+            BuiltinVariable::Instance(variable) => {
+                let id: usize = match scope {
+                    Scope::Instance(id) => *id,
+                    Scope::Object(object_index) => match self.instances.ids_of_object(*object_index).first() {
+                        Some(id) => *id,
+                        None => return Ok(None),
+                    },
+                    Scope::Struct(_) | Scope::Global | Scope::Local => return Ok(None),
+                };
+                self.read_instance_builtin(id, variable, index)?
+            }
+        }))
+    }
+
+    /// Returns whether the variable was a built-in one and has been written.
+    pub fn write_builtin_variable(&mut self, variable_index: usize, scope: &Scope, index: Option<&Value>, value: Value, builtin_scope: bool) -> Result<bool, String> {
+        let Some(variable) = self.builtin_variable(variable_index, scope, builtin_scope) else { return Ok(false) };
+        self.write_builtin(variable, scope, index, value)
+            .map_err(|e| format!("Could not assign to built-in variable {}: {e}", self.variable_name(variable_index)))
+    }
+
+    fn write_builtin(&mut self, variable: BuiltinVariable, scope: &Scope, index: Option<&Value>, value: Value) -> Result<bool, String> {
+        match variable {
+            BuiltinVariable::Argument(argument) => self.write_argument(argument, value)?,
+            BuiltinVariable::ArgumentArray => match index {
+                Some(index) => self.write_argument(argument_index(index)?, value)?,
+                None => return Ok(false),
+            },
+            BuiltinVariable::Global(variable) => self.write_global_builtin(variable, &value)?,
+            BuiltinVariable::View(variable) => set_view_variable(&mut self.views, variable, index, &value)?,
+            BuiltinVariable::Instance(variable) => {
+                let ids: Vec<usize> = match scope {
+                    Scope::Instance(id) => vec![*id],
+                    Scope::Object(object_index) => self.instances.ids_of_object(*object_index),
+                    Scope::Struct(_) | Scope::Global | Scope::Local => return Ok(false),
+                };
+                // without instances there is nothing to assign to, but a built-in must not end up as an ordinary variable either
+                for id in ids {
+                    set_instance_builtin(&mut self.instances.get_mut(id)?.builtins, variable, index, &value)?;
+                    if variable.moves_mask() {
+                        self.mark_moved(id);
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    fn read_argument(&self, argument: usize) -> Result<Value, String> {
+        let arguments: &Vec<Value> = &self.call_stack.current()?.arguments;
+        Ok(arguments.get(argument).cloned().unwrap_or(Value::Undefined))
+    }
+
+    fn write_argument(&mut self, argument: usize, value: Value) -> Result<(), String> {
+        let arguments: &mut Vec<Value> = &mut self.call_stack.current_mut()?.arguments;
+        if arguments.len() <= argument {
+            arguments.resize(argument + 1, Value::Undefined);
+        }
+        arguments[argument] = value;
+        Ok(())
+    }
+
+    fn read_instance_builtin(&self, id: usize, variable: InstanceVariable, index: Option<&Value>) -> Result<Value, String> {
+        let instance: &Instance = self.instances.get(id)?;
+        let builtins: &InstanceBuiltins = &instance.builtins;
+        Ok(match variable {
+            InstanceVariable::Id => Value::Real(id as f64),
+            InstanceVariable::ObjectIndex => Value::Real(instance.object_index as f64),
+            InstanceVariable::BboxLeft | InstanceVariable::BboxTop | InstanceVariable::BboxRight
+            | InstanceVariable::BboxBottom => Value::Real(self.bounding_box_variable(id, variable)?),
+            InstanceVariable::X => Value::Real(builtins.x),
+            InstanceVariable::Y => Value::Real(builtins.y),
+            InstanceVariable::XPrevious => Value::Real(builtins.xprevious),
+            InstanceVariable::YPrevious => Value::Real(builtins.yprevious),
+            InstanceVariable::XStart => Value::Real(builtins.xstart),
+            InstanceVariable::YStart => Value::Real(builtins.ystart),
+            InstanceVariable::HSpeed => Value::Real(builtins.hspeed()),
+            InstanceVariable::VSpeed => Value::Real(builtins.vspeed()),
+            InstanceVariable::Speed => Value::Real(builtins.speed()),
+            InstanceVariable::Direction => Value::Real(builtins.direction()),
+            InstanceVariable::Friction => Value::Real(builtins.friction),
+            InstanceVariable::Gravity => Value::Real(builtins.gravity),
+            InstanceVariable::GravityDirection => Value::Real(builtins.gravity_direction),
+            InstanceVariable::SpriteIndex => Value::Real(f64::from(builtins.sprite_index)),
+            InstanceVariable::ImageIndex => Value::Real(builtins.image_index),
+            InstanceVariable::ImageSpeed => Value::Real(builtins.image_speed),
+            InstanceVariable::ImageXscale => Value::Real(builtins.image_xscale),
+            InstanceVariable::ImageYscale => Value::Real(builtins.image_yscale),
+            InstanceVariable::ImageAngle => Value::Real(builtins.image_angle),
+            InstanceVariable::ImageAlpha => Value::Real(builtins.image_alpha),
+            InstanceVariable::ImageBlend => Value::Real(f64::from(builtins.image_blend)),
+            InstanceVariable::MaskIndex => Value::Real(f64::from(builtins.mask_index)),
+            InstanceVariable::Depth => Value::Real(builtins.depth),
+            InstanceVariable::Visible => Value::Bool(builtins.visible),
+            InstanceVariable::Solid => Value::Bool(builtins.solid),
+            InstanceVariable::Persistent => Value::Bool(builtins.persistent),
+            InstanceVariable::Alarm => Value::Real(f64::from(builtins.alarm[alarm_index(index)?])),
+        })
+    }
+
+    /// `bbox_*`: the mask's bounding box with inclusive right and bottom edges; without a mask, the position
+    fn bounding_box_variable(&self, id: usize, variable: InstanceVariable) -> Result<f64, String> {
+        let Some(collider) = self.collider(id)? else {
+            let builtins: &InstanceBuiltins = &self.instances.get(id)?.builtins;
+            return Ok(if matches!(variable, InstanceVariable::BboxLeft | InstanceVariable::BboxRight) { builtins.x } else { builtins.y })
+        };
+        Ok(match variable {
+            InstanceVariable::BboxLeft => collider.bbox.left.round(),
+            InstanceVariable::BboxTop => collider.bbox.top.round(),
+            InstanceVariable::BboxRight => collider.bbox.right.round() - 1.0,
+            _ => collider.bbox.bottom.round() - 1.0,
+        })
+    }
+
+    fn read_global_builtin(&self, variable: GlobalVariable) -> Result<Value, String> {
+        Ok(match variable {
+            GlobalVariable::ArgumentCount => Value::Real(self.call_stack.current()?.arguments.len() as f64),
+            GlobalVariable::Room => Value::Real(self.room_index as f64),
+            GlobalVariable::RoomSpeed => Value::Real(self.game_speed()),
+            GlobalVariable::RoomWidth => Value::Real(f64::from(self.current_room.width)),
+            GlobalVariable::RoomHeight => Value::Real(f64::from(self.current_room.height)),
+            GlobalVariable::Fps => Value::Real(self.fps),
+            GlobalVariable::CurrentTime => Value::Real(self.start_time.elapsed().as_millis() as f64),
+            GlobalVariable::InstanceCount => Value::Real(self.instances.living_ids().len() as f64),
+            GlobalVariable::ViewCurrent => Value::Real(self.view_current as f64),
+            GlobalVariable::ViewEnabled => Value::Bool(self.views.enabled),
+            GlobalVariable::KeyboardKey => Value::Real(f64::from(self.keyboard.current_key)),
+            GlobalVariable::KeyboardLastkey => Value::Real(f64::from(self.keyboard.last_key)),
+            GlobalVariable::KeyboardLastchar => Value::string(&self.keyboard.last_char),
+            GlobalVariable::KeyboardString => Value::string(&self.keyboard.string),
+            GlobalVariable::MouseX => Value::Real(self.mouse_room_position().0),
+            GlobalVariable::MouseY => Value::Real(self.mouse_room_position().1),
+            GlobalVariable::MouseButton => Value::Real(f64::from(self.mouse.current_button)),
+            GlobalVariable::MouseLastbutton => Value::Real(f64::from(self.mouse.last_button)),
+            GlobalVariable::AsyncLoad => Value::Real(if self.async_load.is_some() { ASYNC_LOAD_MAP as f64 } else { -1.0 }),
+        })
+    }
+
+    /// The game may change the room or overwrite the input variables, e.g. to clear `keyboard_string`
+    fn write_global_builtin(&mut self, variable: GlobalVariable, value: &Value) -> Result<(), String> {
+        let code = || value.to_int64().map(|code| u32::try_from(code).unwrap_or(0));
+        let string = || value.as_str().map(str::to_string);
+        let size = || value.to_int64().map(|size| u32::try_from(size).unwrap_or(0));
+        match variable {
+            GlobalVariable::Room => {
+                let room_index: i64 = value.to_int64()?;
+                let room_index: usize = usize::try_from(room_index).map_err(|_| format!("Invalid room {room_index}"))?;
+                self.goto_room(room_index)?;
+            }
+            GlobalVariable::RoomSpeed => self.clock.set_speed(value.to_real()?),
+            GlobalVariable::RoomWidth => self.current_room.width = size()?,
+            GlobalVariable::RoomHeight => self.current_room.height = size()?,
+            GlobalVariable::ViewEnabled => self.views.enabled = value.to_bool()?,
+            GlobalVariable::KeyboardKey => self.keyboard.current_key = code()?,
+            GlobalVariable::KeyboardLastkey => self.keyboard.last_key = code()?,
+            GlobalVariable::KeyboardLastchar => self.keyboard.last_char = string()?,
+            GlobalVariable::KeyboardString => self.keyboard.string = string()?,
+            GlobalVariable::MouseButton => self.mouse.current_button = code()?,
+            GlobalVariable::MouseLastbutton => self.mouse.last_button = code()?,
+            _ => return Err(read_only()),
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::structs::Struct;
+
+    #[test]
+    fn resolves_runner_variables() {
+        assert_eq!(BuiltinVariable::from_name("argument"), Some(BuiltinVariable::ArgumentArray));
+        assert_eq!(BuiltinVariable::from_name("argument3"), Some(BuiltinVariable::Argument(3)));
+        assert_eq!(BuiltinVariable::from_name("argument16"), None);
+        assert_eq!(BuiltinVariable::from_name("argument_count"), Some(BuiltinVariable::Global(GlobalVariable::ArgumentCount)));
+        assert_eq!(BuiltinVariable::from_name("fps"), Some(BuiltinVariable::Global(GlobalVariable::Fps)));
+        assert_eq!(BuiltinVariable::from_name("view_enabled"), Some(BuiltinVariable::Global(GlobalVariable::ViewEnabled)));
+        assert_eq!(BuiltinVariable::from_name("view_xview"), Some(BuiltinVariable::View(ViewVariable::XView)));
+        // other names starting with `view_` are ordinary variables
+        assert_eq!(BuiltinVariable::from_name("view_target"), None);
+        assert_eq!(BuiltinVariable::from_name("player_speed"), None);
+    }
+
+    #[test]
+    fn runner_variables_do_not_shadow_globals_and_struct_variables() {
+        let fps: BuiltinVariable = BuiltinVariable::Global(GlobalVariable::Fps);
+        let structure: Scope = Scope::Struct(Struct::new_ref(None));
+        assert!(fps.applies_to(&Scope::Instance(100000), false));
+        assert!(fps.applies_to(&Scope::Object(3), false));
+        assert!(fps.applies_to(&structure, true));
+        assert!(!fps.applies_to(&structure, false));
+        assert!(!fps.applies_to(&Scope::Global, false));
+        assert!(!fps.applies_to(&Scope::Local, false));
+    }
+
+    #[test]
+    fn resolves_instance_variables() {
+        assert_eq!(BuiltinVariable::from_name("x"), Some(BuiltinVariable::Instance(InstanceVariable::X)));
+        assert_eq!(BuiltinVariable::from_name("bbox_right"), Some(BuiltinVariable::Instance(InstanceVariable::BboxRight)));
+        assert_eq!(BuiltinVariable::from_name("alarm"), Some(BuiltinVariable::Instance(InstanceVariable::Alarm)));
+        assert_eq!(BuiltinVariable::from_name("xspeed"), None);
+    }
+
+    #[test]
+    fn instance_variables_only_apply_to_instances() {
+        let x: BuiltinVariable = BuiltinVariable::Instance(InstanceVariable::X);
+        let structure: Scope = Scope::Struct(Struct::new_ref(None));
+        assert!(x.applies_to(&Scope::Instance(100000), false));
+        assert!(x.applies_to(&Scope::Instance(100000), true));
+        assert!(x.applies_to(&Scope::Object(3), false));
+        // `x` of a struct or `global.x` is the game's own variable
+        assert!(!x.applies_to(&structure, false));
+        assert!(!x.applies_to(&structure, true));
+        assert!(!x.applies_to(&Scope::Global, false));
+        assert!(!x.applies_to(&Scope::Local, false));
+    }
+}
@@ -0,0 +1,55 @@
+use crate::App;
+use crate::code::array::{Array, ArrayRef};
+use crate::code::builtins::{arg_int, Arity, Builtins};
+use crate::code::value::Value;
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("is_array", Arity::Exact(1), is_array);
+    builtins.register("array_create", Arity::Range(1, 2), array_create);
+    builtins.register("array_length", Arity::Exact(1), array_length);
+    builtins.register("array_length_1d", Arity::Exact(1), array_length);
+    builtins.register("array_height_2d", Arity::Exact(1), array_height_2d);
+    builtins.register("array_length_2d", Arity::Exact(2), array_length_2d);
+}
+
+fn arg_array(arguments: &[Value], index: usize) -> Result<Option<&ArrayRef>, String> {
+    match arguments.get(index) {
+        Some(Value::Array(array)) => Ok(Some(array)),
+        Some(_) => Ok(None),
+        None => Err(format!("Missing argument {index} (got {} arguments)", arguments.len())),
+    }
+}
+
+fn is_array(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(matches!(arguments[0], Value::Array(_))))
+}
+
+fn array_create(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let size: i64 = arg_int(arguments, 0)?;
+    if size < 0 {
+        return Err(format!("Cannot create array with negative size {size}"))
+    }
+    let value: Value = arguments.get(1).cloned().unwrap_or(Value::Real(0.0));
+    let array = Array::from_items(vec![value; size as usize]);
+    Ok(Value::Array(Array::new_ref(array)))
+}
+
+/// Non-arrays have a length of 0
+fn array_length(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let length: usize = arg_array(arguments, 0)?.map_or(0, |array| array.borrow().len());
+    Ok(Value::Real(length as f64))
+}
+
+fn array_height_2d(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let height: usize = arg_array(arguments, 0)?.map_or(0, |array| array.borrow().rows.len());
+    Ok(Value::Real(height as f64))
+}
+
+fn array_length_2d(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let row: i64 = arg_int(arguments, 1)?;
+    let length: usize = arg_array(arguments, 0)?
+        .and_then(|array| usize::try_from(row).ok().and_then(|row| array.borrow().rows.get(row).map(|i| i.len())))
+        .unwrap_or(0);
+    Ok(Value::Real(length as f64))
+}
+
@@ -0,0 +1,115 @@
+use crate::App;
+use crate::code::builtins::{arg_real, argument, Arity, Builtins};
+use crate::code::builtins::instance::targets;
+use crate::code::value::Value;
+use crate::collision::Shape;
+use crate::instance::{InstanceRef, ALL, NOONE};
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("place_meeting", Arity::Exact(3), place_meeting);
+    builtins.register("place_free", Arity::Exact(2), place_free);
+    builtins.register("place_empty", Arity::Range(2, 3), place_empty);
+    builtins.register("instance_place", Arity::Exact(3), instance_place);
+    builtins.register("position_meeting", Arity::Exact(3), position_meeting);
+    builtins.register("instance_position", Arity::Exact(3), instance_position);
+    builtins.register("collision_point", Arity::Exact(5), collision_point);
+    builtins.register("collision_rectangle", Arity::Exact(7), collision_rectangle);
+    builtins.register("collision_circle", Arity::Exact(6), collision_circle);
+    builtins.register("collision_line", Arity::Exact(7), collision_line);
+}
+
+fn self_id(app: &App) -> Result<usize, String> {
+    match app.call_stack.current()?.self_instance {
+        InstanceRef::Instance(id) => Ok(id),
+        InstanceRef::Struct(_) => Err("Collision functions cannot be called on a struct".to_string()),
+    }
+}
+
+fn arg_bool(arguments: &[Value], index: usize) -> Result<bool, String> {
+    argument(arguments, index)?.to_bool().map_err(|e| format!("{e} for argument {index}"))
+}
+
+/// The first instance found, or `noone`
+fn first_or_noone(ids: &[usize]) -> Value {
+    Value::Real(ids.first().map_or(NOONE as f64, |id| *id as f64))
+}
+
+/// Instances of the target colliding with `self` if it stood at (x, y)
+fn place_collisions(app: &App, arguments: &[Value], target: &Value) -> Result<Vec<usize>, String> {
+    let id: usize = self_id(app)?;
+    app.collisions_at(id, arg_real(arguments, 0)?, arg_real(arguments, 1)?, &targets(app, target)?)
+}
+
+fn place_meeting(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(!place_collisions(app, arguments, argument(arguments, 2)?)?.is_empty()))
+}
+
+/// No solid instance at the position
+fn place_free(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let collisions: Vec<usize> = place_collisions(app, arguments, &Value::Real(ALL as f64))?;
+    let solid: bool = collisions.iter()
+        .any(|id| app.instances.get(*id).is_ok_and(|instance| instance.builtins.solid));
+    Ok(Value::Bool(!solid))
+}
+
+/// No instance (of the given object) at the position
+fn place_empty(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let target: Value = arguments.get(2).cloned().unwrap_or(Value::Real(ALL as f64));
+    Ok(Value::Bool(place_collisions(app, arguments, &target)?.is_empty()))
+}
+
+fn instance_place(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(first_or_noone(&place_collisions(app, arguments, argument(arguments, 2)?)?))
+}
+
+/// Instances of the target whose masks contain the point
+fn point_collisions(app: &App, arguments: &[Value]) -> Result<Vec<usize>, String> {
+    let shape: Shape = Shape::Point(arg_real(arguments, 0)?, arg_real(arguments, 1)?);
+    app.shape_collisions(&shape, &targets(app, argument(arguments, 2)?)?, true)
+}
+
+fn position_meeting(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(!point_collisions(app, arguments)?.is_empty()))
+}
+
+fn instance_position(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(first_or_noone(&point_collisions(app, arguments)?))
+}
+
+/// The `collision_*` functions end with `obj, prec, notme`, starting at argument `index`
+fn shape_query(app: &App, shape: Shape, arguments: &[Value], index: usize) -> Result<Value, String> {
+    let among: Vec<usize> = targets(app, argument(arguments, index)?)?;
+    let precise: bool = arg_bool(arguments, index + 1)?;
+    let mut collisions: Vec<usize> = app.shape_collisions(&shape, &among, precise)?;
+    if arg_bool(arguments, index + 2)? {
+        if let InstanceRef::Instance(id) = app.call_stack.current()?.self_instance {
+            collisions.retain(|other| *other != id);
+        }
+    }
+    Ok(first_or_noone(&collisions))
+}
+
+fn collision_point(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let shape: Shape = Shape::Point(arg_real(arguments, 0)?, arg_real(arguments, 1)?);
+    shape_query(app, shape, arguments, 2)
+}
+
+fn collision_rectangle(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let shape: Shape = Shape::rectangle(arg_real(arguments, 0)?, arg_real(arguments, 1)?, arg_real(arguments, 2)?, arg_real(arguments, 3)?);
+    shape_query(app, shape, arguments, 4)
+}
+
+fn collision_circle(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let shape: Shape = Shape::Circle { x: arg_real(arguments, 0)?, y: arg_real(arguments, 1)?, radius: arg_real(arguments, 2)? };
+    shape_query(app, shape, arguments, 3)
+}
+
+fn collision_line(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let shape: Shape = Shape::Line {
+        x1: arg_real(arguments, 0)?,
+        y1: arg_real(arguments, 1)?,
+        x2: arg_real(arguments, 2)?,
+        y2: arg_real(arguments, 3)?,
+    };
+    shape_query(app, shape, arguments, 4)
+}
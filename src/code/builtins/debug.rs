@@ -0,0 +1,14 @@
+use log::info;
+use crate::App;
+use crate::code::builtins::{Arity, Builtins};
+use crate::code::value::Value;
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("show_debug_message", Arity::Exact(1), show_debug_message);
+}
+
+fn show_debug_message(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    info!("[Game] {}", app.value_to_string(&arguments[0]));
+    Ok(Value::Undefined)
+}
+
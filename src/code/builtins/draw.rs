@@ -0,0 +1,119 @@
+use crate::App;
+use crate::code::builtins::{arg_int, arg_real, Arity, Builtins};
+use crate::code::value::Value;
+use crate::graphics::canvas::DrawParams;
+use crate::graphics::sprite::instance_params;
+use crate::instance::{InstanceBuiltins, InstanceRef};
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("draw_self", Arity::Exact(0), draw_self);
+    builtins.register("draw_sprite", Arity::Exact(4), draw_sprite);
+    builtins.register("draw_sprite_ext", Arity::Exact(9), draw_sprite_ext);
+    builtins.register("draw_sprite_part", Arity::Exact(8), draw_sprite_part);
+    builtins.register("draw_sprite_part_ext", Arity::Exact(12), draw_sprite_part_ext);
+    builtins.register("sprite_get_number", Arity::Exact(1), sprite_get_number);
+    builtins.register("sprite_get_width", Arity::Exact(1), sprite_get_width);
+    builtins.register("sprite_get_height", Arity::Exact(1), sprite_get_height);
+    builtins.register("sprite_get_xoffset", Arity::Exact(1), sprite_get_xoffset);
+    builtins.register("sprite_get_yoffset", Arity::Exact(1), sprite_get_yoffset);
+}
+
+fn self_builtins(app: &App) -> Result<InstanceBuiltins, String> {
+    match &app.call_stack.current()?.self_instance {
+        InstanceRef::Instance(id) => Ok(app.instances.get(*id)?.builtins.clone()),
+        InstanceRef::Struct(_) => Err("Cannot draw a struct".to_string()),
+    }
+}
+
+/// A sub-image of -1 means the current image index of `self`
+fn arg_image_index(app: &App, arguments: &[Value], index: usize) -> Result<f64, String> {
+    let image_index: f64 = arg_real(arguments, index)?;
+    if image_index < 0.0 {
+        return Ok(self_builtins(app)?.image_index)
+    }
+    Ok(image_index)
+}
+
+fn draw_self(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    let builtins: InstanceBuiltins = self_builtins(app)?;
+    app.draw_sprite_with(i64::from(builtins.sprite_index), builtins.image_index, instance_params(&builtins))?;
+    Ok(Value::Undefined)
+}
+
+fn draw_sprite(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let sprite_index: i64 = arg_int(arguments, 0)?;
+    let image_index: f64 = arg_image_index(app, arguments, 1)?;
+    let params: DrawParams = DrawParams::at(arg_real(arguments, 2)?, arg_real(arguments, 3)?);
+    app.draw_sprite_with(sprite_index, image_index, params)?;
+    Ok(Value::Undefined)
+}
+
+fn draw_sprite_ext(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let sprite_index: i64 = arg_int(arguments, 0)?;
+    let image_index: f64 = arg_image_index(app, arguments, 1)?;
+    let params: DrawParams = DrawParams {
+        xscale: arg_real(arguments, 4)?,
+        yscale: arg_real(arguments, 5)?,
+        angle: arg_real(arguments, 6)?,
+        blend: arg_int(arguments, 7)? as u32,
+        alpha: arg_real(arguments, 8)?,
+        ..DrawParams::at(arg_real(arguments, 2)?, arg_real(arguments, 3)?)
+    };
+    app.draw_sprite_with(sprite_index, image_index, params)?;
+    Ok(Value::Undefined)
+}
+
+/// Parts are drawn with their top left corner at (x, y), ignoring the sprite's origin
+fn draw_sprite_part(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let sprite_index: i64 = arg_int(arguments, 0)?;
+    let image_index: f64 = arg_image_index(app, arguments, 1)?;
+    let left: f64 = arg_real(arguments, 2)?;
+    let top: f64 = arg_real(arguments, 3)?;
+    let params: DrawParams = DrawParams {
+        origin_x: left,
+        origin_y: top,
+        region: Some((left, top, arg_real(arguments, 4)?, arg_real(arguments, 5)?)),
+        ..DrawParams::at(arg_real(arguments, 6)?, arg_real(arguments, 7)?)
+    };
+    app.draw_sprite_with(sprite_index, image_index, params)?;
+    Ok(Value::Undefined)
+}
+
+fn draw_sprite_part_ext(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let sprite_index: i64 = arg_int(arguments, 0)?;
+    let image_index: f64 = arg_image_index(app, arguments, 1)?;
+    let left: f64 = arg_real(arguments, 2)?;
+    let top: f64 = arg_real(arguments, 3)?;
+    let params: DrawParams = DrawParams {
+        origin_x: left,
+        origin_y: top,
+        xscale: arg_real(arguments, 8)?,
+        yscale: arg_real(arguments, 9)?,
+        blend: arg_int(arguments, 10)? as u32,
+        alpha: arg_real(arguments, 11)?,
+        region: Some((left, top, arg_real(arguments, 4)?, arg_real(arguments, 5)?)),
+        ..DrawParams::at(arg_real(arguments, 6)?, arg_real(arguments, 7)?)
+    };
+    app.draw_sprite_with(sprite_index, image_index, params)?;
+    Ok(Value::Undefined)
+}
+
+fn sprite_get_number(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(app.sprite_frame_count(arg_int(arguments, 0)?)? as f64))
+}
+
+fn sprite_get_width(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(f64::from(app.sprite(arg_int(arguments, 0)?)?.width)))
+}
+
+fn sprite_get_height(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(f64::from(app.sprite(arg_int(arguments, 0)?)?.height)))
+}
+
+fn sprite_get_xoffset(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(f64::from(app.sprite(arg_int(arguments, 0)?)?.origin_x)))
+}
+
+fn sprite_get_yoffset(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(f64::from(app.sprite(arg_int(arguments, 0)?)?.origin_y)))
+}
@@ -0,0 +1,44 @@
+use crate::App;
+use crate::code::builtins::{arg_int, Arity, Builtins};
+use crate::code::value::Value;
+use crate::event::{Event, EV_OTHER, EV_USER0};
+use crate::instance::InstanceRef;
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("event_inherited", Arity::Exact(0), event_inherited);
+    builtins.register("event_perform", Arity::Exact(2), event_perform);
+    builtins.register("event_user", Arity::Exact(1), event_user);
+}
+
+fn event_inherited(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    app.event_inherited()?;
+    Ok(Value::Undefined)
+}
+
+/// Runs an event of `self` right away, like a script call
+fn perform(app: &mut App, event: Event) -> Result<Value, String> {
+    let InstanceRef::Instance(id) = app.call_stack.current()?.self_instance else {
+        return Err("Cannot perform events on a struct".to_string())
+    };
+    app.fire_event(id, event)?;
+    Ok(Value::Undefined)
+}
+
+fn event_perform(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let event_type: i64 = arg_int(arguments, 0)?;
+    let subtype: i64 = arg_int(arguments, 1)?;
+    let event = Event::new(
+        u32::try_from(event_type).map_err(|_| format!("Invalid event type {event_type}"))?,
+        u32::try_from(subtype).map_err(|_| format!("Invalid event number {subtype}"))?,
+    );
+    perform(app, event)
+}
+
+fn event_user(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let number: i64 = arg_int(arguments, 0)?;
+    if !(0..16).contains(&number) {
+        return Err(format!("Invalid user event {number}"))
+    }
+    perform(app, Event::new(EV_OTHER, EV_USER0 + number as u32))
+}
+
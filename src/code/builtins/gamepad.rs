@@ -0,0 +1,166 @@
+use crate::App;
+use crate::code::builtins::{arg_int, arg_real, Arity, Builtins};
+use crate::code::value::Value;
+use crate::input::gamepad::{Gamepad, Gamepads, AXIS_COUNT, BUTTON_COUNT, GAMEPAD_SLOTS, GP_AXISLH, GP_FACE1};
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("gamepad_is_supported", Arity::Exact(0), gamepad_is_supported);
+    builtins.register("gamepad_get_device_count", Arity::Exact(0), gamepad_get_device_count);
+    builtins.register("gamepad_is_connected", Arity::Exact(1), gamepad_is_connected);
+    builtins.register("gamepad_get_description", Arity::Exact(1), gamepad_get_description);
+    builtins.register("gamepad_button_count", Arity::Exact(1), gamepad_button_count);
+    builtins.register("gamepad_button_check", Arity::Exact(2), gamepad_button_check);
+    builtins.register("gamepad_button_check_pressed", Arity::Exact(2), gamepad_button_check_pressed);
+    builtins.register("gamepad_button_check_released", Arity::Exact(2), gamepad_button_check_released);
+    builtins.register("gamepad_button_value", Arity::Exact(2), gamepad_button_value);
+    builtins.register("gamepad_axis_count", Arity::Exact(1), gamepad_axis_count);
+    builtins.register("gamepad_axis_value", Arity::Exact(2), gamepad_axis_value);
+    builtins.register("gamepad_set_axis_deadzone", Arity::Exact(2), gamepad_set_axis_deadzone);
+    builtins.register("gamepad_get_axis_deadzone", Arity::Exact(1), gamepad_get_axis_deadzone);
+    builtins.register("gamepad_set_button_threshold", Arity::Exact(2), gamepad_set_button_threshold);
+    builtins.register("gamepad_get_button_threshold", Arity::Exact(1), gamepad_get_button_threshold);
+}
+
+/// The slot's gamepad; `None` for slots that do not exist, which read like disconnected ones
+fn arg_gamepad<'a>(gamepads: &'a Gamepads, arguments: &[Value], index: usize) -> Result<Option<&'a Gamepad>, String> {
+    Ok(gamepads.get(arg_int(arguments, index)?))
+}
+
+/// Index of a `gp_*` constant among the buttons (or axes, starting at `first`)
+fn arg_input(arguments: &[Value], index: usize, first: u32, count: usize) -> Result<Option<usize>, String> {
+    let constant: i64 = arg_int(arguments, index)?;
+    Ok(usize::try_from(constant - i64::from(first)).ok().filter(|i| *i < count))
+}
+
+fn button_state(gamepads: &Gamepads, arguments: &[Value], state: fn(&Gamepad, usize) -> bool) -> Result<Value, String> {
+    let Some(button) = arg_input(arguments, 1, GP_FACE1, BUTTON_COUNT)? else { return Ok(Value::Bool(false)) };
+    Ok(Value::Bool(arg_gamepad(gamepads, arguments, 0)?.is_some_and(|gamepad| state(gamepad, button))))
+}
+
+fn gamepad_is_supported(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(app.gamepads.is_supported()))
+}
+
+fn gamepad_get_device_count(_: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(GAMEPAD_SLOTS as f64))
+}
+
+fn gamepad_is_connected(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(arg_gamepad(&app.gamepads, arguments, 0)?.is_some_and(|gamepad| gamepad.connected)))
+}
+
+fn gamepad_get_description(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let description: &str = match arg_gamepad(&app.gamepads, arguments, 0)? {
+        Some(gamepad) if gamepad.connected => &gamepad.description,
+        _ => "",
+    };
+    Ok(Value::string(description))
+}
+
+fn gamepad_button_count(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let connected: bool = arg_gamepad(&app.gamepads, arguments, 0)?.is_some_and(|gamepad| gamepad.connected);
+    Ok(Value::Real(if connected { BUTTON_COUNT as f64 } else { 0.0 }))
+}
+
+fn gamepad_button_check(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    button_state(&app.gamepads, arguments, Gamepad::check)
+}
+
+fn gamepad_button_check_pressed(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    button_state(&app.gamepads, arguments, Gamepad::check_pressed)
+}
+
+fn gamepad_button_check_released(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    button_state(&app.gamepads, arguments, Gamepad::check_released)
+}
+
+/// How far the button is pressed, from 0 to 1; only triggers have values in between
+fn gamepad_button_value(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let Some(button) = arg_input(arguments, 1, GP_FACE1, BUTTON_COUNT)? else { return Ok(Value::Real(0.0)) };
+    let value: Option<f64> = arg_gamepad(&app.gamepads, arguments, 0)?
+        .filter(|gamepad| gamepad.connected)
+        .map(|gamepad| gamepad.buttons[button]);
+    Ok(Value::Real(value.unwrap_or(0.0)))
+}
+
+fn gamepad_axis_count(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let connected: bool = arg_gamepad(&app.gamepads, arguments, 0)?.is_some_and(|gamepad| gamepad.connected);
+    Ok(Value::Real(if connected { AXIS_COUNT as f64 } else { 0.0 }))
+}
+
+fn gamepad_axis_value(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    axis_value(&app.gamepads, arguments)
+}
+
+fn axis_value(gamepads: &Gamepads, arguments: &[Value]) -> Result<Value, String> {
+    let Some(axis) = arg_input(arguments, 1, GP_AXISLH, AXIS_COUNT)? else { return Ok(Value::Real(0.0)) };
+    let value: Option<f64> = arg_gamepad(gamepads, arguments, 0)?.map(|gamepad| gamepad.axis_value(axis));
+    Ok(Value::Real(value.unwrap_or(0.0)))
+}
+
+fn gamepad_set_axis_deadzone(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let deadzone: f64 = arg_real(arguments, 1)?;
+    if let Some(gamepad) = app.gamepads.get_mut(arg_int(arguments, 0)?) {
+        gamepad.deadzone = deadzone.clamp(0.0, 1.0);
+    }
+    Ok(Value::Undefined)
+}
+
+fn gamepad_get_axis_deadzone(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(arg_gamepad(&app.gamepads, arguments, 0)?.map_or(0.0, |gamepad| gamepad.deadzone)))
+}
+
+fn gamepad_set_button_threshold(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let threshold: f64 = arg_real(arguments, 1)?;
+    if let Some(gamepad) = app.gamepads.get_mut(arg_int(arguments, 0)?) {
+        gamepad.button_threshold = threshold.clamp(0.0, 1.0);
+    }
+    Ok(Value::Undefined)
+}
+
+fn gamepad_get_button_threshold(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(arg_gamepad(&app.gamepads, arguments, 0)?.map_or(0.0, |gamepad| gamepad.button_threshold)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::gamepad::virtual_pad::VirtualGamepad;
+
+    const GP_FACE2: u32 = GP_FACE1 + 1;
+    const GP_AXISLV: u32 = GP_AXISLH + 1;
+
+    fn button(gamepads: &Gamepads, slot: i64, button: u32, state: fn(&Gamepad, usize) -> bool) -> bool {
+        let arguments: [Value; 2] = [Value::Real(slot as f64), Value::Real(f64::from(button))];
+        button_state(gamepads, &arguments, state).unwrap().to_bool().unwrap()
+    }
+
+    fn axis(gamepads: &Gamepads, slot: i64, axis: u32) -> f64 {
+        let arguments: [Value; 2] = [Value::Real(slot as f64), Value::Real(f64::from(axis))];
+        axis_value(gamepads, &arguments).unwrap().to_real().unwrap()
+    }
+
+    #[test]
+    fn reads_a_scripted_gamepad() {
+        let script: &str = "0 connect\n0 press face2\n0 axis axislv 0.75\n1 release face2";
+        let mut gamepads: Gamepads = Gamepads::new();
+        gamepads.add_backend(Box::new(VirtualGamepad::parse(script).unwrap()));
+
+        gamepads.poll();
+        assert!(button(&gamepads, 0, GP_FACE2, Gamepad::check));
+        assert!(button(&gamepads, 0, GP_FACE2, Gamepad::check_pressed));
+        assert!(!button(&gamepads, 0, GP_FACE1, Gamepad::check));
+        assert_eq!(axis(&gamepads, 0, GP_AXISLV), 0.75);
+        assert_eq!(axis(&gamepads, 0, GP_AXISLH), 0.0);
+        // other slots and constants which are not buttons read as not pressed
+        assert!(!button(&gamepads, 1, GP_FACE2, Gamepad::check));
+        assert!(!button(&gamepads, 99, GP_FACE2, Gamepad::check));
+        assert!(!button(&gamepads, 0, 65, Gamepad::check));
+
+        gamepads.poll();
+        assert!(!button(&gamepads, 0, GP_FACE2, Gamepad::check));
+        assert!(button(&gamepads, 0, GP_FACE2, Gamepad::check_released));
+        assert_eq!(axis(&gamepads, 0, GP_AXISLV), 0.75);
+    }
+}
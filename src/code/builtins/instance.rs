@@ -0,0 +1,107 @@
+use crate::App;
+use crate::code::builtins::{arg_int, arg_real, argument, Arity, Builtins};
+use crate::code::call::CallFrame;
+use crate::code::value::Value;
+use crate::instance::{InstanceRef, NOONE};
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("instance_create", Arity::Exact(3), instance_create);
+    builtins.register("instance_create_depth", Arity::Exact(4), instance_create_depth);
+    builtins.register("instance_create_layer", Arity::Exact(4), instance_create_layer);
+    builtins.register("instance_destroy", Arity::Range(0, 2), instance_destroy);
+    builtins.register("instance_exists", Arity::Exact(1), instance_exists);
+    builtins.register("instance_number", Arity::Exact(1), instance_number);
+    builtins.register("instance_find", Arity::Exact(2), instance_find);
+}
+
+fn arg_object(arguments: &[Value], index: usize) -> Result<usize, String> {
+    let object_index: i64 = arg_int(arguments, index)?;
+    usize::try_from(object_index).map_err(|_| format!("Invalid object index {object_index} for argument {index}"))
+}
+
+/// Instances matched by an instance ID, object index or keyword like `all`
+pub fn targets(app: &App, target: &Value) -> Result<Vec<usize>, String> {
+    let frame: &CallFrame = app.call_stack.current()?;
+    let instances: Vec<InstanceRef> = app.instances.resolve_target(target, &frame.self_instance, &frame.other_instance)?;
+    Ok(instances.into_iter()
+        .filter_map(|instance| match instance {
+            InstanceRef::Instance(id) if app.instances.exists(id) => Some(id),
+            _ => None,
+        })
+        .collect())
+}
+
+/// GMS1: `instance_create(x, y, obj)`
+fn instance_create(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let x: f64 = arg_real(arguments, 0)?;
+    let y: f64 = arg_real(arguments, 1)?;
+    let object_index: usize = arg_object(arguments, 2)?;
+    let id: usize = app.create_instance(x, y, object_index, None)?;
+    Ok(Value::Real(id as f64))
+}
+
+fn instance_create_depth(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let x: f64 = arg_real(arguments, 0)?;
+    let y: f64 = arg_real(arguments, 1)?;
+    let depth: f64 = arg_real(arguments, 2)?;
+    let object_index: usize = arg_object(arguments, 3)?;
+    let id: usize = app.create_instance(x, y, object_index, Some(depth))?;
+    Ok(Value::Real(id as f64))
+}
+
+/// The layer (given by name or ID) only determines the depth
+fn instance_create_layer(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let x: f64 = arg_real(arguments, 0)?;
+    let y: f64 = arg_real(arguments, 1)?;
+    let depth: f64 = match argument(arguments, 2)? {
+        Value::String(name) => *app.room_layers.depths_by_name.get(name.as_ref())
+            .ok_or_else(|| format!("Layer \"{name}\" does not exist in the current room"))?,
+        _ => {
+            let layer_id: i64 = arg_int(arguments, 2)?;
+            *u32::try_from(layer_id).ok().and_then(|i| app.room_layers.depths_by_id.get(&i))
+                .ok_or_else(|| format!("Layer {layer_id} does not exist in the current room"))?
+        }
+    };
+    let object_index: usize = arg_object(arguments, 3)?;
+    let id: usize = app.create_instance(x, y, object_index, Some(depth))?;
+    Ok(Value::Real(id as f64))
+}
+
+/// `instance_destroy()` destroys `self`; `instance_destroy(target, [execute_event])` every matching instance
+fn instance_destroy(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let execute_event: bool = match arguments.get(1) {
+        Some(value) => value.to_bool().map_err(|e| format!("{e} for argument 1"))?,
+        None => true,
+    };
+    let ids: Vec<usize> = match arguments.first() {
+        Some(target) => targets(app, target)?,
+        None => match &app.call_stack.current()?.self_instance {
+            InstanceRef::Instance(id) => vec![*id],
+            InstanceRef::Struct(_) => return Err("Cannot destroy a struct".to_string()),
+        },
+    };
+    for id in ids {
+        app.destroy_instance(id, execute_event)?;
+    }
+    Ok(Value::Undefined)
+}
+
+fn instance_exists(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    if matches!(arguments[0], Value::Struct(_)) {
+        return Ok(Value::Bool(false))
+    }
+    Ok(Value::Bool(!targets(app, &arguments[0])?.is_empty()))
+}
+
+fn instance_number(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(targets(app, &arguments[0])?.len() as f64))
+}
+
+/// The n-th instance of an object, or `noone`
+fn instance_find(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let n: i64 = arg_int(arguments, 1)?;
+    let ids: Vec<usize> = targets(app, &arguments[0])?;
+    let id: Option<usize> = usize::try_from(n).ok().and_then(|n| ids.get(n).copied());
+    Ok(Value::Real(id.map_or(NOONE as f64, |id| id as f64)))
+}
+
@@ -0,0 +1,65 @@
+use crate::App;
+use crate::code::builtins::{arg_int, Arity, Builtins};
+use crate::code::value::Value;
+use crate::input::keyboard::Keyboard;
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("keyboard_check", Arity::Exact(1), keyboard_check);
+    builtins.register("keyboard_check_pressed", Arity::Exact(1), keyboard_check_pressed);
+    builtins.register("keyboard_check_released", Arity::Exact(1), keyboard_check_released);
+    builtins.register("keyboard_check_direct", Arity::Exact(1), keyboard_check);
+    builtins.register("keyboard_clear", Arity::Exact(1), keyboard_clear);
+    builtins.register("keyboard_key_press", Arity::Exact(1), keyboard_key_press);
+    builtins.register("keyboard_key_release", Arity::Exact(1), keyboard_key_release);
+    builtins.register("io_clear", Arity::Exact(0), io_clear);
+}
+
+/// A virtual key code; `None` for numbers no key can have
+fn arg_key(arguments: &[Value], index: usize) -> Result<Option<u32>, String> {
+    Ok(u32::try_from(arg_int(arguments, index)?).ok())
+}
+
+fn check(app: &App, arguments: &[Value], test: fn(&Keyboard, u32) -> bool) -> Result<Value, String> {
+    let Some(key) = arg_key(arguments, 0)? else { return Ok(Value::Bool(false)) };
+    Ok(Value::Bool(test(&app.keyboard, key)))
+}
+
+fn keyboard_check(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    check(app, arguments, Keyboard::check)
+}
+
+fn keyboard_check_pressed(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    check(app, arguments, Keyboard::check_pressed)
+}
+
+fn keyboard_check_released(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    check(app, arguments, Keyboard::check_released)
+}
+
+fn keyboard_clear(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    if let Some(key) = arg_key(arguments, 0)? {
+        app.keyboard.clear(key);
+    }
+    Ok(Value::Undefined)
+}
+
+/// Simulates a key press; the game sees it like a real one
+fn keyboard_key_press(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    if let Some(key) = arg_key(arguments, 0)? {
+        app.keyboard.press(key);
+    }
+    Ok(Value::Undefined)
+}
+
+fn keyboard_key_release(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    if let Some(key) = arg_key(arguments, 0)? {
+        app.keyboard.release(key);
+    }
+    Ok(Value::Undefined)
+}
+
+/// Forgets every key state
+fn io_clear(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    app.keyboard = Keyboard::default();
+    Ok(Value::Undefined)
+}
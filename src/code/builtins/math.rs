@@ -0,0 +1,263 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::App;
+use crate::code::builtins::{arg_int, arg_real, Arity, Builtins};
+use crate::code::value::Value;
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("floor", Arity::Exact(1), floor);
+    builtins.register("ceil", Arity::Exact(1), ceil);
+    builtins.register("round", Arity::Exact(1), round);
+    builtins.register("frac", Arity::Exact(1), frac);
+    builtins.register("abs", Arity::Exact(1), abs);
+    builtins.register("sign", Arity::Exact(1), sign);
+    builtins.register("sqr", Arity::Exact(1), sqr);
+    builtins.register("sqrt", Arity::Exact(1), sqrt);
+    builtins.register("power", Arity::Exact(2), power);
+    builtins.register("sin", Arity::Exact(1), sin);
+    builtins.register("cos", Arity::Exact(1), cos);
+    builtins.register("degtorad", Arity::Exact(1), degtorad);
+    builtins.register("radtodeg", Arity::Exact(1), radtodeg);
+    builtins.register("min", Arity::Variadic, min);
+    builtins.register("max", Arity::Variadic, max);
+    builtins.register("clamp", Arity::Exact(3), clamp);
+    builtins.register("lerp", Arity::Exact(3), lerp);
+    builtins.register("point_distance", Arity::Exact(4), point_distance);
+    builtins.register("point_direction", Arity::Exact(4), point_direction);
+    builtins.register("lengthdir_x", Arity::Exact(2), lengthdir_x);
+    builtins.register("lengthdir_y", Arity::Exact(2), lengthdir_y);
+    builtins.register("random", Arity::Exact(1), random);
+    builtins.register("random_range", Arity::Exact(2), random_range);
+    builtins.register("irandom", Arity::Exact(1), irandom);
+    builtins.register("irandom_range", Arity::Exact(2), irandom_range);
+    builtins.register("choose", Arity::Variadic, choose);
+    builtins.register("randomize", Arity::Exact(0), randomize);
+    builtins.register("random_set_seed", Arity::Exact(1), random_set_seed);
+    builtins.register("random_get_seed", Arity::Exact(0), random_get_seed);
+    builtins.register("math_set_epsilon", Arity::Exact(1), math_set_epsilon);
+    builtins.register("math_get_epsilon", Arity::Exact(0), math_get_epsilon);
+}
+
+
+/// GameMaker's WELL512a random number generator.
+#[derive(Debug, Clone)]
+pub struct Random {
+    seed: u32,
+    state: [u32; 16],
+    index: usize,
+}
+impl Random {
+    pub fn new(seed: u32) -> Self {
+        let mut random = Self { seed, state: [0; 16], index: 0 };
+        random.set_seed(seed);
+        random
+    }
+    pub fn set_seed(&mut self, seed: u32) {
+        self.seed = seed;
+        self.index = 0;
+        let mut value: u32 = seed;
+        for item in &mut self.state {
+            value = value.wrapping_mul(0x343FD).wrapping_add(0x269EC3);
+            *item = (value >> 16) & 0x7FFF;
+        }
+    }
+    pub fn next_u32(&mut self) -> u32 {
+        let mut a: u32 = self.state[self.index];
+        let mut c: u32 = self.state[(self.index + 13) & 15];
+        let b: u32 = a ^ c ^ (a << 16) ^ (c << 15);
+        c = self.state[(self.index + 9) & 15];
+        c ^= c >> 11;
+        a = b ^ c;
+        self.state[self.index] = a;
+        let d: u32 = a ^ ((a << 5) & 0xDA442D24);
+        self.index = (self.index + 15) & 15;
+        a = self.state[self.index];
+        self.state[self.index] = a ^ b ^ d ^ (a << 2) ^ (b << 18) ^ (c << 28);
+        self.state[self.index]
+    }
+    /// Random number in the range [0, 1)
+    pub fn next_real(&mut self) -> f64 {
+        f64::from(self.next_u32()) / 4294967296.0
+    }
+}
+
+
+fn real(value: f64) -> Result<Value, String> {
+    Ok(Value::Real(value))
+}
+
+fn floor(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    real(arg_real(arguments, 0)?.floor())
+}
+
+fn ceil(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    real(arg_real(arguments, 0)?.ceil())
+}
+
+/// GameMaker rounds halfway cases to the nearest even number
+fn round(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    real(arg_real(arguments, 0)?.round_ties_even())
+}
+
+fn frac(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    real(arg_real(arguments, 0)?.fract())
+}
+
+fn abs(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    real(arg_real(arguments, 0)?.abs())
+}
+
+fn sign(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let value: f64 = arg_real(arguments, 0)?;
+    real(if value > 0.0 { 1.0 } else if value < 0.0 { -1.0 } else { 0.0 })
+}
+
+fn sqr(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let value: f64 = arg_real(arguments, 0)?;
+    real(value * value)
+}
+
+fn sqrt(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let value: f64 = arg_real(arguments, 0)?;
+    if value < 0.0 {
+        return Err(format!("Cannot get square root of negative number {value}"))
+    }
+    real(value.sqrt())
+}
+
+fn power(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    real(arg_real(arguments, 0)?.powf(arg_real(arguments, 1)?))
+}
+
+fn sin(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    real(arg_real(arguments, 0)?.sin())
+}
+
+fn cos(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    real(arg_real(arguments, 0)?.cos())
+}
+
+fn degtorad(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    real(arg_real(arguments, 0)?.to_radians())
+}
+
+fn radtodeg(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    real(arg_real(arguments, 0)?.to_degrees())
+}
+
+fn min(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let mut result: f64 = arg_real(arguments, 0)?;
+    for i in 1..arguments.len() {
+        result = result.min(arg_real(arguments, i)?);
+    }
+    real(result)
+}
+
+fn max(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let mut result: f64 = arg_real(arguments, 0)?;
+    for i in 1..arguments.len() {
+        result = result.max(arg_real(arguments, i)?);
+    }
+    real(result)
+}
+
+fn clamp(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let value: f64 = arg_real(arguments, 0)?;
+    let min: f64 = arg_real(arguments, 1)?;
+    let max: f64 = arg_real(arguments, 2)?;
+    real(value.max(min).min(max))
+}
+
+fn lerp(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let a: f64 = arg_real(arguments, 0)?;
+    let b: f64 = arg_real(arguments, 1)?;
+    let amount: f64 = arg_real(arguments, 2)?;
+    real(a + (b - a) * amount)
+}
+
+fn point_distance(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let dx: f64 = arg_real(arguments, 2)? - arg_real(arguments, 0)?;
+    let dy: f64 = arg_real(arguments, 3)? - arg_real(arguments, 1)?;
+    real(dx.hypot(dy))
+}
+
+/// Angle in degrees; counter-clockwise since the y axis points down
+fn point_direction(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let dx: f64 = arg_real(arguments, 2)? - arg_real(arguments, 0)?;
+    let dy: f64 = arg_real(arguments, 3)? - arg_real(arguments, 1)?;
+    real((-dy).atan2(dx).to_degrees().rem_euclid(360.0))
+}
+
+fn lengthdir_x(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let length: f64 = arg_real(arguments, 0)?;
+    let direction: f64 = arg_real(arguments, 1)?;
+    real(length * direction.to_radians().cos())
+}
+
+fn lengthdir_y(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let length: f64 = arg_real(arguments, 0)?;
+    let direction: f64 = arg_real(arguments, 1)?;
+    real(-length * direction.to_radians().sin())
+}
+
+fn random(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let upper: f64 = arg_real(arguments, 0)?;
+    real(app.random.next_real() * upper)
+}
+
+fn random_range(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let lower: f64 = arg_real(arguments, 0)?;
+    let upper: f64 = arg_real(arguments, 1)?;
+    real(lower + app.random.next_real() * (upper - lower))
+}
+
+fn irandom(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let upper: i64 = arg_int(arguments, 0)?;
+    real((app.random.next_real() * (upper + 1) as f64).floor())
+}
+
+fn irandom_range(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let a: i64 = arg_int(arguments, 0)?;
+    let b: i64 = arg_int(arguments, 1)?;
+    let (lower, upper) = if a <= b { (a, b) } else { (b, a) };
+    real(lower as f64 + (app.random.next_real() * (upper - lower + 1) as f64).floor())
+}
+
+fn choose(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    if arguments.is_empty() {
+        return Err("Cannot choose from zero arguments".to_string())
+    }
+    let index: usize = (app.random.next_real() * arguments.len() as f64) as usize;
+    Ok(arguments[index.min(arguments.len() - 1)].clone())
+}
+
+fn randomize(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    let seed: u32 = SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|i| i.as_nanos() as u32)
+        .unwrap_or(0);
+    app.random.set_seed(seed);
+    real(f64::from(seed))
+}
+
+fn random_set_seed(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let seed: i64 = arg_int(arguments, 0)?;
+    app.random.set_seed(seed as u32);
+    Ok(Value::Undefined)
+}
+
+fn random_get_seed(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    real(f64::from(app.random.seed))
+}
+
+fn math_set_epsilon(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let epsilon: f64 = arg_real(arguments, 0)?;
+    if !(0.0..=1.0).contains(&epsilon) {
+        return Err(format!("Epsilon {epsilon} is out of range 0..1"))
+    }
+    app.math_epsilon = epsilon;
+    Ok(Value::Undefined)
+}
+
+fn math_get_epsilon(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    real(app.math_epsilon)
+}
+
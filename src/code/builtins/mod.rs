@@ -0,0 +1,134 @@
+pub mod array;
+pub mod collision;
+pub mod debug;
+pub mod draw;
+pub mod event;
+pub mod gamepad;
+pub mod instance;
+pub mod keyboard;
+pub mod math;
+pub mod mouse;
+pub mod primitives;
+pub mod room;
+pub mod string;
+pub mod structs;
+pub mod text;
+pub mod view;
+
+use std::collections::HashMap;
+use libgm::GMData;
+use libgm::gm::GMInstruction;
+use log::{info, warn};
+use crate::App;
+use crate::code::call::FunctionTarget;
+use crate::code::value::Value;
+
+pub type BuiltinFunction = fn(&mut App, &[Value]) -> Result<Value, String>;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Arity {
+    Exact(usize),
+    Range(usize, usize),
+    Variadic,
+}
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exact(n) => count == n,
+            Arity::Range(min, max) => count >= min && count <= max,
+            Arity::Variadic => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub arity: Arity,
+    pub function: BuiltinFunction,
+}
+
+#[derive(Debug, Clone)]
+pub struct Builtins {
+    by_name: HashMap<&'static str, Builtin>,
+}
+impl Builtins {
+    pub fn new() -> Self {
+        let mut builtins = Self { by_name: HashMap::new() };
+        array::register(&mut builtins);
+        collision::register(&mut builtins);
+        debug::register(&mut builtins);
+        draw::register(&mut builtins);
+        event::register(&mut builtins);
+        gamepad::register(&mut builtins);
+        instance::register(&mut builtins);
+        keyboard::register(&mut builtins);
+        math::register(&mut builtins);
+        mouse::register(&mut builtins);
+        primitives::register(&mut builtins);
+        room::register(&mut builtins);
+        string::register(&mut builtins);
+        structs::register(&mut builtins);
+        text::register(&mut builtins);
+        view::register(&mut builtins);
+        builtins
+    }
+    pub fn register(&mut self, name: &'static str, arity: Arity, function: BuiltinFunction) {
+        if self.by_name.insert(name, Builtin { name, arity, function }).is_some() {
+            warn!("Builtin function \"{name}\" was registered twice");
+        }
+    }
+    pub fn get(&self, name: &str) -> Option<&Builtin> {
+        self.by_name.get(name)
+    }
+}
+
+
+/// Logs every builtin function referenced by the game's code which is not implemented yet,
+/// together with the number of call sites, so the most important ones can be implemented first.
+pub fn report_unimplemented(data: &GMData, functions: &[FunctionTarget], builtins: &Builtins) {
+    let mut call_sites: HashMap<&str, usize> = HashMap::new();
+    for code in &data.codes.codes_by_index {
+        for instruction in &code.instructions {
+            let GMInstruction::Call(instr) = instruction else { continue };
+            let Some(FunctionTarget::Builtin(name)) = functions.get(instr.function.index) else { continue };
+            if builtins.get(name).is_none() {
+                *call_sites.entry(name).or_insert(0) += 1;
+            }
+        }
+    }
+
+    if call_sites.is_empty() {
+        info!("All builtin functions used by this game are implemented");
+        return
+    }
+
+    let mut call_sites: Vec<(&str, usize)> = call_sites.into_iter().collect();
+    call_sites.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    warn!("{} builtin functions used by this game are not implemented:", call_sites.len());
+    for (name, count) in call_sites {
+        warn!("| {count:>6} call sites: {name}");
+    }
+}
+
+
+pub fn argument(arguments: &[Value], index: usize) -> Result<&Value, String> {
+    arguments.get(index)
+        .ok_or_else(|| format!("Missing argument {index} (got {} arguments)", arguments.len()))
+}
+
+pub fn arg_real(arguments: &[Value], index: usize) -> Result<f64, String> {
+    argument(arguments, index)?.to_real()
+        .map_err(|e| format!("{e} for argument {index}"))
+}
+
+pub fn arg_int(arguments: &[Value], index: usize) -> Result<i64, String> {
+    argument(arguments, index)?.to_int64()
+        .map_err(|e| format!("{e} for argument {index}"))
+}
+
+pub fn arg_string(arguments: &[Value], index: usize) -> Result<&str, String> {
+    argument(arguments, index)?.as_str()
+        .map_err(|e| format!("{e} for argument {index}"))
+}
+
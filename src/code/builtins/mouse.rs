@@ -0,0 +1,82 @@
+use crate::App;
+use crate::code::builtins::{arg_int, Arity, Builtins};
+use crate::code::value::Value;
+use crate::input::mouse::Mouse;
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("mouse_check_button", Arity::Exact(1), mouse_check_button);
+    builtins.register("mouse_check_button_pressed", Arity::Exact(1), mouse_check_button_pressed);
+    builtins.register("mouse_check_button_released", Arity::Exact(1), mouse_check_button_released);
+    builtins.register("mouse_clear", Arity::Exact(1), mouse_clear);
+    builtins.register("mouse_wheel_up", Arity::Exact(0), mouse_wheel_up);
+    builtins.register("mouse_wheel_down", Arity::Exact(0), mouse_wheel_down);
+    builtins.register("device_mouse_x", Arity::Exact(1), device_mouse_x);
+    builtins.register("device_mouse_y", Arity::Exact(1), device_mouse_y);
+    builtins.register("device_mouse_x_to_gui", Arity::Exact(1), device_mouse_x_to_gui);
+    builtins.register("device_mouse_y_to_gui", Arity::Exact(1), device_mouse_y_to_gui);
+    builtins.register("window_mouse_get_x", Arity::Exact(0), window_mouse_get_x);
+    builtins.register("window_mouse_get_y", Arity::Exact(0), window_mouse_get_y);
+}
+
+fn check(app: &App, arguments: &[Value], test: fn(&Mouse, i64) -> bool) -> Result<Value, String> {
+    Ok(Value::Bool(test(&app.mouse, arg_int(arguments, 0)?)))
+}
+
+fn mouse_check_button(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    check(app, arguments, Mouse::check)
+}
+
+fn mouse_check_button_pressed(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    check(app, arguments, Mouse::check_pressed)
+}
+
+fn mouse_check_button_released(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    check(app, arguments, Mouse::check_released)
+}
+
+fn mouse_clear(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    app.mouse.clear(arg_int(arguments, 0)?);
+    Ok(Value::Undefined)
+}
+
+fn mouse_wheel_up(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(app.mouse.wheel_up()))
+}
+
+fn mouse_wheel_down(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(app.mouse.wheel_down()))
+}
+
+/// Only device 0, the mouse, exists; touch devices are never reported
+fn device_position(arguments: &[Value], position: (f64, f64)) -> Result<Option<(f64, f64)>, String> {
+    Ok((arg_int(arguments, 0)? == 0).then_some(position))
+}
+
+fn device_mouse_x(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let position: Option<(f64, f64)> = device_position(arguments, app.mouse_room_position())?;
+    Ok(Value::Real(position.map_or(0.0, |(x, _)| x)))
+}
+
+fn device_mouse_y(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let position: Option<(f64, f64)> = device_position(arguments, app.mouse_room_position())?;
+    Ok(Value::Real(position.map_or(0.0, |(_, y)| y)))
+}
+
+/// The GUI is drawn in application surface coordinates
+fn device_mouse_x_to_gui(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let position: Option<(f64, f64)> = device_position(arguments, app.mouse_surface_position())?;
+    Ok(Value::Real(position.map_or(0.0, |(x, _)| x)))
+}
+
+fn device_mouse_y_to_gui(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let position: Option<(f64, f64)> = device_position(arguments, app.mouse_surface_position())?;
+    Ok(Value::Real(position.map_or(0.0, |(_, y)| y)))
+}
+
+fn window_mouse_get_x(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(app.mouse.window_x))
+}
+
+fn window_mouse_get_y(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(app.mouse.window_y))
+}
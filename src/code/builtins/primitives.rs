@@ -0,0 +1,233 @@
+use crate::App;
+use crate::code::builtins::{arg_int, arg_real, argument, Arity, Builtins};
+use crate::code::value::Value;
+use crate::graphics::canvas::Vertex;
+use crate::graphics::primitives::{Primitive, PrimitiveKind};
+use crate::graphics::text::mix_colors;
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("draw_set_colour", Arity::Exact(1), draw_set_colour);
+    builtins.register("draw_set_color", Arity::Exact(1), draw_set_colour);
+    builtins.register("draw_get_colour", Arity::Exact(0), draw_get_colour);
+    builtins.register("draw_get_color", Arity::Exact(0), draw_get_colour);
+    builtins.register("draw_set_alpha", Arity::Exact(1), draw_set_alpha);
+    builtins.register("draw_get_alpha", Arity::Exact(0), draw_get_alpha);
+    builtins.register("draw_set_circle_precision", Arity::Exact(1), draw_set_circle_precision);
+    builtins.register("make_colour_rgb", Arity::Exact(3), make_colour_rgb);
+    builtins.register("make_color_rgb", Arity::Exact(3), make_colour_rgb);
+    builtins.register("merge_colour", Arity::Exact(3), merge_colour);
+    builtins.register("merge_color", Arity::Exact(3), merge_colour);
+    builtins.register("draw_clear", Arity::Exact(1), draw_clear);
+    builtins.register("draw_clear_alpha", Arity::Exact(2), draw_clear);
+    builtins.register("draw_point", Arity::Exact(2), draw_point);
+    builtins.register("draw_line", Arity::Exact(4), draw_line);
+    builtins.register("draw_line_width", Arity::Exact(5), draw_line_width);
+    builtins.register("draw_line_colour", Arity::Exact(6), draw_line_colour);
+    builtins.register("draw_line_color", Arity::Exact(6), draw_line_colour);
+    builtins.register("draw_rectangle", Arity::Exact(5), draw_rectangle);
+    builtins.register("draw_rectangle_colour", Arity::Exact(9), draw_rectangle_colour);
+    builtins.register("draw_rectangle_color", Arity::Exact(9), draw_rectangle_colour);
+    builtins.register("draw_circle", Arity::Exact(4), draw_circle);
+    builtins.register("draw_circle_colour", Arity::Exact(6), draw_circle_colour);
+    builtins.register("draw_circle_color", Arity::Exact(6), draw_circle_colour);
+    builtins.register("draw_ellipse", Arity::Exact(5), draw_ellipse);
+    builtins.register("draw_triangle", Arity::Exact(7), draw_triangle);
+    builtins.register("draw_triangle_colour", Arity::Exact(10), draw_triangle_colour);
+    builtins.register("draw_triangle_color", Arity::Exact(10), draw_triangle_colour);
+    builtins.register("draw_healthbar", Arity::Exact(11), draw_healthbar);
+    builtins.register("draw_primitive_begin", Arity::Exact(1), draw_primitive_begin);
+    builtins.register("draw_vertex", Arity::Exact(2), draw_vertex);
+    builtins.register("draw_vertex_colour", Arity::Exact(4), draw_vertex_colour);
+    builtins.register("draw_vertex_color", Arity::Exact(4), draw_vertex_colour);
+    builtins.register("draw_primitive_end", Arity::Exact(0), draw_primitive_end);
+}
+
+/// Colours are BGR integers; anything above 24 bits is ignored
+fn arg_color(arguments: &[Value], index: usize) -> Result<u32, String> {
+    Ok(arg_int(arguments, index)? as u32 & 0xFFFFFF)
+}
+
+fn arg_bool(arguments: &[Value], index: usize) -> Result<bool, String> {
+    argument(arguments, index)?.to_bool().map_err(|e| format!("{e} for argument {index}"))
+}
+
+/// (x1, y1, x2, y2) starting at argument `index`
+fn arg_rectangle(arguments: &[Value], index: usize) -> Result<(f64, f64, f64, f64), String> {
+    Ok((arg_real(arguments, index)?, arg_real(arguments, index + 1)?, arg_real(arguments, index + 2)?, arg_real(arguments, index + 3)?))
+}
+
+fn draw_set_colour(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    app.draw_state.color = arg_color(arguments, 0)?;
+    Ok(Value::Undefined)
+}
+
+fn draw_get_colour(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(f64::from(app.draw_state.color)))
+}
+
+fn draw_set_alpha(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    app.draw_state.alpha = arg_real(arguments, 0)?.clamp(0.0, 1.0);
+    Ok(Value::Undefined)
+}
+
+fn draw_get_alpha(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(app.draw_state.alpha))
+}
+
+/// GameMaker rounds the precision to a multiple of 4 between 4 and 64
+fn draw_set_circle_precision(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let precision: i64 = arg_int(arguments, 0)?.clamp(4, 64);
+    app.draw_state.circle_precision = (precision / 4 * 4) as u32;
+    Ok(Value::Undefined)
+}
+
+fn make_colour_rgb(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let channel = |index: usize| -> Result<u32, String> { Ok(arg_int(arguments, index)?.clamp(0, 255) as u32) };
+    Ok(Value::Real(f64::from(channel(0)? | channel(1)? << 8 | channel(2)? << 16)))
+}
+
+fn merge_colour(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let amount: f64 = arg_real(arguments, 2)?.clamp(0.0, 1.0);
+    Ok(Value::Real(f64::from(mix_colors(arg_color(arguments, 0)?, arg_color(arguments, 1)?, amount))))
+}
+
+/// Clears the current view's port (or the whole surface while drawing the GUI); the alpha is ignored
+fn draw_clear(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    app.canvas.clear(arg_color(arguments, 0)?);
+    Ok(Value::Undefined)
+}
+
+fn draw_point(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let (x, y): (f64, f64) = (arg_real(arguments, 0)?, arg_real(arguments, 1)?);
+    app.fill_rectangle(x, y, x, y, [app.draw_state.color; 4], app.draw_state.alpha);
+    Ok(Value::Undefined)
+}
+
+fn draw_line(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let (x1, y1, x2, y2) = arg_rectangle(arguments, 0)?;
+    let (color, alpha): (u32, f64) = (app.draw_state.color, app.draw_state.alpha);
+    app.draw_line_between(Vertex::new(x1, y1, color, alpha), Vertex::new(x2, y2, color, alpha), 1.0);
+    Ok(Value::Undefined)
+}
+
+fn draw_line_width(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let (x1, y1, x2, y2) = arg_rectangle(arguments, 0)?;
+    let width: f64 = arg_real(arguments, 4)?;
+    let (color, alpha): (u32, f64) = (app.draw_state.color, app.draw_state.alpha);
+    app.draw_line_between(Vertex::new(x1, y1, color, alpha), Vertex::new(x2, y2, color, alpha), width);
+    Ok(Value::Undefined)
+}
+
+fn draw_line_colour(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let (x1, y1, x2, y2) = arg_rectangle(arguments, 0)?;
+    let alpha: f64 = app.draw_state.alpha;
+    let from: Vertex = Vertex::new(x1, y1, arg_color(arguments, 4)?, alpha);
+    let to: Vertex = Vertex::new(x2, y2, arg_color(arguments, 5)?, alpha);
+    app.draw_line_between(from, to, 1.0);
+    Ok(Value::Undefined)
+}
+
+fn draw_rectangle(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let (x1, y1, x2, y2) = arg_rectangle(arguments, 0)?;
+    let colors: [u32; 4] = [app.draw_state.color; 4];
+    if arg_bool(arguments, 4)? {
+        app.outline_rectangle(x1, y1, x2, y2, colors, app.draw_state.alpha);
+    } else {
+        app.fill_rectangle(x1, y1, x2, y2, colors, app.draw_state.alpha);
+    }
+    Ok(Value::Undefined)
+}
+
+fn draw_rectangle_colour(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let (x1, y1, x2, y2) = arg_rectangle(arguments, 0)?;
+    let colors: [u32; 4] = [arg_color(arguments, 4)?, arg_color(arguments, 5)?, arg_color(arguments, 6)?, arg_color(arguments, 7)?];
+    if arg_bool(arguments, 8)? {
+        app.outline_rectangle(x1, y1, x2, y2, colors, app.draw_state.alpha);
+    } else {
+        app.fill_rectangle(x1, y1, x2, y2, colors, app.draw_state.alpha);
+    }
+    Ok(Value::Undefined)
+}
+
+fn draw_circle(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let (x, y, radius): (f64, f64, f64) = (arg_real(arguments, 0)?, arg_real(arguments, 1)?, arg_real(arguments, 2)?);
+    let color: u32 = app.draw_state.color;
+    app.draw_ellipse_with((x, y, radius, radius), color, color, arg_bool(arguments, 3)?);
+    Ok(Value::Undefined)
+}
+
+fn draw_circle_colour(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let (x, y, radius): (f64, f64, f64) = (arg_real(arguments, 0)?, arg_real(arguments, 1)?, arg_real(arguments, 2)?);
+    app.draw_ellipse_with((x, y, radius, radius), arg_color(arguments, 3)?, arg_color(arguments, 4)?, arg_bool(arguments, 5)?);
+    Ok(Value::Undefined)
+}
+
+fn draw_ellipse(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let (x1, y1, x2, y2) = arg_rectangle(arguments, 0)?;
+    let color: u32 = app.draw_state.color;
+    let ellipse: (f64, f64, f64, f64) = ((x1 + x2) / 2.0, (y1 + y2) / 2.0, (x2 - x1).abs() / 2.0, (y2 - y1).abs() / 2.0);
+    app.draw_ellipse_with(ellipse, color, color, arg_bool(arguments, 4)?);
+    Ok(Value::Undefined)
+}
+
+fn draw_triangle(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let (color, alpha): (u32, f64) = (app.draw_state.color, app.draw_state.alpha);
+    let vertices: [Vertex; 3] = [
+        Vertex::new(arg_real(arguments, 0)?, arg_real(arguments, 1)?, color, alpha),
+        Vertex::new(arg_real(arguments, 2)?, arg_real(arguments, 3)?, color, alpha),
+        Vertex::new(arg_real(arguments, 4)?, arg_real(arguments, 5)?, color, alpha),
+    ];
+    app.draw_triangle_with(vertices, arg_bool(arguments, 6)?);
+    Ok(Value::Undefined)
+}
+
+fn draw_triangle_colour(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let alpha: f64 = app.draw_state.alpha;
+    let vertices: [Vertex; 3] = [
+        Vertex::new(arg_real(arguments, 0)?, arg_real(arguments, 1)?, arg_color(arguments, 6)?, alpha),
+        Vertex::new(arg_real(arguments, 2)?, arg_real(arguments, 3)?, arg_color(arguments, 7)?, alpha),
+        Vertex::new(arg_real(arguments, 4)?, arg_real(arguments, 5)?, arg_color(arguments, 8)?, alpha),
+    ];
+    app.draw_triangle_with(vertices, arg_bool(arguments, 9)?);
+    Ok(Value::Undefined)
+}
+
+fn draw_healthbar(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let rectangle: (f64, f64, f64, f64) = arg_rectangle(arguments, 0)?;
+    let amount: f64 = arg_real(arguments, 4)?;
+    let colors: (u32, u32, u32) = (arg_color(arguments, 5)?, arg_color(arguments, 6)?, arg_color(arguments, 7)?);
+    let direction: i64 = arg_int(arguments, 8)?;
+    app.draw_healthbar_with(rectangle, amount, colors, direction, arg_bool(arguments, 9)?, arg_bool(arguments, 10)?);
+    Ok(Value::Undefined)
+}
+
+/// The texture argument of GMS2's `draw_primitive_begin_texture` does not exist here
+fn draw_primitive_begin(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let kind: PrimitiveKind = PrimitiveKind::from_int(arg_int(arguments, 0)?)?;
+    app.draw_state.primitive = Some(Primitive { kind, vertices: Vec::new() });
+    Ok(Value::Undefined)
+}
+
+fn add_vertex(app: &mut App, vertex: Vertex) -> Result<Value, String> {
+    let primitive: &mut Primitive = app.draw_state.primitive.as_mut()
+        .ok_or_else(|| "Cannot add a vertex without calling draw_primitive_begin first".to_string())?;
+    primitive.vertices.push(vertex);
+    Ok(Value::Undefined)
+}
+
+fn draw_vertex(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let vertex: Vertex = Vertex::new(arg_real(arguments, 0)?, arg_real(arguments, 1)?, app.draw_state.color, app.draw_state.alpha);
+    add_vertex(app, vertex)
+}
+
+fn draw_vertex_colour(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let vertex: Vertex = Vertex::new(arg_real(arguments, 0)?, arg_real(arguments, 1)?, arg_color(arguments, 2)?, arg_real(arguments, 3)?);
+    add_vertex(app, vertex)
+}
+
+fn draw_primitive_end(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    let primitive: Primitive = app.draw_state.primitive.take()
+        .ok_or_else(|| "draw_primitive_end called without draw_primitive_begin".to_string())?;
+    app.draw_primitive(primitive);
+    Ok(Value::Undefined)
+}
@@ -0,0 +1,57 @@
+use crate::App;
+use crate::code::builtins::{arg_int, Arity, Builtins};
+use crate::code::value::Value;
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("room_goto", Arity::Exact(1), room_goto);
+    builtins.register("room_goto_next", Arity::Exact(0), room_goto_next);
+    builtins.register("room_goto_previous", Arity::Exact(0), room_goto_previous);
+    builtins.register("room_restart", Arity::Exact(0), room_restart);
+    builtins.register("room_next", Arity::Exact(1), room_next);
+    builtins.register("room_previous", Arity::Exact(1), room_previous);
+}
+
+fn room_goto(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let room_index: i64 = arg_int(arguments, 0)?;
+    let room_index: usize = usize::try_from(room_index).map_err(|_| format!("Invalid room {room_index}"))?;
+    app.goto_room(room_index)?;
+    Ok(Value::Undefined)
+}
+
+fn room_goto_next(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    let room_index: usize = app.relative_room(1)?
+        .ok_or_else(|| "Cannot go to the next room because this is the last room".to_string())?;
+    app.goto_room(room_index)?;
+    Ok(Value::Undefined)
+}
+
+fn room_goto_previous(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    let room_index: usize = app.relative_room(-1)?
+        .ok_or_else(|| "Cannot go to the previous room because this is the first room".to_string())?;
+    app.goto_room(room_index)?;
+    Ok(Value::Undefined)
+}
+
+fn room_restart(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    app.goto_room(app.room_index)?;
+    Ok(Value::Undefined)
+}
+
+/// The room after `room` in the room order, or -1
+fn room_next(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let room: i64 = arg_int(arguments, 0)?;
+    let order: &Vec<u32> = &app.data.general_info.room_order;
+    let next: Option<u32> = order.iter().position(|i| i64::from(*i) == room).and_then(|position| order.get(position + 1).copied());
+    Ok(Value::Real(next.map_or(-1.0, f64::from)))
+}
+
+/// The room before `room` in the room order, or -1
+fn room_previous(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let room: i64 = arg_int(arguments, 0)?;
+    let order: &Vec<u32> = &app.data.general_info.room_order;
+    let previous: Option<u32> = order.iter().position(|i| i64::from(*i) == room)
+        .and_then(|position| position.checked_sub(1))
+        .and_then(|position| order.get(position).copied());
+    Ok(Value::Real(previous.map_or(-1.0, f64::from)))
+}
+
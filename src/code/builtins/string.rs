@@ -0,0 +1,93 @@
+use crate::App;
+use crate::code::builtins::{arg_int, arg_string, Arity, Builtins};
+use crate::code::value::Value;
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("string", Arity::Exact(1), string);
+    builtins.register("real", Arity::Exact(1), real);
+    builtins.register("typeof", Arity::Exact(1), typeof_);
+    builtins.register("is_string", Arity::Exact(1), is_string);
+    builtins.register("is_real", Arity::Exact(1), is_real);
+    builtins.register("is_undefined", Arity::Exact(1), is_undefined);
+    builtins.register("string_length", Arity::Exact(1), string_length);
+    builtins.register("string_upper", Arity::Exact(1), string_upper);
+    builtins.register("string_lower", Arity::Exact(1), string_lower);
+    builtins.register("string_char_at", Arity::Exact(2), string_char_at);
+    builtins.register("string_copy", Arity::Exact(3), string_copy);
+    builtins.register("string_pos", Arity::Exact(2), string_pos);
+}
+
+fn string(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::string(app.value_to_string(&arguments[0])))
+}
+
+fn real(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let Value::String(string) = &arguments[0] else {
+        return Ok(Value::Real(arguments[0].to_real()?))
+    };
+    let value: f64 = string.trim().parse()
+        .map_err(|e| format!("Could not convert string \"{string}\" to a real number: {e}"))?;
+    Ok(Value::Real(value))
+}
+
+fn typeof_(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::string(arguments[0].type_name()))
+}
+
+fn is_string(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(matches!(arguments[0], Value::String(_))))
+}
+
+fn is_real(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(matches!(arguments[0], Value::Real(_) | Value::Int32(_) | Value::Int64(_))))
+}
+
+fn is_undefined(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(matches!(arguments[0], Value::Undefined)))
+}
+
+fn string_length(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let string: &str = arg_string(arguments, 0)?;
+    Ok(Value::Real(string.chars().count() as f64))
+}
+
+fn string_upper(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::string(arg_string(arguments, 0)?.to_uppercase()))
+}
+
+fn string_lower(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::string(arg_string(arguments, 0)?.to_lowercase()))
+}
+
+/// GameMaker string positions are 1-based
+fn string_char_at(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let position: i64 = arg_int(arguments, 1)?.max(1);
+    let string: String = arg_string(arguments, 0)?
+        .chars()
+        .nth(position as usize - 1)
+        .map(String::from)
+        .unwrap_or_default();
+    Ok(Value::string(string))
+}
+
+fn string_copy(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let position: i64 = arg_int(arguments, 1)?.max(1);
+    let count: i64 = arg_int(arguments, 2)?.max(0);
+    let string: String = arg_string(arguments, 0)?
+        .chars()
+        .skip(position as usize - 1)
+        .take(count as usize)
+        .collect();
+    Ok(Value::string(string))
+}
+
+fn string_pos(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let substring: &str = arg_string(arguments, 0)?;
+    let string: &str = arg_string(arguments, 1)?;
+    let position: usize = match string.find(substring) {
+        Some(byte_index) => string[..byte_index].chars().count() + 1,
+        None => 0,
+    };
+    Ok(Value::Real(position as f64))
+}
+
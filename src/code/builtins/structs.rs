@@ -0,0 +1,88 @@
+use std::rc::Rc;
+use crate::App;
+use crate::code::array::Array;
+use crate::code::builtins::{argument, Arity, Builtins};
+use crate::code::structs::{Method, Struct, StructRef};
+use crate::code::value::Value;
+use crate::instance::InstanceRef;
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("@@NewGMLObject@@", Arity::Variadic, new_gml_object);
+    builtins.register("@@NewGMLArray@@", Arity::Variadic, new_gml_array);
+    builtins.register("@@This@@", Arity::Exact(0), this);
+    builtins.register("@@Other@@", Arity::Exact(0), other);
+    builtins.register("method", Arity::Exact(2), method);
+    builtins.register("method_get_index", Arity::Exact(1), method_get_index);
+    builtins.register("method_get_self", Arity::Exact(1), method_get_self);
+    builtins.register("is_struct", Arity::Exact(1), is_struct);
+    builtins.register("is_method", Arity::Exact(1), is_method);
+}
+
+/// Function index of a function reference, which is either a method or a plain function index
+fn function_index(value: &Value) -> Result<usize, String> {
+    match value {
+        Value::Method(method) => Ok(method.function),
+        other => Ok(other.to_int64().map_err(|e| format!("{e} for function"))? as usize),
+    }
+}
+
+/// `new Constructor(args)`: runs the constructor with a fresh struct as `self` and returns that struct
+fn new_gml_object(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let constructor: usize = function_index(argument(arguments, 0)?)?;
+    let structure: StructRef = Struct::new_ref(Some(constructor));
+    let other_instance: InstanceRef = app.call_stack.current()?.self_instance.clone();
+    app.call_function_index(
+        constructor,
+        InstanceRef::Struct(structure.clone()),
+        other_instance,
+        arguments[1..].to_vec(),
+        None,
+    ).map_err(|e| format!("{e}\n↳ while constructing struct"))?;
+    Ok(Value::Struct(structure))
+}
+
+/// Array literals `[a, b, c]`
+fn new_gml_array(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Array(Array::new_ref(Array::from_items(arguments.to_vec()))))
+}
+
+fn this(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(app.call_stack.current()?.self_instance.to_value())
+}
+
+fn other(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(app.call_stack.current()?.other_instance.to_value())
+}
+
+/// Binds a function to an instance or struct; `undefined` creates an unbound method
+fn method(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let bound_self: Option<InstanceRef> = match &arguments[0] {
+        Value::Undefined => None,
+        instance => Some(app.instance_ref(instance)?),
+    };
+    let function: usize = function_index(&arguments[1])?;
+    Ok(Value::Method(Rc::new(Method { function, bound_self })))
+}
+
+fn method_get_index(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    match &arguments[0] {
+        Value::Method(method) => Ok(Value::Real(method.function as f64)),
+        _ => Ok(Value::Undefined),
+    }
+}
+
+fn method_get_self(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    match &arguments[0] {
+        Value::Method(method) => Ok(method.bound_self.as_ref().map_or(Value::Undefined, |i| i.to_value())),
+        _ => Ok(Value::Undefined),
+    }
+}
+
+fn is_struct(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(matches!(arguments[0], Value::Struct(_))))
+}
+
+fn is_method(_: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(matches!(arguments[0], Value::Method(_))))
+}
+
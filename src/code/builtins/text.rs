@@ -0,0 +1,144 @@
+use crate::App;
+use crate::code::builtins::{arg_int, arg_real, argument, Arity, Builtins};
+use crate::code::value::Value;
+use crate::graphics::state::Align;
+use crate::graphics::text::TextParams;
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("draw_set_font", Arity::Exact(1), draw_set_font);
+    builtins.register("draw_get_font", Arity::Exact(0), draw_get_font);
+    builtins.register("draw_set_halign", Arity::Exact(1), draw_set_halign);
+    builtins.register("draw_set_valign", Arity::Exact(1), draw_set_valign);
+    builtins.register("draw_get_halign", Arity::Exact(0), draw_get_halign);
+    builtins.register("draw_get_valign", Arity::Exact(0), draw_get_valign);
+    builtins.register("draw_text", Arity::Exact(3), draw_text);
+    builtins.register("draw_text_ext", Arity::Exact(5), draw_text_ext);
+    builtins.register("draw_text_transformed", Arity::Exact(6), draw_text_transformed);
+    builtins.register("draw_text_ext_transformed", Arity::Exact(8), draw_text_ext_transformed);
+    builtins.register("draw_text_colour", Arity::Exact(8), draw_text_colour);
+    builtins.register("draw_text_color", Arity::Exact(8), draw_text_colour);
+    builtins.register("draw_text_ext_colour", Arity::Exact(10), draw_text_ext_colour);
+    builtins.register("draw_text_ext_color", Arity::Exact(10), draw_text_ext_colour);
+    builtins.register("string_width", Arity::Exact(1), string_width);
+    builtins.register("string_height", Arity::Exact(1), string_height);
+    builtins.register("string_width_ext", Arity::Exact(3), string_width_ext);
+    builtins.register("string_height_ext", Arity::Exact(3), string_height_ext);
+}
+
+/// Anything can be drawn as text; it is converted like `string()` does
+fn arg_text(app: &App, arguments: &[Value], index: usize) -> Result<String, String> {
+    Ok(app.value_to_string(argument(arguments, index)?))
+}
+
+fn arg_color(arguments: &[Value], index: usize) -> Result<u32, String> {
+    Ok(arg_int(arguments, index)? as u32 & 0xFFFFFF)
+}
+
+fn draw_set_font(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let font: i64 = arg_int(arguments, 0)?;
+    if font >= 0 && app.fonts.get(font).is_none() {
+        return Err(format!("Font {font} does not exist"))
+    }
+    app.draw_state.font = font;
+    Ok(Value::Undefined)
+}
+
+fn draw_get_font(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(app.draw_state.font as f64))
+}
+
+fn draw_set_halign(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    app.draw_state.halign = Align::from_int(arg_int(arguments, 0)?)?;
+    Ok(Value::Undefined)
+}
+
+fn draw_set_valign(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    app.draw_state.valign = Align::from_int(arg_int(arguments, 0)?)?;
+    Ok(Value::Undefined)
+}
+
+fn draw_get_halign(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(app.draw_state.halign.to_int() as f64))
+}
+
+fn draw_get_valign(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(app.draw_state.valign.to_int() as f64))
+}
+
+fn draw_text(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let params: TextParams = TextParams::new(&app.draw_state);
+    app.draw_text_with(arg_real(arguments, 0)?, arg_real(arguments, 1)?, &arg_text(app, arguments, 2)?, &params);
+    Ok(Value::Undefined)
+}
+
+fn draw_text_ext(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let params: TextParams = TextParams {
+        separation: arg_real(arguments, 3)?,
+        max_width: arg_real(arguments, 4)?,
+        ..TextParams::new(&app.draw_state)
+    };
+    app.draw_text_with(arg_real(arguments, 0)?, arg_real(arguments, 1)?, &arg_text(app, arguments, 2)?, &params);
+    Ok(Value::Undefined)
+}
+
+fn draw_text_transformed(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let params: TextParams = TextParams {
+        xscale: arg_real(arguments, 3)?,
+        yscale: arg_real(arguments, 4)?,
+        angle: arg_real(arguments, 5)?,
+        ..TextParams::new(&app.draw_state)
+    };
+    app.draw_text_with(arg_real(arguments, 0)?, arg_real(arguments, 1)?, &arg_text(app, arguments, 2)?, &params);
+    Ok(Value::Undefined)
+}
+
+fn draw_text_ext_transformed(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let params: TextParams = TextParams {
+        separation: arg_real(arguments, 3)?,
+        max_width: arg_real(arguments, 4)?,
+        xscale: arg_real(arguments, 5)?,
+        yscale: arg_real(arguments, 6)?,
+        angle: arg_real(arguments, 7)?,
+        ..TextParams::new(&app.draw_state)
+    };
+    app.draw_text_with(arg_real(arguments, 0)?, arg_real(arguments, 1)?, &arg_text(app, arguments, 2)?, &params);
+    Ok(Value::Undefined)
+}
+
+fn draw_text_colour(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let params: TextParams = TextParams {
+        colors: [arg_color(arguments, 3)?, arg_color(arguments, 4)?, arg_color(arguments, 5)?, arg_color(arguments, 6)?],
+        alpha: arg_real(arguments, 7)?,
+        ..TextParams::new(&app.draw_state)
+    };
+    app.draw_text_with(arg_real(arguments, 0)?, arg_real(arguments, 1)?, &arg_text(app, arguments, 2)?, &params);
+    Ok(Value::Undefined)
+}
+
+fn draw_text_ext_colour(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let params: TextParams = TextParams {
+        separation: arg_real(arguments, 3)?,
+        max_width: arg_real(arguments, 4)?,
+        colors: [arg_color(arguments, 5)?, arg_color(arguments, 6)?, arg_color(arguments, 7)?, arg_color(arguments, 8)?],
+        alpha: arg_real(arguments, 9)?,
+        ..TextParams::new(&app.draw_state)
+    };
+    app.draw_text_with(arg_real(arguments, 0)?, arg_real(arguments, 1)?, &arg_text(app, arguments, 2)?, &params);
+    Ok(Value::Undefined)
+}
+
+fn string_width(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(app.text_size(&arg_text(app, arguments, 0)?, -1.0, -1.0).0))
+}
+
+fn string_height(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(app.text_size(&arg_text(app, arguments, 0)?, -1.0, -1.0).1))
+}
+
+fn string_width_ext(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(app.text_size(&arg_text(app, arguments, 0)?, arg_real(arguments, 1)?, arg_real(arguments, 2)?).0))
+}
+
+fn string_height_ext(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(app.text_size(&arg_text(app, arguments, 0)?, arg_real(arguments, 1)?, arg_real(arguments, 2)?).1))
+}
@@ -0,0 +1,140 @@
+use crate::App;
+use crate::code::builtins::{arg_int, arg_real, argument, Arity, Builtins};
+use crate::code::value::Value;
+use crate::graphics::view::View;
+use crate::instance::NOONE;
+
+pub fn register(builtins: &mut Builtins) {
+    builtins.register("camera_get_view_x", Arity::Exact(1), camera_get_view_x);
+    builtins.register("camera_get_view_y", Arity::Exact(1), camera_get_view_y);
+    builtins.register("camera_get_view_width", Arity::Exact(1), camera_get_view_width);
+    builtins.register("camera_get_view_height", Arity::Exact(1), camera_get_view_height);
+    builtins.register("camera_get_view_target", Arity::Exact(1), camera_get_view_target);
+    builtins.register("camera_set_view_pos", Arity::Exact(3), camera_set_view_pos);
+    builtins.register("camera_set_view_size", Arity::Exact(3), camera_set_view_size);
+    builtins.register("camera_set_view_target", Arity::Exact(2), camera_set_view_target);
+    builtins.register("camera_set_view_border", Arity::Exact(3), camera_set_view_border);
+    builtins.register("camera_set_view_speed", Arity::Exact(3), camera_set_view_speed);
+    builtins.register("view_get_camera", Arity::Exact(1), view_get_camera);
+    builtins.register("view_get_visible", Arity::Exact(1), view_get_visible);
+    builtins.register("view_set_visible", Arity::Exact(2), view_set_visible);
+    builtins.register("view_get_xport", Arity::Exact(1), view_get_xport);
+    builtins.register("view_get_yport", Arity::Exact(1), view_get_yport);
+    builtins.register("view_get_wport", Arity::Exact(1), view_get_wport);
+    builtins.register("view_get_hport", Arity::Exact(1), view_get_hport);
+    builtins.register("window_get_width", Arity::Exact(0), window_get_width);
+    builtins.register("window_get_height", Arity::Exact(0), window_get_height);
+}
+
+/// Cameras and views share indices: camera `i` belongs to view `i`
+fn arg_view<'a>(app: &'a App, arguments: &[Value], index: usize) -> Result<&'a View, String> {
+    app.views.get(arg_int(arguments, index)?)
+}
+
+fn arg_view_mut<'a>(app: &'a mut App, arguments: &[Value], index: usize) -> Result<&'a mut View, String> {
+    app.views.get_mut(arg_int(arguments, index)?)
+}
+
+fn camera_get_view_x(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(arg_view(app, arguments, 0)?.x))
+}
+
+fn camera_get_view_y(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(arg_view(app, arguments, 0)?.y))
+}
+
+fn camera_get_view_width(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(arg_view(app, arguments, 0)?.width))
+}
+
+fn camera_get_view_height(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(arg_view(app, arguments, 0)?.height))
+}
+
+fn camera_get_view_target(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let target: Option<usize> = arg_view(app, arguments, 0)?.target;
+    Ok(Value::Real(target.map_or(NOONE as f64, |target| target as f64)))
+}
+
+fn camera_set_view_pos(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let x: f64 = arg_real(arguments, 1)?;
+    let y: f64 = arg_real(arguments, 2)?;
+    let view: &mut View = arg_view_mut(app, arguments, 0)?;
+    view.x = x;
+    view.y = y;
+    Ok(Value::Undefined)
+}
+
+fn camera_set_view_size(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let width: f64 = arg_real(arguments, 1)?;
+    let height: f64 = arg_real(arguments, 2)?;
+    let view: &mut View = arg_view_mut(app, arguments, 0)?;
+    view.width = width;
+    view.height = height;
+    Ok(Value::Undefined)
+}
+
+/// An instance ID or object index; `noone` stops following
+fn camera_set_view_target(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let target: Option<usize> = usize::try_from(arg_int(arguments, 1)?).ok();
+    arg_view_mut(app, arguments, 0)?.target = target;
+    Ok(Value::Undefined)
+}
+
+fn camera_set_view_border(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let border_x: f64 = arg_real(arguments, 1)?;
+    let border_y: f64 = arg_real(arguments, 2)?;
+    let view: &mut View = arg_view_mut(app, arguments, 0)?;
+    view.border_x = border_x;
+    view.border_y = border_y;
+    Ok(Value::Undefined)
+}
+
+fn camera_set_view_speed(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let speed_x: f64 = arg_real(arguments, 1)?;
+    let speed_y: f64 = arg_real(arguments, 2)?;
+    let view: &mut View = arg_view_mut(app, arguments, 0)?;
+    view.speed_x = speed_x;
+    view.speed_y = speed_y;
+    Ok(Value::Undefined)
+}
+
+fn view_get_camera(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let view_index: i64 = arg_int(arguments, 0)?;
+    app.views.get(view_index)?;
+    Ok(Value::Real(view_index as f64))
+}
+
+fn view_get_visible(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(arg_view(app, arguments, 0)?.visible))
+}
+
+fn view_set_visible(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    let visible: bool = argument(arguments, 1)?.to_bool().map_err(|e| format!("{e} for argument 1"))?;
+    arg_view_mut(app, arguments, 0)?.visible = visible;
+    Ok(Value::Undefined)
+}
+
+fn view_get_xport(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(arg_view(app, arguments, 0)?.port_x))
+}
+
+fn view_get_yport(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(arg_view(app, arguments, 0)?.port_y))
+}
+
+fn view_get_wport(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(arg_view(app, arguments, 0)?.port_width))
+}
+
+fn view_get_hport(app: &mut App, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(arg_view(app, arguments, 0)?.port_height))
+}
+
+fn window_get_width(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(f64::from(app.window_width)))
+}
+
+fn window_get_height(app: &mut App, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Real(f64::from(app.window_height)))
+}
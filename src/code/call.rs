@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use libgm::GMData;
+use libgm::gm::{GMFunction, GMRef};
+use crate::App;
+use crate::code::builtins::Builtin;
+use crate::code::value::Value;
+use crate::event::EventContext;
+use crate::instance::InstanceRef;
+
+/// What a `GMFunction` resolves to when it is called.
+#[derive(Debug, Clone)]
+pub enum FunctionTarget {
+    /// GMS1 script or GMS2.3 global function; contains the code index
+    Script(usize),
+    /// Anything not defined by the game itself; contains the function name
+    Builtin(String),
+}
+
+/// Everything belonging to one execution of a code entry.
+/// A new frame is pushed for every script call and event, so recursive scripts get their own locals.
+#[derive(Debug, Clone)]
+pub struct CallFrame {
+    pub code_index: usize,
+    pub locals: HashMap<usize, Value>,    // key: variable index
+    pub arguments: Vec<Value>,
+    pub self_instance: InstanceRef,
+    pub other_instance: InstanceRef,
+    /// Instruction index in the calling frame to continue at; `None` if the runner started this code directly
+    pub return_address: Option<usize>,
+    /// Currently active `with` blocks, innermost last
+    pub environments: Vec<Environment>,
+    /// Array reference kept aside by `savearef` until `restorearef`
+    pub saved_array: Option<Value>,
+    /// Set if this frame runs an object event rather than a script
+    pub event: Option<EventContext>,
+}
+impl CallFrame {
+    pub fn new(code_index: usize, self_instance: InstanceRef, other_instance: InstanceRef, arguments: Vec<Value>) -> Self {
+        Self {
+            code_index,
+            locals: HashMap::new(),
+            arguments,
+            self_instance,
+            other_instance,
+            return_address: None,
+            environments: Vec::new(),
+            saved_array: None,
+            event: None,
+        }
+    }
+}
+
+/// State of one `with` block
+#[derive(Debug, Clone)]
+pub struct Environment {
+    pub instances: Vec<InstanceRef>,
+    pub position: usize,
+    /// `self` and `other` from before entering the block, restored when leaving it
+    pub previous_self: InstanceRef,
+    pub previous_other: InstanceRef,
+}
+
+/// GameMaker's own limit is a lot higher, but we recurse on the native stack
+const MAX_CALL_DEPTH: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub struct CallStack {
+    pub frames: Vec<CallFrame>,
+}
+impl CallStack {
+    pub fn new() -> Self {
+        Self { frames: Vec::new() }
+    }
+    pub fn push(&mut self, frame: CallFrame) -> Result<(), String> {
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(format!("Call stack overflow: exceeded maximum call depth of {MAX_CALL_DEPTH}"))
+        }
+        self.frames.push(frame);
+        Ok(())
+    }
+    pub fn pop(&mut self) -> Result<CallFrame, String> {
+        self.frames.pop()
+            .ok_or_else(|| "Could not pop call frame because the call stack is empty".to_string())
+    }
+    pub fn current(&self) -> Result<&CallFrame, String> {
+        self.frames.last()
+            .ok_or_else(|| "There is no active call frame".to_string())
+    }
+    pub fn current_mut(&mut self) -> Result<&mut CallFrame, String> {
+        self.frames.last_mut()
+            .ok_or_else(|| "There is no active call frame".to_string())
+    }
+}
+
+
+/// Resolves every function in the FUNC chunk to either a script or a builtin.
+/// The returned list is indexed by function index.
+pub fn resolve_function_targets(data: &GMData) -> Result<Vec<FunctionTarget>, String> {
+    let strings: &Vec<String> = &data.strings.strings_by_index;
+
+    // GMS1 scripts and GMS2.3 script assets are referenced by their script name
+    let mut scripts: HashMap<&str, usize> = HashMap::new();
+    for script in &data.scripts.scripts_by_index {
+        let Some(code) = &script.code else { continue };
+        scripts.insert(script.name.resolve(strings)?, code.index);
+    }
+
+    // GMS2.3 global functions (and anonymous functions) are referenced by their code entry name
+    let mut codes: HashMap<&str, usize> = HashMap::new();
+    for (code_index, code) in data.codes.codes_by_index.iter().enumerate() {
+        let name: &str = code.name.resolve(strings)?;
+        if name.starts_with("gml_Script_") || name.starts_with("gml_GlobalScript_") {
+            codes.insert(name, code_index);
+        }
+    }
+
+    let mut targets: Vec<FunctionTarget> = Vec::with_capacity(data.functions.functions_by_index.len());
+    for function in &data.functions.functions_by_index {
+        let name: &str = function.name.resolve(strings)?;
+        let target: FunctionTarget = if let Some(code_index) = scripts.get(name) {
+            FunctionTarget::Script(*code_index)
+        } else if let Some(code_index) = codes.get(name) {
+            FunctionTarget::Script(*code_index)
+        } else if let Some(code_index) = codes.get(format!("gml_Script_{name}").as_str()) {
+            FunctionTarget::Script(*code_index)
+        } else {
+            FunctionTarget::Builtin(name.to_string())
+        };
+        targets.push(target);
+    }
+
+    Ok(targets)
+}
+
+
+impl App {
+    pub fn call_function(&mut self, function: &GMRef<GMFunction>, arguments_count: usize, return_address: usize) -> Result<Value, String> {
+        let arguments: Vec<Value> = self.pop_arguments(arguments_count)?;
+        // scripts inherit `self` and `other` from their caller
+        let caller: &CallFrame = self.call_stack.current()?;
+        let self_instance: InstanceRef = caller.self_instance.clone();
+        let other_instance: InstanceRef = caller.other_instance.clone();
+        self.call_function_index(function.index, self_instance, other_instance, arguments, Some(return_address))
+    }
+
+    /// `callv`: calls the function or method on top of the stack, with the instance below it as `self`.
+    pub fn call_variable(&mut self, arguments_count: usize, return_address: usize) -> Result<Value, String> {
+        let function: Value = self.stack.pop()?;
+        let instance: Value = self.stack.pop()?;
+        let arguments: Vec<Value> = self.pop_arguments(arguments_count)?;
+
+        let (function_index, bound_self): (usize, Option<InstanceRef>) = match &function {
+            Value::Method(method) => (method.function, method.bound_self.clone()),
+            other => (other.to_int64().map_err(|e| format!("{e} for function to call"))? as usize, None),
+        };
+        let self_instance: InstanceRef = match bound_self {
+            Some(bound_self) => bound_self,
+            None => self.instance_ref(&instance)?,
+        };
+        let other_instance: InstanceRef = self.call_stack.current()?.self_instance.clone();
+        self.call_function_index(function_index, self_instance, other_instance, arguments, Some(return_address))
+    }
+
+    /// Arguments are pushed in reverse order, so the first pop yields argument0
+    pub fn pop_arguments(&mut self, count: usize) -> Result<Vec<Value>, String> {
+        let mut arguments: Vec<Value> = Vec::with_capacity(count);
+        for _ in 0..count {
+            arguments.push(self.stack.pop()?);
+        }
+        Ok(arguments)
+    }
+
+    pub fn call_function_index(
+        &mut self,
+        function_index: usize,
+        self_instance: InstanceRef,
+        other_instance: InstanceRef,
+        arguments: Vec<Value>,
+        return_address: Option<usize>,
+    ) -> Result<Value, String> {
+        let target: FunctionTarget = self.functions.get(function_index).cloned()
+            .ok_or_else(|| format!("Function index {} out of bounds (length {})", function_index, self.functions.len()))?;
+
+        match target {
+            FunctionTarget::Script(code_index) => {
+                let mut frame = CallFrame::new(code_index, self_instance, other_instance, arguments);
+                frame.return_address = return_address;
+                let value: Option<Value> = self.run_code(frame)?;
+                Ok(value.unwrap_or(Value::Undefined))
+            }
+            FunctionTarget::Builtin(name) => self.call_builtin(&name, &arguments),
+        }
+    }
+
+    pub fn call_builtin(&mut self, name: &str, arguments: &[Value]) -> Result<Value, String> {
+        let Some(builtin): Option<Builtin> = self.builtins.get(name).copied() else {
+            if let Some(value) = self.read_async_load(name, arguments) {
+                return Ok(value)
+            }
+            return Err(format!("Builtin function \"{name}\" is not implemented (called with {} arguments)", arguments.len()))
+        };
+        if !builtin.arity.accepts(arguments.len()) {
+            return Err(format!("Builtin function \"{name}\" expects {:?} arguments but was called with {}", builtin.arity, arguments.len()))
+        }
+        (builtin.function)(self, arguments)
+            .map_err(|e| format!("{e}\n↳ while calling builtin function \"{name}\""))
+    }
+}
+
@@ -1,65 +1,24 @@
-use std::any::type_name_of_val;
-use libgm::gm::{GMDataType, GMValue};
+use libgm::gm::GMDataType;
 use crate::code::run::Stack;
-use std::convert::TryInto;
+use crate::code::value::{NumericType, Value};
 
 pub fn conv(stack: &mut Stack, target_data_type: GMDataType) -> Result<(), String> {
-    let old: GMValue = stack.pop()?;
-    let new: GMValue = match target_data_type {
-        GMDataType::Double => GMValue::Double(match old {
-            GMValue::Double(val) => val,
-            GMValue::Float(val) => f64::from(val),
-            GMValue::Int16(val) => f64::from(val),
-            GMValue::Int32(val) => f64::from(val),
-            GMValue::Int64(val) => f64::from(val as i32),
-            GMValue::Boolean(val) => f64::from(val),
-            other => return Err(format!("Invalid source conversion Data Type {other:?} while converting to Double"))
-        }),
-        GMDataType::Float => GMValue::Float(match old {
-            GMValue::Double(val) => val as f32,
-            GMValue::Float(val) => val,
-            GMValue::Int16(val) => f32::from(val),
-            GMValue::Int32(val) => val as f32,
-            GMValue::Int64(val) => val as f32,
-            GMValue::Boolean(val) => f32::from(val),
-            other => return Err(format!("Invalid source conversion Data Type {other:?} while converting to Float"))
-        }),
-        GMDataType::Int16 => GMValue::Int16(match old {
-            GMValue::Double(val) => val as i16,
-            GMValue::Float(val) => val as i16,
-            GMValue::Int16(val) => val,
-            GMValue::Int32(val) => val as i16,
-            GMValue::Int64(val) => val as i16,
-            GMValue::Boolean(val) => i16::from(val),
-            other => return Err(format!("Invalid source conversion Data Type {other:?} while converting to Int16"))
-        }),
-        GMDataType::Int32 => GMValue::Int32(match old {
-            GMValue::Double(val) => val as i32,
-            GMValue::Float(val) => val as i32,
-            GMValue::Int16(val) => i32::from(val),
-            GMValue::Int32(val) => val,
-            GMValue::Int64(val) => val as i32,
-            GMValue::Boolean(val) => i32::from(val),
-            other => return Err(format!("Invalid source conversion Data Type {other:?} while converting to Int32"))
-        }),
-        GMDataType::Int64 => GMValue::Int64(match old {
-            GMValue::Double(val) => val as i64,
-            GMValue::Float(val) => val as i64,
-            GMValue::Int16(val) => i64::from(val),
-            GMValue::Int32(val) => i64::from(val),
-            GMValue::Int64(val) => val,
-            GMValue::Boolean(val) => i64::from(val),
-            other => return Err(format!("Invalid source conversion Data Type {other:?} while converting to Int64"))
-        }),
-        GMDataType::Boolean => GMValue::Boolean(match old {
-            GMValue::Double(val) => val == 1.0,     // !!!! this is probably bad
-            GMValue::Float(val) => val == 1.0,      // !!!!
-            GMValue::Int16(val) => val == 1,
-            GMValue::Int32(val) => val == 1,
-            GMValue::Int64(val) => val == 1,
-            GMValue::Boolean(val) => val,
-            other => return Err(format!("Invalid source conversion Data Type {other:?} while converting to Boolean"))
-        }),
+    let old: Value = stack.pop()?;
+    let new: Value = match target_data_type {
+        GMDataType::Double | GMDataType::Float => Value::Real(old.to_real()
+            .map_err(|e| format!("{e} while converting to Double"))?),
+        GMDataType::Int16 | GMDataType::Int32 => Value::Int32(old.to_int32()
+            .map_err(|e| format!("{e} while converting to Int32"))?),
+        GMDataType::Int64 => Value::Int64(old.to_int64()
+            .map_err(|e| format!("{e} while converting to Int64"))?),
+        GMDataType::Boolean => Value::Bool(old.to_bool()
+            .map_err(|e| format!("{e} while converting to Boolean"))?),
+        GMDataType::String => match old {
+            Value::String(_) => old,
+            other => return Err(format!("Cannot implicitly convert {} to String", other.type_name())),
+        },
+        // variables can hold anything
+        GMDataType::Variable => old,
         other => return Err(format!("Invalid target conversion Data Type {other:?}"))
     };
     stack.push(new);
@@ -67,185 +26,195 @@ pub fn conv(stack: &mut Stack, target_data_type: GMDataType) -> Result<(), Strin
 }
 
 pub fn mul(stack: &mut Stack) -> Result<(), String> {
-    let lhs: GMValue = stack.pop()?;
-    let rhs: GMValue = stack.pop()?;
-    let result: GMValue = match (lhs, rhs) {
-        (GMValue::Double(a), GMValue::Double(b)) => GMValue::Double(a * b),
-        (GMValue::Float(a), GMValue::Float(b)) => GMValue::Float(a * b),
-        (GMValue::Int16(a), GMValue::Int16(b)) => GMValue::Int16(a * b),
-        (GMValue::Int32(a), GMValue::Int32(b)) => GMValue::Int32(a * b),
-        (GMValue::Int64(a), GMValue::Int64(b)) => GMValue::Int64(a * b),
-        (a, b) => return Err(format!("Cannot multiply {:?} with {:?}", a, b)),
+    let (lhs, rhs): (Value, Value) = stack.pop_operands()?;
+    let result: Value = match (&lhs, &rhs) {
+        // multiplying a string by a number repeats it
+        (Value::String(string), count) | (count, Value::String(string)) if count.is_number() => {
+            Value::string(string.repeat(count.to_int64()?.max(0) as usize))
+        }
+        _ => arithmetic(
+            &lhs, &rhs, "multiply",
+            |a, b| Ok(a.wrapping_mul(b)),
+            |a, b| Ok(a.wrapping_mul(b)),
+            |a, b| Ok(a * b),
+        )?,
     };
     stack.push(result);
     Ok(())
 }
 
-
 pub fn div(stack: &mut Stack) -> Result<(), String> {
-    let lhs: GMValue = stack.pop()?;
-    let rhs: GMValue = stack.pop()?;
-    let result: GMValue = match (lhs, rhs) {
-        (GMValue::Double(a), GMValue::Double(b)) => GMValue::Double(a / b),
-        (GMValue::Float(a), GMValue::Float(b)) => GMValue::Float(a / b),
-        (GMValue::Int16(a), GMValue::Int16(b)) => GMValue::Int16(safe_div(a, b)?),
-        (GMValue::Int32(a), GMValue::Int32(b)) => GMValue::Int32(safe_div(a, b)?),
-        (GMValue::Int64(a), GMValue::Int64(b)) => GMValue::Int64(safe_div(a, b)?),
-        (a, b) => return Err(format!("Cannot divide {:?} by {:?}", a, b)),
-    };
-    stack.push(result);
+    let (lhs, rhs): (Value, Value) = stack.pop_operands()?;
+    // division always yields a real in GameMaker
+    let a: f64 = lhs.to_real().map_err(|e| format!("Cannot divide: {e}"))?;
+    let b: f64 = rhs.to_real().map_err(|e| format!("Cannot divide: {e}"))?;
+    if b == 0.0 {
+        return Err(format!("Failed to divide {a} / {b}: Division by zero"))
+    }
+    stack.push(Value::Real(a / b));
     Ok(())
 }
 
-
+/// GML `div`: division truncated towards zero
 pub fn rem(stack: &mut Stack) -> Result<(), String> {
-    let lhs: GMValue = stack.pop()?;
-    let rhs: GMValue = stack.pop()?;
-    let result: GMValue = match (lhs, rhs) {
-        (GMValue::Double(a), GMValue::Double(b)) => GMValue::Double(a.rem_euclid(b)),
-        (GMValue::Float(a), GMValue::Float(b)) => GMValue::Float(a.rem_euclid(b)),
-        (GMValue::Int16(a), GMValue::Int16(b)) => GMValue::Int16(a.checked_rem(b).ok_or_else(|| format!("Failed to get remainder of {a} with divisor {b}: Division by zero"))?),
-        (GMValue::Int32(a), GMValue::Int32(b)) => GMValue::Int32(a.checked_rem(b).ok_or_else(|| format!("Failed to get remainder of {a} with divisor {b}: Division by zero"))?),
-        (GMValue::Int64(a), GMValue::Int64(b)) => GMValue::Int64(a.checked_rem(b).ok_or_else(|| format!("Failed to get remainder of {a} with divisor {b}: Division by zero"))?),
-        (a, b) => return Err(format!("Cannot get remainder of type {:?} with divisor type {:?}", a, b)),
-    };
+    let (lhs, rhs): (Value, Value) = stack.pop_operands()?;
+    let result: Value = arithmetic(
+        &lhs, &rhs, "integer divide",
+        |a, b| a.checked_div(b).ok_or_else(|| format!("Failed to divide {a} div {b}: Division by zero")),
+        |a, b| a.checked_div(b).ok_or_else(|| format!("Failed to divide {a} div {b}: Division by zero")),
+        |a, b| if b == 0.0 {
+            Err(format!("Failed to divide {a} div {b}: Division by zero"))
+        } else {
+            Ok((a / b).trunc())
+        },
+    )?;
     stack.push(result);
     Ok(())
 }
 
+/// GML `mod` / `%`: the result has the sign of the dividend
 pub fn mod_(stack: &mut Stack) -> Result<(), String> {
-    let lhs: GMValue = stack.pop()?;
-    let rhs: GMValue = stack.pop()?;
-    let result: GMValue = match (lhs, rhs) {
-        (GMValue::Double(a), GMValue::Double(b)) => GMValue::Double(a % b),
-        (GMValue::Float(a), GMValue::Float(b)) => GMValue::Float(a % b),
-        (GMValue::Int16(a), GMValue::Int16(b)) => GMValue::Int16(safe_mod(a, b)?),
-        (GMValue::Int32(a), GMValue::Int32(b)) => GMValue::Int32(safe_mod(a, b)?),
-        (GMValue::Int64(a), GMValue::Int64(b)) => GMValue::Int64(safe_mod(a, b)?),
-        (a, b) => return Err(format!("Cannot get modulus of type {:?} with divisor type {:?}", a, b)),
-    };
+    let (lhs, rhs): (Value, Value) = stack.pop_operands()?;
+    let result: Value = arithmetic(
+        &lhs, &rhs, "get modulus of",
+        |a, b| a.checked_rem(b).ok_or_else(|| format!("Failed to get the modulus {a} % {b}: Division by zero")),
+        |a, b| a.checked_rem(b).ok_or_else(|| format!("Failed to get the modulus {a} % {b}: Division by zero")),
+        |a, b| if b == 0.0 {
+            Err(format!("Failed to get the modulus {a} % {b}: Division by zero"))
+        } else {
+            Ok(a % b)
+        },
+    )?;
     stack.push(result);
     Ok(())
 }
 
 pub fn add(stack: &mut Stack) -> Result<(), String> {
-    let lhs: GMValue = stack.pop()?;
-    let rhs: GMValue = stack.pop()?;
-    let result: GMValue = match (lhs, rhs) {
-        (GMValue::Double(a), GMValue::Double(b)) => GMValue::Double(a + b),
-        (GMValue::Float(a), GMValue::Float(b)) => GMValue::Float(a + b),
-        (GMValue::Int16(a), GMValue::Int16(b)) => GMValue::Int16(a + b),
-        (GMValue::Int32(a), GMValue::Int32(b)) => GMValue::Int32(a + b),
-        (GMValue::Int64(a), GMValue::Int64(b)) => GMValue::Int64(a + b),
-        (a, b) => return Err(format!("Cannot add {:?} to {:?}", a, b)),
+    let (lhs, rhs): (Value, Value) = stack.pop_operands()?;
+    let result: Value = match (&lhs, &rhs) {
+        (Value::String(a), Value::String(b)) => Value::string(format!("{a}{b}")),
+        _ => arithmetic(
+            &lhs, &rhs, "add",
+            |a, b| Ok(a.wrapping_add(b)),
+            |a, b| Ok(a.wrapping_add(b)),
+            |a, b| Ok(a + b),
+        )?,
     };
     stack.push(result);
     Ok(())
 }
 
 pub fn sub(stack: &mut Stack) -> Result<(), String> {
-    let lhs: GMValue = stack.pop()?;
-    let rhs: GMValue = stack.pop()?;
-    let result: GMValue = match (lhs, rhs) {
-        (GMValue::Double(a), GMValue::Double(b)) => GMValue::Double(a - b),
-        (GMValue::Float(a), GMValue::Float(b)) => GMValue::Float(a - b),
-        (GMValue::Int16(a), GMValue::Int16(b)) => GMValue::Int16(a - b),
-        (GMValue::Int32(a), GMValue::Int32(b)) => GMValue::Int32(a - b),
-        (GMValue::Int64(a), GMValue::Int64(b)) => GMValue::Int64(a - b),
-        (a, b) => return Err(format!("Cannot subtract {:?} from {:?}", a, b)),
-    };
+    let (lhs, rhs): (Value, Value) = stack.pop_operands()?;
+    let result: Value = arithmetic(
+        &lhs, &rhs, "subtract",
+        |a, b| Ok(a.wrapping_sub(b)),
+        |a, b| Ok(a.wrapping_sub(b)),
+        |a, b| Ok(a - b),
+    )?;
     stack.push(result);
     Ok(())
 }
 
 pub fn and(stack: &mut Stack) -> Result<(), String> {
-    let lhs: GMValue = stack.pop()?;
-    let rhs: GMValue = stack.pop()?;
-    let result: GMValue = match (lhs, rhs) {
-        (GMValue::Int16(a), GMValue::Int16(b)) => GMValue::Int16(a & b),
-        (GMValue::Int32(a), GMValue::Int32(b)) => GMValue::Int32(a & b),
-        (GMValue::Int64(a), GMValue::Int64(b)) => GMValue::Int64(a & b),
-        (GMValue::Boolean(a), GMValue::Boolean(b)) => GMValue::Boolean(a & b),
-        (a, b) => return Err(format!("Cannot bitwise AND {:?} with {:?}", a, b)),
-    };
+    let (lhs, rhs): (Value, Value) = stack.pop_operands()?;
+    let result: Value = bitwise(&lhs, &rhs, "bitwise AND", |a, b| Ok(a & b), |a, b| Ok(a & b), |a, b| a & b)?;
     stack.push(result);
     Ok(())
 }
 
 pub fn or(stack: &mut Stack) -> Result<(), String> {
-    let lhs: GMValue = stack.pop()?;
-    let rhs: GMValue = stack.pop()?;
-    let result: GMValue = match (lhs, rhs) {
-        (GMValue::Int16(a), GMValue::Int16(b)) => GMValue::Int16(a | b),
-        (GMValue::Int32(a), GMValue::Int32(b)) => GMValue::Int32(a | b),
-        (GMValue::Int64(a), GMValue::Int64(b)) => GMValue::Int64(a | b),
-        (GMValue::Boolean(a), GMValue::Boolean(b)) => GMValue::Boolean(a | b),
-        (a, b) => return Err(format!("Cannot bitwise OR {:?} with {:?}", a, b)),
-    };
+    let (lhs, rhs): (Value, Value) = stack.pop_operands()?;
+    let result: Value = bitwise(&lhs, &rhs, "bitwise OR", |a, b| Ok(a | b), |a, b| Ok(a | b), |a, b| a | b)?;
     stack.push(result);
     Ok(())
 }
 
 pub fn xor(stack: &mut Stack) -> Result<(), String> {
-    let lhs: GMValue = stack.pop()?;
-    let rhs: GMValue = stack.pop()?;
-    let result: GMValue = match (lhs, rhs) {
-        (GMValue::Int16(a), GMValue::Int16(b)) => GMValue::Int16(a ^ b),
-        (GMValue::Int32(a), GMValue::Int32(b)) => GMValue::Int32(a ^ b),
-        (GMValue::Int64(a), GMValue::Int64(b)) => GMValue::Int64(a ^ b),
-        (GMValue::Boolean(a), GMValue::Boolean(b)) => GMValue::Boolean(a ^ b),
-        (a, b) => return Err(format!("Cannot bitwise XOR {:?} with {:?}", a, b)),
-    };
+    let (lhs, rhs): (Value, Value) = stack.pop_operands()?;
+    let result: Value = bitwise(&lhs, &rhs, "bitwise XOR", |a, b| Ok(a ^ b), |a, b| Ok(a ^ b), |a, b| a ^ b)?;
     stack.push(result);
     Ok(())
 }
 
 pub fn shl(stack: &mut Stack) -> Result<(), String> {
-    let lhs: GMValue = stack.pop()?;
-    let rhs: GMValue = stack.pop()?;
-    let result: GMValue = match (lhs, rhs) {
-        (GMValue::Int16(a), GMValue::Int16(b)) => GMValue::Int16(safe_shift_left(a, b as u32)?),
-        (GMValue::Int16(a), GMValue::Int32(b)) => GMValue::Int16(safe_shift_left(a, b as u32)?),
-        (GMValue::Int16(a), GMValue::Int64(b)) => GMValue::Int16(safe_shift_left(a, b as u32)?),
-        (GMValue::Int32(a), GMValue::Int16(b)) => GMValue::Int32(safe_shift_left(a, b as u32)?),
-        (GMValue::Int32(a), GMValue::Int32(b)) => GMValue::Int32(safe_shift_left(a, b as u32)?),
-        (GMValue::Int32(a), GMValue::Int64(b)) => GMValue::Int32(safe_shift_left(a, b as u32)?),
-        (GMValue::Int64(a), GMValue::Int16(b)) => GMValue::Int64(safe_shift_left(a, b as u32)?),
-        (GMValue::Int64(a), GMValue::Int32(b)) => GMValue::Int64(safe_shift_left(a, b as u32)?),
-        (GMValue::Int64(a), GMValue::Int64(b)) => GMValue::Int64(safe_shift_left(a, b as u32)?),
-        (GMValue::Boolean(a), GMValue::Boolean(b)) => GMValue::Boolean(a ^ b),
-        (a, b) => return Err(format!("Cannot left-bitshift type {:?} by type {:?}", a, b)),
-    };
+    let (lhs, rhs): (Value, Value) = stack.pop_operands()?;
+    let result: Value = shift(
+        &lhs, &rhs, "left-bitshift",
+        |a, b| safe_shift_left(a, b as u32),
+        |a, b| safe_shift_left(a, b as u32),
+    )?;
     stack.push(result);
     Ok(())
 }
 
 pub fn shr(stack: &mut Stack) -> Result<(), String> {
-    let lhs: GMValue = stack.pop()?;
-    let rhs: GMValue = stack.pop()?;
-    let result: GMValue = match (lhs, rhs) {
-        (GMValue::Int16(a), GMValue::Int16(b)) => GMValue::Int16(safe_shift_right(a, b as u32)?),
-        (GMValue::Int16(a), GMValue::Int32(b)) => GMValue::Int16(safe_shift_right(a, b as u32)?),
-        (GMValue::Int16(a), GMValue::Int64(b)) => GMValue::Int16(safe_shift_right(a, b as u32)?),
-        (GMValue::Int32(a), GMValue::Int16(b)) => GMValue::Int32(safe_shift_right(a, b as u32)?),
-        (GMValue::Int32(a), GMValue::Int32(b)) => GMValue::Int32(safe_shift_right(a, b as u32)?),
-        (GMValue::Int32(a), GMValue::Int64(b)) => GMValue::Int32(safe_shift_right(a, b as u32)?),
-        (GMValue::Int64(a), GMValue::Int16(b)) => GMValue::Int64(safe_shift_right(a, b as u32)?),
-        (GMValue::Int64(a), GMValue::Int32(b)) => GMValue::Int64(safe_shift_right(a, b as u32)?),
-        (GMValue::Int64(a), GMValue::Int64(b)) => GMValue::Int64(safe_shift_right(a, b as u32)?),
-        (GMValue::Boolean(a), GMValue::Boolean(b)) => GMValue::Boolean(a ^ b),
-        (a, b) => return Err(format!("Cannot right-bitshift type {:?} by type {:?}", a, b)),
-    };
+    let (lhs, rhs): (Value, Value) = stack.pop_operands()?;
+    let result: Value = shift(
+        &lhs, &rhs, "right-bitshift",
+        |a, b| safe_shift_right(a, b as u32),
+        |a, b| safe_shift_right(a, b as u32),
+    )?;
     stack.push(result);
     Ok(())
 }
 
-fn safe_div<T: num_traits::ops::checked::CheckedDiv + std::fmt::Display>(lhs: T, rhs: T) -> Result<T, String> {
-    lhs.checked_div(&rhs).ok_or_else(|| format!("Failed to divide {lhs} / {rhs}: Division by zero"))
+
+/// Promotes both operands to their common numeric type and applies the matching operation.
+fn arithmetic(
+    lhs: &Value,
+    rhs: &Value,
+    verb: &str,
+    int32: fn(i32, i32) -> Result<i32, String>,
+    int64: fn(i64, i64) -> Result<i64, String>,
+    real: fn(f64, f64) -> Result<f64, String>,
+) -> Result<Value, String> {
+    let numeric_type: NumericType = NumericType::promote(lhs, rhs)
+        .ok_or_else(|| format!("Cannot {verb} {} and {}", lhs.type_name(), rhs.type_name()))?;
+    Ok(match numeric_type {
+        NumericType::Int32 => Value::Int32(int32(lhs.to_int32()?, rhs.to_int32()?)?),
+        NumericType::Int64 => Value::Int64(int64(lhs.to_int64()?, rhs.to_int64()?)?),
+        NumericType::Real => Value::Real(real(lhs.to_real()?, rhs.to_real()?)?),
+    })
+}
+
+/// Bitwise operations work on integers; reals are converted to 64-bit integers first.
+fn bitwise(
+    lhs: &Value,
+    rhs: &Value,
+    verb: &str,
+    int32: fn(i32, i32) -> Result<i32, String>,
+    int64: fn(i64, i64) -> Result<i64, String>,
+    boolean: fn(bool, bool) -> bool,
+) -> Result<Value, String> {
+    if let (Value::Bool(a), Value::Bool(b)) = (lhs, rhs) {
+        return Ok(Value::Bool(boolean(*a, *b)))
+    }
+    let numeric_type: NumericType = NumericType::promote(lhs, rhs)
+        .ok_or_else(|| format!("Cannot {verb} {} with {}", lhs.type_name(), rhs.type_name()))?;
+    Ok(match numeric_type {
+        NumericType::Int32 => Value::Int32(int32(lhs.to_int32()?, rhs.to_int32()?)?),
+        NumericType::Int64 | NumericType::Real => Value::Int64(int64(lhs.to_int64()?, rhs.to_int64()?)?),
+    })
 }
 
-fn safe_mod<T: num_traits::ops::checked::CheckedRem + std::fmt::Display>(lhs: T, rhs: T) -> Result<T, String> {
-    lhs.checked_rem(&rhs).ok_or_else(|| format!("Failed to get the modulus {lhs} % {rhs}: Division by zero"))
+/// Shifts treat booleans as the numbers 0 and 1; the result keeps the integer width of the shifted value.
+fn shift(
+    lhs: &Value,
+    rhs: &Value,
+    verb: &str,
+    int32: fn(i32, i64) -> Result<i32, String>,
+    int64: fn(i64, i64) -> Result<i64, String>,
+) -> Result<Value, String> {
+    let amount: i64 = rhs.to_int64().map_err(|e| format!("Cannot {verb} by {}: {e}", rhs.type_name()))?;
+    if amount < 0 {
+        return Err(format!("Cannot {verb} by negative amount {amount}"))
+    }
+    Ok(match lhs {
+        Value::Int32(val) => Value::Int32(int32(*val, amount)?),
+        other if other.is_number() => Value::Int64(int64(other.to_int64()?, amount)?),
+        other => return Err(format!("Cannot {verb} {}", other.type_name())),
+    })
 }
 
 fn safe_shift_left<T: num_traits::ops::checked::CheckedShl + std::fmt::Display>(lhs: T, rhs: u32) -> Result<T, String> {
@@ -255,3 +224,184 @@ fn safe_shift_left<T: num_traits::ops::checked::CheckedShl + std::fmt::Display>(
 fn safe_shift_right<T: num_traits::ops::checked::CheckedShr + std::fmt::Display>(lhs: T, rhs: u32) -> Result<T, String> {
     lhs.checked_shr(rhs).ok_or_else(|| format!("Failed to bitshift right {lhs} >> {rhs}: Result overflowed"))
 }
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libgm::gm::GMComparisonType;
+    use crate::code::instructions::other::cmp;
+    use crate::code::value::DEFAULT_EPSILON;
+    use crate::code::value::Value::{Bool, Int32 as I32, Int64 as I64, Real, Undefined};
+
+    type Instruction = fn(&mut Stack) -> Result<(), String>;
+
+    fn s(string: &str) -> Value {
+        Value::string(string)
+    }
+
+    /// Same type and same payload; `Value` has no `PartialEq` since arrays and structs compare by reference
+    fn same(a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::Real(a), Value::Real(b)) => a == b,
+            (Value::Int32(a), Value::Int32(b)) => a == b,
+            (Value::Int64(a), Value::Int64(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Undefined, Value::Undefined) => true,
+            _ => false,
+        }
+    }
+
+    fn run(instruction: Instruction, lhs: Value, rhs: Value) -> Result<Value, String> {
+        let mut stack: Stack = Stack { items: vec![lhs, rhs] };
+        instruction(&mut stack)?;
+        assert_eq!(stack.items.len(), 1, "the operands should be replaced by the result");
+        stack.pop()
+    }
+
+    /// Every case is `(name, instruction, lhs, rhs, expected)`; `None` means the instruction has to fail
+    fn check(cases: Vec<(&str, Instruction, Value, Value, Option<Value>)>) {
+        for (name, instruction, lhs, rhs, expected) in cases {
+            let description: String = format!("{name} {lhs:?} {rhs:?}");
+            match (run(instruction, lhs, rhs), expected) {
+                (Ok(result), Some(expected)) => assert!(same(&result, &expected), "{description}: expected {expected:?}, got {result:?}"),
+                (Ok(result), None) => panic!("{description}: expected an error, got {result:?}"),
+                (Err(e), Some(expected)) => panic!("{description}: expected {expected:?}, got error {e}"),
+                (Err(_), None) => {}
+            }
+        }
+    }
+
+    #[test]
+    fn add_and_sub() {
+        check(vec![
+            ("add", add, I32(2), I32(3), Some(I32(5))),
+            ("add", add, I32(2), I64(3), Some(I64(5))),
+            ("add", add, I64(2), Real(0.5), Some(Real(2.5))),
+            ("add", add, I32(2), Bool(true), Some(Real(3.0))),
+            ("add", add, Bool(true), Bool(true), Some(Real(2.0))),
+            ("add", add, I32(i32::MAX), I32(1), Some(I32(i32::MIN))),
+            ("add", add, s("a"), s("b"), Some(s("ab"))),
+            ("add", add, s("a"), I32(1), None),
+            ("add", add, Undefined, Real(1.0), None),
+            ("sub", sub, I32(5), I32(7), Some(I32(-2))),
+            ("sub", sub, I64(5), I32(7), Some(I64(-2))),
+            ("sub", sub, Real(1.5), I32(1), Some(Real(0.5))),
+            ("sub", sub, Bool(false), Real(1.0), Some(Real(-1.0))),
+            ("sub", sub, s("a"), s("b"), None),
+        ]);
+    }
+
+    #[test]
+    fn mul_and_div() {
+        check(vec![
+            ("mul", mul, I32(6), I32(7), Some(I32(42))),
+            ("mul", mul, I32(6), I64(7), Some(I64(42))),
+            ("mul", mul, Real(1.5), I64(2), Some(Real(3.0))),
+            ("mul", mul, Bool(true), I32(4), Some(Real(4.0))),
+            ("mul", mul, s("ab"), I32(3), Some(s("ababab"))),
+            ("mul", mul, I32(2), s("x"), Some(s("xx"))),
+            ("mul", mul, s("ab"), Real(-1.0), Some(s(""))),
+            ("mul", mul, s("a"), s("b"), None),
+            ("div", div, I32(7), I32(2), Some(Real(3.5))),
+            ("div", div, I64(1), Bool(true), Some(Real(1.0))),
+            ("div", div, I32(1), I32(0), None),
+            ("div", div, s("4"), I32(2), None),
+        ]);
+    }
+
+    #[test]
+    fn rem_and_mod() {
+        check(vec![
+            ("rem", rem, I32(7), I32(2), Some(I32(3))),
+            ("rem", rem, I32(-7), I32(2), Some(I32(-3))),
+            ("rem", rem, I64(7), I32(2), Some(I64(3))),
+            ("rem", rem, Real(7.5), Real(2.0), Some(Real(3.0))),
+            ("rem", rem, Real(-7.5), I32(2), Some(Real(-3.0))),
+            ("rem", rem, I32(1), I32(0), None),
+            ("rem", rem, Real(1.0), Real(0.0), None),
+            ("rem", rem, s("7"), I32(2), None),
+            ("mod", mod_, I32(7), I32(3), Some(I32(1))),
+            ("mod", mod_, I32(-7), I32(3), Some(I32(-1))),
+            ("mod", mod_, I64(7), I64(-3), Some(I64(1))),
+            ("mod", mod_, Real(5.5), Real(2.0), Some(Real(1.5))),
+            ("mod", mod_, Bool(true), I32(1), Some(Real(0.0))),
+            ("mod", mod_, I32(1), I32(0), None),
+            ("mod", mod_, Real(1.0), Real(0.0), None),
+            ("mod", mod_, Undefined, I32(2), None),
+        ]);
+    }
+
+    #[test]
+    fn bitwise_operations() {
+        check(vec![
+            ("and", and, I32(6), I32(3), Some(I32(2))),
+            ("and", and, I32(6), I64(3), Some(I64(2))),
+            ("and", and, Real(6.4), I32(3), Some(I64(2))),
+            ("and", and, Bool(true), Bool(false), Some(Bool(false))),
+            ("and", and, Bool(true), I32(1), Some(I64(1))),
+            ("and", and, s("6"), I32(3), None),
+            ("or", or, I32(4), I32(1), Some(I32(5))),
+            ("or", or, Real(2.0), I64(1), Some(I64(3))),
+            ("or", or, Bool(false), Bool(true), Some(Bool(true))),
+            ("or", or, Undefined, I32(1), None),
+            ("xor", xor, I32(6), I32(3), Some(I32(5))),
+            ("xor", xor, I64(6), Real(3.0), Some(I64(5))),
+            ("xor", xor, Bool(true), Bool(true), Some(Bool(false))),
+            ("xor", xor, I32(1), s("1"), None),
+        ]);
+    }
+
+    #[test]
+    fn shifts() {
+        check(vec![
+            ("shl", shl, I32(1), I32(4), Some(I32(16))),
+            ("shl", shl, I64(1), I32(40), Some(I64(1 << 40))),
+            ("shl", shl, Real(1.0), I32(3), Some(I64(8))),
+            ("shl", shl, Bool(true), Real(2.0), Some(I64(4))),
+            ("shl", shl, I32(1), I32(-1), None),
+            ("shl", shl, I32(1), I32(32), None),
+            ("shl", shl, s("1"), I32(1), None),
+            ("shl", shl, I32(1), s("1"), None),
+            ("shr", shr, I32(16), I32(2), Some(I32(4))),
+            ("shr", shr, I32(-16), I32(2), Some(I32(-4))),
+            ("shr", shr, I64(1 << 40), Real(40.0), Some(I64(1))),
+            ("shr", shr, I64(1), I32(64), None),
+            ("shr", shr, Undefined, I32(1), None),
+        ]);
+    }
+
+    #[test]
+    fn comparisons() {
+        use GMComparisonType::{EQ, GTE, LT, NEQ};
+        let cases: Vec<(Value, GMComparisonType, Value, Option<bool>)> = vec![
+            (I32(1), LT, I64(2), Some(true)),
+            (Real(1.0), EQ, I32(1), Some(true)),
+            (Real(1.000001), EQ, Real(1.0), Some(true)),
+            (Real(1.1), EQ, Real(1.0), Some(false)),
+            (Bool(true), EQ, Real(1.0), Some(true)),
+            (I64(3), GTE, Bool(true), Some(true)),
+            (s("a"), LT, s("b"), Some(true)),
+            (s("b"), GTE, s("a"), Some(true)),
+            (s("1"), EQ, I32(1), Some(false)),
+            (s("1"), NEQ, I32(1), Some(true)),
+            (Undefined, EQ, Undefined, Some(true)),
+            (s("1"), LT, I32(1), None),
+            (Undefined, LT, Real(0.0), None),
+        ];
+        for (lhs, comparison_type, rhs, expected) in cases {
+            let description: String = format!("{lhs:?} {comparison_type:?} {rhs:?}");
+            let mut stack: Stack = Stack { items: vec![lhs, rhs] };
+            let result: Result<bool, String> = cmp(&mut stack, comparison_type, DEFAULT_EPSILON)
+                .and_then(|_| stack.pop()?.to_bool());
+            match (result, expected) {
+                (Ok(result), Some(expected)) => assert_eq!(result, expected, "{description}"),
+                (Ok(result), None) => panic!("{description}: expected an error, got {result}"),
+                (Err(e), Some(expected)) => panic!("{description}: expected {expected}, got error {e}"),
+                (Err(_), None) => {}
+            }
+        }
+    }
+}
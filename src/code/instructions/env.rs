@@ -0,0 +1,52 @@
+use crate::App;
+use crate::code::call::{CallFrame, Environment};
+use crate::code::value::Value;
+use crate::instance::InstanceRef;
+
+impl App {
+    /// Enters a `with` block; returns false if there is no instance to run it for.
+    pub fn push_env(&mut self) -> Result<bool, String> {
+        let target: Value = self.stack.pop()?;
+        let frame: &mut CallFrame = self.call_stack.current_mut()?;
+        let instances: Vec<InstanceRef> = self.instances.resolve_target(&target, &frame.self_instance, &frame.other_instance)
+            .map_err(|e| format!("{e} for the target of a with statement"))?;
+        let Some(first) = instances.first().cloned() else { return Ok(false) };
+
+        let previous_self: InstanceRef = std::mem::replace(&mut frame.self_instance, first);
+        let previous_other: InstanceRef = std::mem::replace(&mut frame.other_instance, previous_self.clone());
+        // inside the block, `other` is the instance which executed the `with`
+        frame.environments.push(Environment {
+            instances,
+            position: 0,
+            previous_self,
+            previous_other,
+        });
+        Ok(true)
+    }
+
+    /// Moves on to the next instance of the innermost `with` block.
+    /// Returns true if there is one and the block has to be run again.
+    pub fn pop_env(&mut self) -> Result<bool, String> {
+        let frame: &mut CallFrame = self.call_stack.current_mut()?;
+        let environment: &mut Environment = frame.environments.last_mut()
+            .ok_or_else(|| "PopEnv without a matching PushEnv".to_string())?;
+        environment.position += 1;
+        if let Some(next) = environment.instances.get(environment.position) {
+            frame.self_instance = next.clone();
+            return Ok(true)
+        }
+        self.exit_env()?;
+        Ok(false)
+    }
+
+    /// Leaves the innermost `with` block, restoring `self` and `other`.
+    pub fn exit_env(&mut self) -> Result<(), String> {
+        let frame: &mut CallFrame = self.call_stack.current_mut()?;
+        let environment: Environment = frame.environments.pop()
+            .ok_or_else(|| "PopEnv without a matching PushEnv".to_string())?;
+        frame.self_instance = environment.previous_self;
+        frame.other_instance = environment.previous_other;
+        Ok(())
+    }
+}
+
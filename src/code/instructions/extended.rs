@@ -0,0 +1,106 @@
+use crate::App;
+use crate::code::array::{container_element, get_element, set_element};
+use crate::code::structs::Struct;
+use crate::code::value::Value;
+
+/// GMS2.3 encodes its newer instructions as `break` with a negative value
+const CHKINDEX: i16 = -1;
+const PUSHAF: i16 = -2;
+const POPAF: i16 = -3;
+const PUSHAC: i16 = -4;
+const SETOWNER: i16 = -5;
+const ISSTATICOK: i16 = -6;
+const SETSTATIC: i16 = -7;
+const SAVEAREF: i16 = -8;
+const RESTOREAREF: i16 = -9;
+const CHKNULLISH: i16 = -10;
+const PUSHREF: i16 = -11;
+
+/// `pushref` packs the asset type into the upper 8 bits and the asset index into the lower 24 bits
+const ASSET_INDEX_MASK: i32 = 0xFFFFFF;
+
+impl App {
+    pub fn execute_break(&mut self, value: i16, int_argument: Option<i32>) -> Result<(), String> {
+        match value {
+            // checks the array index on top of the stack without popping it
+            CHKINDEX => {
+                let index: Value = self.stack.peek()?;
+                if !index.is_number() {
+                    return Err(format!("Array index has to be a number, got {}", index.type_name()))
+                }
+                let index: i64 = index.to_int64()?;
+                if index < 0 {
+                    return Err(format!("Negative array index {index}"))
+                }
+            }
+            // read `array[index]` where the array itself is on the stack (`a[i][j]`)
+            PUSHAF => {
+                let index: Value = self.stack.pop()?;
+                let array: Value = self.stack.pop()?;
+                let element: Value = get_element(&array, &index, false, "<array>")?;
+                self.stack.push(element);
+            }
+            // write `array[index] = value` where the array itself is on the stack
+            POPAF => {
+                let index: Value = self.stack.pop()?;
+                let mut array: Value = self.stack.pop()?;
+                let value: Value = self.stack.pop()?;
+                if !matches!(array, Value::Array(_)) {
+                    return Err(format!("Cannot set element {index} of {} because it is not an array", array.type_name()))
+                }
+                set_element(&mut array, &index, value, false, "<array>")?;
+            }
+            // push the nested array at `array[index]`, creating it if it does not exist yet
+            PUSHAC => {
+                let index: Value = self.stack.pop()?;
+                let mut array: Value = self.stack.pop()?;
+                if !matches!(array, Value::Array(_)) {
+                    return Err(format!("Cannot access element {index} of {} because it is not an array", array.type_name()))
+                }
+                let container: Value = container_element(&mut array, &index, "<array>")?;
+                self.stack.push(container);
+            }
+            // sets the owner of the next array for copy-on-write, which GMS2.3 arrays do not do
+            SETOWNER => {
+                self.stack.pop()?;
+            }
+            // whether the static variables of the current function were already initialized
+            ISSTATICOK => {
+                let code_index: usize = self.call_stack.current()?.code_index;
+                let initialized: bool = self.statics.contains_key(&code_index);
+                if !initialized {
+                    self.statics.insert(code_index, Struct::new_ref(None));
+                }
+                self.stack.push(Value::Bool(initialized));
+            }
+            SETSTATIC => {
+                let code_index: usize = self.call_stack.current()?.code_index;
+                self.statics.entry(code_index).or_insert_with(|| Struct::new_ref(None));
+            }
+            // compound assignments like `a[i] += 1` keep the array aside while computing the new value
+            SAVEAREF => {
+                let array: Value = self.stack.pop()?;
+                self.call_stack.current_mut()?.saved_array = Some(array);
+            }
+            RESTOREAREF => {
+                let array: Value = self.call_stack.current_mut()?.saved_array.take()
+                    .ok_or_else(|| "Cannot restore array reference because none was saved".to_string())?;
+                self.stack.push(array);
+            }
+            // `??`: pushes whether the value on top of the stack is undefined, leaving the value itself there
+            CHKNULLISH => {
+                let value: Value = self.stack.peek()?;
+                self.stack.push(Value::Bool(matches!(value, Value::Undefined)));
+            }
+            // references to assets (and functions) are pushed as their index
+            PUSHREF => {
+                let reference: i32 = int_argument
+                    .ok_or_else(|| "Break instruction pushref is missing its asset reference".to_string())?;
+                self.stack.push(Value::Real(f64::from(reference & ASSET_INDEX_MASK)));
+            }
+            other => return Err(format!("Unknown extended opcode: Break instruction with value {other}")),
+        }
+        Ok(())
+    }
+}
+
@@ -1,3 +1,5 @@
 pub mod single_type;
 pub mod double_type;
 pub mod other;
+pub mod env;
+pub mod extended;
@@ -1,32 +1,36 @@
-use libgm::gm::{GMCodeVariable, GMComparisonType, GMInstanceType, GMValue};
-use crate::code::run::{Stack, Variables};
-
-pub fn cmp(stack: &mut Stack, comparison_type: GMComparisonType) -> Result<(), String> {
-    let lhs: GMValue = stack.pop()?;
-    let rhs: GMValue = stack.pop()?;
-    
-    let result: bool = match (&lhs, &rhs) {
-        (GMValue::Double(a), GMValue::Double(b)) => compare(a, b, comparison_type),
-        (GMValue::Float(a), GMValue::Float(b)) => compare(a, b, comparison_type),
-        (GMValue::Int16(a), GMValue::Int16(b)) => compare(a, b, comparison_type),
-        (GMValue::Int32(a), GMValue::Int32(b)) => compare(a, b, comparison_type),
-        (GMValue::Int64(a), GMValue::Int64(b)) => compare(a, b, comparison_type),
-        (GMValue::Boolean(a), GMValue::Boolean(b)) => compare(a, b, comparison_type),
-        _ => return Err(format!("Cannot compare {lhs:?} and {rhs:?}"))
+use std::cmp::Ordering;
+use libgm::gm::{GMCodeVariable, GMComparisonType, GMDataType, GMInstanceType, GMVariableType};
+use crate::App;
+use crate::code::array::{container_element, get_element, set_element};
+use crate::code::call::CallFrame;
+use crate::code::run::Stack;
+use crate::code::structs::StructRef;
+use crate::code::value::Value;
+use crate::instance::{InstanceRef, FIRST_INSTANCE_ID, GLOBAL, LOCAL, OTHER, SELF};
+
+pub fn cmp(stack: &mut Stack, comparison_type: GMComparisonType, epsilon: f64) -> Result<(), String> {
+    let (lhs, rhs): (Value, Value) = stack.pop_operands()?;
+
+    let result: bool = match (lhs.compare(&rhs, epsilon), comparison_type) {
+        (Some(ordering), _) => compare(ordering, comparison_type),
+        // values of different types are never equal
+        (None, GMComparisonType::EQ) => false,
+        (None, GMComparisonType::NEQ) => true,
+        (None, _) => return Err(format!("Cannot compare {} and {}", lhs.type_name(), rhs.type_name())),
     };
 
-    stack.push(GMValue::Boolean(result));
+    stack.push(Value::Bool(result));
     Ok(())
 }
 
-fn compare<T: PartialOrd>(a: T, b: T, comparison_type: GMComparisonType) -> bool {
+fn compare(ordering: Ordering, comparison_type: GMComparisonType) -> bool {
     match comparison_type {
-        GMComparisonType::LT => a < b,
-        GMComparisonType::LTE => a <= b,
-        GMComparisonType::EQ => a == b,
-        GMComparisonType::NEQ => a != b,
-        GMComparisonType::GTE => a >= b,
-        GMComparisonType::GT => a > b,
+        GMComparisonType::LT => ordering == Ordering::Less,
+        GMComparisonType::LTE => ordering != Ordering::Greater,
+        GMComparisonType::EQ => ordering == Ordering::Equal,
+        GMComparisonType::NEQ => ordering != Ordering::Equal,
+        GMComparisonType::GTE => ordering != Ordering::Less,
+        GMComparisonType::GT => ordering == Ordering::Greater,
     }
 }
 
@@ -34,49 +38,285 @@ fn compare<T: PartialOrd>(a: T, b: T, comparison_type: GMComparisonType) -> bool
 
 /// returns whether to jump
 pub fn bt(stack: &mut Stack) -> Result<bool, String> {
-    let value: GMValue = stack.pop()?;
-    match value {
-        GMValue::Boolean(boolean) => Ok(boolean),
-        other => Err(format!("Expected boolean for jump condition, got {other:?}"))
-    }
+    let value: Value = stack.pop()?;
+    value.to_bool().map_err(|e| format!("{e} for jump condition"))
 }
 
 /// returns whether to jump
 pub fn bf(stack: &mut Stack) -> Result<bool, String> {
-    let value: GMValue = stack.pop()?;
-    match value {
-        GMValue::Boolean(boolean) => Ok(!boolean),
-        other => Err(format!("Expected boolean for jump condition, got {other:?}"))
+    let value: Value = stack.pop()?;
+    let boolean: bool = value.to_bool().map_err(|e| format!("{e} for jump condition"))?;
+    Ok(!boolean)
+}
+
+
+/// Where a variable lives once its instance type has been resolved
+#[derive(Debug, Clone)]
+pub enum Scope {
+    Global,
+    Local,
+    Instance(usize),
+    /// Every instance of an object when assigning; the first one when reading
+    Object(usize),
+    Struct(StructRef),
+}
+impl Scope {
+    pub fn of(instance: &InstanceRef) -> Self {
+        match instance {
+            InstanceRef::Instance(id) => Scope::Instance(*id),
+            InstanceRef::Struct(structure) => Scope::Struct(structure.clone()),
+        }
     }
 }
 
 
-pub fn pop(
-    variables: &mut Variables,
-    code_index: usize,
-    object_index: usize,
-    stack: &mut Stack,
-    instance_type: &GMInstanceType,
-    destination: &GMCodeVariable,
-) -> Result<(), String> {
-    let value: GMValue = stack.pop()?;
+impl App {
+    pub fn scope_of_instance_type(&self, instance_type: &GMInstanceType) -> Result<Scope, String> {
+        let frame: &CallFrame = self.call_stack.current()?;
+        Ok(match instance_type {
+            GMInstanceType::Instance(Some(obj)) => Scope::Object(obj.index),
+            GMInstanceType::Instance(None) => Scope::of(&frame.self_instance),
+            GMInstanceType::Other => Scope::of(&frame.other_instance),
+            GMInstanceType::Global => Scope::Global,
+            GMInstanceType::Local => Scope::Local,
+            // built-in variables like `argument0` or `room` are resolved relative to `self`
+            GMInstanceType::Builtin => Scope::of(&frame.self_instance),
+            GMInstanceType::Static => {
+                let statics: &StructRef = self.statics.get(&frame.code_index)
+                    .ok_or_else(|| "Static variables accessed before being initialized".to_string())?;
+                Scope::Struct(statics.clone())
+            }
+            other => return Err(format!("Invalid Instance Type {other:?}"))
+        })
+    }
+
+    /// Array accesses (and GMS2.3 `a.b` accesses) push their instance onto the stack instead
+    pub fn scope_of_stack_value(&self, value: &Value) -> Result<Scope, String> {
+        if let Value::Struct(structure) = value {
+            return Ok(Scope::Struct(structure.clone()))
+        }
+        let frame: &CallFrame = self.call_stack.current()?;
+        let value: i64 = value.to_int64().map_err(|e| format!("{e} for instance of variable access"))?;
+        Ok(match value {
+            SELF => Scope::of(&frame.self_instance),
+            OTHER => Scope::of(&frame.other_instance),
+            GLOBAL => Scope::Global,
+            LOCAL => Scope::Local,
+            id if id >= FIRST_INSTANCE_ID as i64 => Scope::Instance(id as usize),
+            object_index if object_index >= 0 => Scope::Object(object_index as usize),
+            other => return Err(format!("Invalid instance {other} for variable access")),
+        })
+    }
+
+    /// Resolves an instance value like `self`, `other` or an instance ID to what it refers to
+    pub fn instance_ref(&self, value: &Value) -> Result<InstanceRef, String> {
+        if let Value::Struct(structure) = value {
+            return Ok(InstanceRef::Struct(structure.clone()))
+        }
+        let frame: &CallFrame = self.call_stack.current()?;
+        let value: i64 = value.to_int64().map_err(|e| format!("{e} for instance"))?;
+        Ok(match value {
+            SELF => frame.self_instance.clone(),
+            OTHER => frame.other_instance.clone(),
+            id if id >= FIRST_INSTANCE_ID as i64 => InstanceRef::Instance(id as usize),
+            object_index if object_index >= 0 => {
+                let id: usize = *self.instances.ids_of_object(object_index as usize).first()
+                    .ok_or_else(|| format!("No instance of object {object_index} exists"))?;
+                InstanceRef::Instance(id)
+            }
+            other => return Err(format!("Invalid instance {other}")),
+        })
+    }
+
+    pub fn variable_name(&self, variable_index: usize) -> String {
+        self.data.variables.variables.get(variable_index)
+            .and_then(|variable| variable.name.resolve(&self.data.strings.strings_by_index).ok())
+            .cloned()
+            .unwrap_or_else(|| format!("<variable #{variable_index}>"))
+    }
+
+    /// Converts a value like `string()` does, with the names of struct variables
+    pub fn value_to_string(&self, value: &Value) -> String {
+        value.display(&|index| self.variable_name(index)).to_string()
+    }
+
+    /// Current value of a variable; `None` if it was never assigned.
+    pub fn read_variable(&self, scope: Scope, variable_index: usize) -> Result<Option<Value>, String> {
+        Ok(match scope {
+            Scope::Global => self.variables.globals.get(&variable_index).cloned(),
+            Scope::Local => self.call_stack.current()?.locals.get(&variable_index).cloned(),
+            Scope::Instance(id) => self.instances.get(id)?.variables.get(&variable_index).cloned(),
+            Scope::Object(object_index) => {
+                let id: usize = *self.instances.ids_of_object(object_index).first()
+                    .ok_or_else(|| format!("Cannot read variable of object {object_index} because no instance of it exists"))?;
+                self.instances.get(id)?.variables.get(&variable_index).cloned()
+            }
+            Scope::Struct(structure) => structure.borrow().variables.get(&variable_index).cloned(),
+        })
+    }
+
+    /// Calls `f` with the storage slot of a variable (with every instance's slot for object scope).
+    /// Slots of variables which were never assigned are created as undefined.
+    pub fn with_variable_slots(
+        &mut self,
+        scope: Scope,
+        variable_index: usize,
+        mut f: impl FnMut(&mut Value) -> Result<(), String>,
+    ) -> Result<(), String> {
+        match scope {
+            Scope::Global => f(self.variables.globals.entry(variable_index).or_insert(Value::Undefined)),
+            Scope::Local => f(self.call_stack.current_mut()?.locals.entry(variable_index).or_insert(Value::Undefined)),
+            Scope::Instance(id) => f(self.instances.get_mut(id)?.variables.entry(variable_index).or_insert(Value::Undefined)),
+            Scope::Object(object_index) => {
+                for id in self.instances.ids_of_object(object_index) {
+                    f(self.instances.get_mut(id)?.variables.entry(variable_index).or_insert(Value::Undefined))?;
+                }
+                Ok(())
+            }
+            Scope::Struct(structure) => f(structure.borrow_mut().variables.entry(variable_index).or_insert(Value::Undefined)),
+        }
+    }
+
+    pub fn write_variable(&mut self, scope: Scope, variable_index: usize, value: Value) -> Result<(), String> {
+        self.with_variable_slots(scope, variable_index, |slot| {
+            *slot = value.clone();
+            Ok(())
+        })
+    }
+
+    /// Error for reading a variable which was never assigned, worded like GameMaker's
+    fn uninitialized_variable(&self, scope: &Scope, variable_index: usize) -> String {
+        let name: String = self.variable_name(variable_index);
+        let owner: String = match scope {
+            Scope::Global => "global".to_string(),
+            Scope::Local => "local".to_string(),
+            Scope::Struct(_) => "struct".to_string(),
+            Scope::Instance(id) => match self.instances.get(*id) {
+                Ok(instance) => self.object_name(instance.object_index),
+                Err(_) => "<unknown_object>".to_string(),
+            },
+            Scope::Object(object_index) => self.object_name(*object_index),
+        };
+        format!("Variable {owner}.{name}({variable_index}, -1) not set before reading it.")
+    }
+
+    pub fn object_name(&self, object_index: usize) -> String {
+        self.data.game_objects.game_objects_by_index.get(object_index)
+            .and_then(|object| object.name.resolve(&self.data.strings.strings_by_index).ok())
+            .cloned()
+            .unwrap_or_else(|| "<unknown_object>".to_string())
+    }
+
+    /// Reads a variable through its scope, giving built-in variables precedence.
+    /// `builtin_scope`: whether the variable was accessed through the built-in instance type.
+    fn read_scoped(&self, scope: Scope, variable_index: usize, index: Option<&Value>, builtin_scope: bool) -> Result<Value, String> {
+        if let Some(value) = self.read_builtin_variable(variable_index, &scope, index, builtin_scope)? {
+            return Ok(value)
+        }
+        let value: Value = self.read_variable(scope.clone(), variable_index)?
+            .ok_or_else(|| self.uninitialized_variable(&scope, variable_index))?;
+        match index {
+            Some(index) => get_element(&value, index, self.legacy_arrays, &self.variable_name(variable_index)),
+            None => Ok(value),
+        }
+    }
 
-    match instance_type {
-        GMInstanceType::Instance(Some(obj)) => {
-            variables.instances.insert((destination.variable.index, obj.index), value);
+    /// Writes a variable through its scope, giving built-in variables precedence.
+    fn write_scoped(&mut self, scope: Scope, variable_index: usize, index: Option<&Value>, value: Value, builtin_scope: bool) -> Result<(), String> {
+        if self.write_builtin_variable(variable_index, &scope, index, value.clone(), builtin_scope)? {
+            return Ok(())
         }
-        GMInstanceType::Instance(None) => {
-            variables.instances.insert((destination.variable.index, object_index), value);
+        match index {
+            Some(index) => {
+                let name: String = self.variable_name(variable_index);
+                let legacy: bool = self.legacy_arrays;
+                self.with_variable_slots(scope, variable_index, |slot| {
+                    set_element(slot, index, value.clone(), legacy, &name)
+                })
+            }
+            None => self.write_variable(scope, variable_index, value),
         }
-        GMInstanceType::Global => {
-            variables.globals.insert(destination.variable.index, value);
+    }
+
+    pub fn pop_variable(
+        &mut self,
+        instance_type: &GMInstanceType,
+        destination: &GMCodeVariable,
+        value_type: GMDataType,
+    ) -> Result<(), String> {
+        // `pop.i.v` has the value on top of the stack; otherwise it was pushed before the instance and array index
+        let mut value: Option<Value> = None;
+        if matches!(value_type, GMDataType::Int32) {
+            value = Some(self.stack.pop()?);
         }
-        GMInstanceType::Local => {
-            variables.locals.insert((destination.variable.index, code_index), value);
+
+        match destination.variable_type {
+            GMVariableType::Array => {
+                let index: Value = self.stack.pop()?;
+                let instance: Value = self.stack.pop()?;
+                let value: Value = match value {
+                    Some(value) => value,
+                    None => self.stack.pop()?,
+                };
+                let scope: Scope = self.scope_of_stack_value(&instance)?;
+                self.write_scoped(scope, destination.variable.index, Some(&index), value, false)
+            }
+            // `instance.variable = value`
+            GMVariableType::StackTop => {
+                let instance: Value = self.stack.pop()?;
+                let value: Value = match value {
+                    Some(value) => value,
+                    None => self.stack.pop()?,
+                };
+                let scope: Scope = self.scope_of_stack_value(&instance)?;
+                self.write_scoped(scope, destination.variable.index, None, value, false)
+            }
+            _ => {
+                let value: Value = match value {
+                    Some(value) => value,
+                    None => self.stack.pop()?,
+                };
+                let scope: Scope = self.scope_of_instance_type(instance_type)?;
+                let builtin_scope: bool = matches!(instance_type, GMInstanceType::Builtin);
+                self.write_scoped(scope, destination.variable.index, None, value, builtin_scope)
+            }
         }
-        other => return Err(format!("Invalid Instance Type {other:?} while popping value {value:?}"))
     }
 
-    Ok(())
+    pub fn push_variable(&mut self, variable: &GMCodeVariable) -> Result<Value, String> {
+        match variable.variable_type {
+            GMVariableType::Normal | GMVariableType::Instance => {
+                let scope: Scope = self.scope_of_instance_type(&variable.instance_type)?;
+                let builtin_scope: bool = matches!(variable.instance_type, GMInstanceType::Builtin);
+                self.read_scoped(scope, variable.variable.index, None, builtin_scope)
+            }
+            // `instance.variable`
+            GMVariableType::StackTop => {
+                let instance: Value = self.stack.pop()?;
+                let scope: Scope = self.scope_of_stack_value(&instance)?;
+                self.read_scoped(scope, variable.variable.index, None, false)
+            }
+            GMVariableType::Array | GMVariableType::MultiPush => {
+                let index: Value = self.stack.pop()?;
+                let instance: Value = self.stack.pop()?;
+                let scope: Scope = self.scope_of_stack_value(&instance)?;
+                self.read_scoped(scope, variable.variable.index, Some(&index), false)
+            }
+            // the start of a GMS2.3 multidimensional array assignment; missing arrays are created on the way
+            GMVariableType::MultiPushPop => {
+                let name: String = self.variable_name(variable.variable.index);
+                let index: Value = self.stack.pop()?;
+                let instance: Value = self.stack.pop()?;
+                let scope: Scope = self.scope_of_stack_value(&instance)?;
+                let mut container: Value = Value::Undefined;
+                self.with_variable_slots(scope, variable.variable.index, |slot| {
+                    container = container_element(slot, &index, &name)?;
+                    Ok(())
+                })?;
+                Ok(container)
+            }
+            other => Err(format!("Pushing {other:?} variable {} is not implemented", self.variable_name(variable.variable.index))),
+        }
+    }
 }
 
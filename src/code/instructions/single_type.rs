@@ -1,37 +1,45 @@
-use libgm::gm::GMValue;
+use libgm::gm::GMDataType;
 use crate::code::run::Stack;
+use crate::code::value::Value;
 
 pub fn neg(stack: &mut Stack) -> Result<(), String> {
-    let old: GMValue = stack.pop()?;
-    let new: GMValue = match old {
-        GMValue::Double(val) => GMValue::Double(-val),
-        GMValue::Float(val) => GMValue::Float(-val),
-        GMValue::Int16(val) => GMValue::Int16(-val),
-        GMValue::Int32(val) => GMValue::Int32(-val),
-        GMValue::Int64(val) => GMValue::Int64(-val),
-        GMValue::Boolean(_) => return Err("Cannot int negate boolean value".to_string()),
-        GMValue::String(_) => return Err("Cannot int negate string value".to_string()),
-        GMValue::Variable(_) => return Err("Variable should not be on the stack".to_string()),
+    let old: Value = stack.pop()?;
+    let new: Value = match old {
+        Value::Real(val) => Value::Real(-val),
+        Value::Int32(val) => Value::Int32(val.wrapping_neg()),
+        Value::Int64(val) => Value::Int64(val.wrapping_neg()),
+        Value::Bool(val) => Value::Real(-f64::from(val)),
+        other => return Err(format!("Cannot int negate {} value", other.type_name())),
     };
     stack.push(new);
     Ok(())
 }
-pub fn not(stack: &mut Stack) -> Result<(), String> {
-    let old: GMValue = stack.pop()?;
-    let new: GMValue = match old {
-        GMValue::Boolean(val) => GMValue::Boolean(!val),
-        other => return Err(format!("Cannot bool negate {other:?} value")),
+/// Logical NOT for booleans, bitwise NOT for integers
+pub fn not(stack: &mut Stack, data_type: GMDataType) -> Result<(), String> {
+    let old: Value = stack.pop()?;
+    let new: Value = match (data_type, old) {
+        (GMDataType::Boolean, old) => Value::Bool(!old.to_bool()?),
+        (_, Value::Bool(val)) => Value::Bool(!val),
+        (_, Value::Int32(val)) => Value::Int32(!val),
+        (_, Value::Int64(val)) => Value::Int64(!val),
+        (_, Value::Real(val)) => Value::Int64(!(val.round() as i64)),
+        (_, other) => return Err(format!("Cannot bool negate {} value", other.type_name())),
     };
     stack.push(new);
     Ok(())
 }
-pub fn dup(stack: &mut Stack) -> Result<(), String> {
-    let value: GMValue = stack.peek()?;
-    stack.push(value);
+/// `dup N` duplicates the top N+1 values in order; compound assignments like `a[i] += 1` duplicate
+/// the instance and index operands this way.
+pub fn dup(stack: &mut Stack, extra: usize) -> Result<(), String> {
+    let count: usize = extra + 1;
+    let start: usize = stack.items.len().checked_sub(count)
+        .ok_or_else(|| format!("Could not duplicate {count} values because the stack only has {}", stack.items.len()))?;
+    let values: Vec<Value> = stack.items[start..].to_vec();
+    stack.items.extend(values);
     Ok(())
 }
-pub fn ret(stack: &mut Stack) -> Result<GMValue, String> {
-    let value: GMValue = stack.pop()?;
+pub fn ret(stack: &mut Stack) -> Result<Value, String> {
+    let value: Value = stack.pop()?;
     Ok(value)
 }
 pub fn popz(stack: &mut Stack) -> Result<(), String> {
@@ -39,3 +47,38 @@ pub fn popz(stack: &mut Stack) -> Result<(), String> {
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack_of(values: &[f64]) -> Stack {
+        Stack { items: values.iter().map(|value| Value::Real(*value)).collect() }
+    }
+
+    fn reals(stack: &Stack) -> Vec<f64> {
+        stack.items.iter().map(|value| value.to_real().unwrap()).collect()
+    }
+
+    #[test]
+    fn dup_copies_the_top_value() {
+        let mut stack: Stack = stack_of(&[1.0, 2.0]);
+        dup(&mut stack, 0).unwrap();
+        assert_eq!(reals(&stack), [1.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn dup_copies_the_top_values_in_order() {
+        let mut stack: Stack = stack_of(&[1.0, 2.0, 3.0]);
+        dup(&mut stack, 1).unwrap();
+        assert_eq!(reals(&stack), [1.0, 2.0, 3.0, 2.0, 3.0]);
+        dup(&mut stack, 4).unwrap();
+        assert_eq!(reals(&stack), [1.0, 2.0, 3.0, 2.0, 3.0, 1.0, 2.0, 3.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn dup_fails_on_a_short_stack() {
+        let mut stack: Stack = stack_of(&[1.0]);
+        assert!(dup(&mut stack, 1).is_err());
+    }
+}
@@ -1,2 +1,8 @@
 pub mod run;
+pub mod call;
+pub mod builtins;
+pub mod value;
+pub mod array;
+pub mod structs;
+pub mod builtin_variables;
 mod instructions;
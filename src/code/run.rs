@@ -1,26 +1,36 @@
 use std::collections::HashMap;
-use libgm::gm::{GMCode, GMFunction, GMInstanceType, GMInstruction, GMOpcode, GMValue};
+use std::sync::Arc;
+use libgm::GMData;
+use libgm::gm::{GMCode, GMInstruction, GMOpcode, GMValue};
 use crate::App;
+use crate::code::call::CallFrame;
 use crate::code::instructions::double_type::{add, and, conv, div, mod_, mul, or, rem, shl, shr, sub, xor};
-use crate::code::instructions::other::{bf, bt, cmp, pop};
+use crate::code::instructions::other::{bf, bt, cmp};
 use crate::code::instructions::single_type::{dup, neg, not, popz, ret};
+use crate::code::value::Value;
 
 #[derive(Debug)]
 pub struct Stack {
-    pub items: Vec<GMValue>,   // GMValue can be anything except a Variable
+    pub items: Vec<Value>,
 }
 impl Stack {
     pub fn new() -> Self {
         Self { items: Vec::new() }
     }
-    pub fn push(&mut self, value: GMValue) {
+    pub fn push(&mut self, value: Value) {
         self.items.push(value);
     }
-    pub fn pop(&mut self) -> Result<GMValue, String> {
+    pub fn pop(&mut self) -> Result<Value, String> {
         self.items.pop()
             .ok_or_else(|| "Could not pop value from stack because it is empty".to_string())
     }
-    pub fn peek(&self) -> Result<GMValue, String> {
+    /// Pops both operands of a binary instruction; the right-hand side is on top of the stack
+    pub fn pop_operands(&mut self) -> Result<(Value, Value), String> {
+        let rhs: Value = self.pop()?;
+        let lhs: Value = self.pop()?;
+        Ok((lhs, rhs))
+    }
+    pub fn peek(&self) -> Result<Value, String> {
         self.items.last()
             .map(|i| i.clone())
             .ok_or_else(|| "Could not peek value from stack because it is empty".to_string())
@@ -30,15 +40,43 @@ impl Stack {
 
 #[derive(Debug, Clone)]
 pub struct Variables {
-    pub globals: HashMap<usize, GMValue>,               // key: variable index
-    pub instances: HashMap<(usize, usize), GMValue>,    // key: variable index, game object index
-    pub locals: HashMap<(usize, usize), GMValue>,       // key: variable index, code index. Is partially reset after execution of a code ends
+    pub globals: HashMap<usize, Value>,     // key: variable index
+}
+
+
+/// PopEnv with this jump offset leaves the `with` block instead of looping (`break` inside `with`)
+const POPENV_EXIT_MAGIC: i32 = 0xF00000;
+
+fn jump_target(i: usize, jump_offset: i32) -> Result<usize, String> {
+    let target: i32 = i as i32 + jump_offset * 4;
+    usize::try_from(target)
+        .map_err(|_| format!("Jump from instruction #{i} with offset {jump_offset} leads to invalid instruction #{target}"))
 }
 
 
 impl App {
-    pub fn run_code(&mut self, code_index: usize, object_index: usize) -> Result<Option<GMValue>, String> {
-        let code: &GMCode = &self.data.codes.codes_by_index[code_index];
+    pub fn run_code(&mut self, frame: CallFrame) -> Result<Option<Value>, String> {
+        let code_index: usize = frame.code_index;
+        self.call_stack.push(frame)?;
+        let result: Result<Option<Value>, String> = self.execute_code(code_index);
+        let frame: CallFrame = self.call_stack.pop()?;
+        result.map_err(|e| {
+            let code_name: &str = self.data.codes.codes_by_index.get(code_index)
+                .and_then(|code| code.name.resolve(&self.data.strings.strings_by_index).ok())
+                .map(|name| name.as_str())
+                .unwrap_or("<invalid code>");
+            match frame.return_address {
+                Some(address) => format!("{e}\n↳ in {code_name} (returning to instruction #{address})"),
+                None => format!("{e}\n↳ in {code_name}"),
+            }
+        })
+    }
+
+    fn execute_code(&mut self, code_index: usize) -> Result<Option<Value>, String> {
+        // keep our own handle to the data so that instructions can call back into `self`
+        let data: Arc<GMData> = self.data.clone();
+        let code: &GMCode = data.codes.codes_by_index.get(code_index)
+            .ok_or_else(|| format!("Code index {} out of bounds (length {})", code_index, data.codes.codes_by_index.len()))?;
         let mut i: usize = 0;
 
         while i < code.instructions.len() {
@@ -47,11 +85,15 @@ impl App {
                     log::debug!("Executing Instruction #{i}: {:?} - {:?}", instr.opcode, instr.data_type);
                     match instr.opcode {
                         GMOpcode::Neg => neg(&mut self.stack)?,
-                        GMOpcode::Not => not(&mut self.stack)?,
-                        GMOpcode::Dup => dup(&mut self.stack)?,
+                        GMOpcode::Not => not(&mut self.stack, instr.data_type)?,
+                        GMOpcode::Dup => dup(&mut self.stack, instr.extra as usize)?,
                         GMOpcode::Ret => return Ok(Some(ret(&mut self.stack)?)),
                         GMOpcode::Exit => return Ok(None),
                         GMOpcode::Popz => popz(&mut self.stack)?,
+                        GMOpcode::CallV => {
+                            let value: Value = self.call_variable(instr.extra as usize, i + 1)?;
+                            self.stack.push(value);
+                        }
                         other => return Err(format!("Invalid Single Type Instruction Opcode {other:?}"))
                     }
                 }
@@ -77,51 +119,70 @@ impl App {
 
                 GMInstruction::Comparison(instr) => {
                     log::debug!("Executing Instruction #{i}: {:?} - {:?} {:?} {:?}", instr.opcode, instr.type1, instr.comparison_type, instr.type2);
-                    cmp(&mut self.stack, instr.comparison_type)?;
+                    cmp(&mut self.stack, instr.comparison_type, self.math_epsilon)?;
                 }
 
                 GMInstruction::Goto(instr) => {
                     log::debug!("Executing Instruction #{i}: {:?} - {}", instr.opcode, instr.jump_offset);
+                    // jumps `continue` so that the target instruction is not skipped by the increment below
                     match instr.opcode {
-                        GMOpcode::B => i = (i as i32 + instr.jump_offset * 4) as usize,
-                        GMOpcode::Bt => if bt(&mut self.stack)? { i = (i as i32 + instr.jump_offset * 4) as usize },
-                        GMOpcode::Bf => if bf(&mut self.stack)? { i = (i as i32 + instr.jump_offset * 4) as usize },
-                        GMOpcode::PushEnv => todo!(),
-                        GMOpcode::PopEnv => todo!(),
+                        GMOpcode::B => {
+                            i = jump_target(i, instr.jump_offset)?;
+                            continue
+                        }
+                        GMOpcode::Bt => if bt(&mut self.stack)? {
+                            i = jump_target(i, instr.jump_offset)?;
+                            continue
+                        },
+                        GMOpcode::Bf => if bf(&mut self.stack)? {
+                            i = jump_target(i, instr.jump_offset)?;
+                            continue
+                        },
+                        // PushEnv jumps to its PopEnv if there is no instance to iterate over
+                        GMOpcode::PushEnv => if !self.push_env()? {
+                            i = jump_target(i, instr.jump_offset)? + 1;
+                            continue
+                        },
+                        // PopEnv jumps back to the start of the `with` body while instances are left
+                        GMOpcode::PopEnv => if instr.jump_offset == POPENV_EXIT_MAGIC {
+                            self.exit_env()?;
+                        } else if self.pop_env()? {
+                            i = jump_target(i, instr.jump_offset)?;
+                            continue
+                        },
                         other => return Err(format!("Invalid Goto Instruction Opcode {other:?}"))
                     }
                 }
 
                 GMInstruction::Pop(instr) => {
                     log::debug!("Executing Instruction #{i}: {:?} - {:?} {:?} {:?}", instr.opcode, instr.destination.variable_type, instr.destination.variable, instr.type2);
-                    // let instance_type: &GMInstanceType = match &instr.instance_type {
-                    //     GMInstanceType::Instance(None) => &instr.destination.variable.resolve(&self.data.variables.variables)?.instance_type,
-                    //     other => other,
-                    // };
-                    pop(&mut self.variables, code_index, object_index, &mut self.stack, &instr.instance_type, &instr.destination)?;
+                    self.pop_variable(&instr.instance_type, &instr.destination, instr.type1)?;
                 }
 
                 GMInstruction::Push(instr) => {
                     log::debug!("Executing Instruction #{i}: {:?} - {:?}", instr.opcode, instr.value);
-                    self.stack.push(instr.value.clone());
+                    let value: Value = match &instr.value {
+                        GMValue::Variable(variable) => self.push_variable(variable)?,
+                        other => Value::from_gm(other, &data)?,
+                    };
+                    self.stack.push(value);
                 }
 
                 GMInstruction::Call(instr) => {
                     log::debug!("Executing Instruction #{i}: {:?} - {:?} {:?}({})", instr.opcode, instr.data_type, instr.function, instr.arguments_count);
-                    todo!()
-                    // let function: &GMFunction = instr.function.resolve(&self.data.functions.functions_by_index)?;
-                    // function.
-                    // self.run_code()
+                    let value: Value = self.call_function(&instr.function, instr.arguments_count, i + 1)?;
+                    self.stack.push(value);
                 }
 
                 GMInstruction::Break(instr) => {
-                    log::debug!("Executing Instruction #{i}: {:?}", instr.opcode);
-                    todo!()
+                    log::debug!("Executing Instruction #{i}: {:?} - {}", instr.opcode, instr.value);
+                    self.execute_break(instr.value, instr.int_argument)?;
                 }
             }
 
             log::trace!("Stack: {:?}", self.stack);
             log::trace!("Variables: {:?}", self.variables);
+            log::trace!("Locals: {:?}", self.call_stack.current()?.locals);
             i += 1;     // increment instruction counter
         }
         Ok(None)
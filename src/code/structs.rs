@@ -0,0 +1,28 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::code::value::Value;
+use crate::instance::InstanceRef;
+
+/// GMS2.3 structs live on the heap and are shared by reference; they are freed once nothing refers to them anymore.
+pub type StructRef = Rc<RefCell<Struct>>;
+
+#[derive(Debug, Clone, Default)]
+pub struct Struct {
+    pub variables: HashMap<usize, Value>,   // key: variable index
+    /// Function index of the constructor this struct was created by, if any
+    pub constructor: Option<usize>,
+}
+impl Struct {
+    pub fn new_ref(constructor: Option<usize>) -> StructRef {
+        Rc::new(RefCell::new(Struct { variables: HashMap::new(), constructor }))
+    }
+}
+
+/// A function value, optionally bound to a `self` by `method()`.
+#[derive(Debug, Clone)]
+pub struct Method {
+    pub function: usize,    // function index
+    pub bound_self: Option<InstanceRef>,
+}
+
@@ -0,0 +1,245 @@
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+use libgm::GMData;
+use libgm::gm::GMValue;
+use crate::code::array::ArrayRef;
+use crate::code::structs::{Method, StructRef};
+
+/// GameMaker's default for `math_set_epsilon`
+pub const DEFAULT_EPSILON: f64 = 0.00001;
+
+/// A runtime value; anything that can live on the stack or in a variable.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Real(f64),
+    Int32(i32),
+    Int64(i64),
+    Bool(bool),
+    String(Rc<str>),
+    Array(ArrayRef),
+    Struct(StructRef),
+    Method(Rc<Method>),
+    Undefined,
+}
+
+impl Value {
+    /// Converts a constant from the bytecode; variables have to be resolved by the caller.
+    pub fn from_gm(value: &GMValue, data: &GMData) -> Result<Self, String> {
+        Ok(match value {
+            GMValue::Double(val) => Value::Real(*val),
+            GMValue::Float(val) => Value::Real(f64::from(*val)),
+            GMValue::Int16(val) => Value::Int32(i32::from(*val)),
+            GMValue::Int32(val) => Value::Int32(*val),
+            GMValue::Int64(val) => Value::Int64(*val),
+            GMValue::Boolean(val) => Value::Bool(*val),
+            GMValue::String(string) => Value::String(Rc::from(string.resolve(&data.strings.strings_by_index)?.as_str())),
+            GMValue::Variable(variable) => return Err(format!("Cannot convert variable reference {variable:?} to a value")),
+        })
+    }
+
+    pub fn string(string: impl AsRef<str>) -> Self {
+        Value::String(Rc::from(string.as_ref()))
+    }
+
+    /// Name of the type as reported by GameMaker's `typeof`
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Real(_) => "number",
+            Value::Int32(_) => "int32",
+            Value::Int64(_) => "int64",
+            Value::Bool(_) => "bool",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Struct(_) => "struct",
+            Value::Method(_) => "method",
+            Value::Undefined => "undefined",
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, Value::Real(_) | Value::Int32(_) | Value::Int64(_) | Value::Bool(_))
+    }
+
+    pub fn to_real(&self) -> Result<f64, String> {
+        match self {
+            Value::Real(val) => Ok(*val),
+            Value::Int32(val) => Ok(f64::from(*val)),
+            Value::Int64(val) => Ok(*val as f64),
+            Value::Bool(val) => Ok(f64::from(*val)),
+            other => Err(format!("Expected a number, got {}", other.type_name())),
+        }
+    }
+
+    /// Reals are rounded, as GameMaker does whenever an integer is expected
+    pub fn to_int64(&self) -> Result<i64, String> {
+        match self {
+            Value::Int32(val) => Ok(i64::from(*val)),
+            Value::Int64(val) => Ok(*val),
+            other => Ok(other.to_real()?.round() as i64),
+        }
+    }
+
+    pub fn to_int32(&self) -> Result<i32, String> {
+        match self {
+            Value::Int32(val) => Ok(*val),
+            other => Ok(other.to_int64()? as i32),
+        }
+    }
+
+    /// Numbers greater than 0.5 are true
+    pub fn to_bool(&self) -> Result<bool, String> {
+        match self {
+            Value::Bool(val) => Ok(*val),
+            Value::Int32(val) => Ok(*val > 0),
+            Value::Int64(val) => Ok(*val > 0),
+            Value::Real(val) => Ok(*val > 0.5),
+            other => Err(format!("Expected a boolean, got {}", other.type_name())),
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&str, String> {
+        match self {
+            Value::String(string) => Ok(string),
+            other => Err(format!("Expected a string, got {}", other.type_name())),
+        }
+    }
+
+    /// Compares two values the way the `cmp` instruction does.
+    /// Returns `None` if the values are not ordered (different types).
+    pub fn compare(&self, other: &Value, epsilon: f64) -> Option<std::cmp::Ordering> {
+        use std::cmp::Ordering;
+        match (self, other) {
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            (Value::Undefined, Value::Undefined) => Some(Ordering::Equal),
+            // arrays, structs and methods are compared by reference
+            (Value::Array(a), Value::Array(b)) if Rc::ptr_eq(a, b) => Some(Ordering::Equal),
+            (Value::Struct(a), Value::Struct(b)) if Rc::ptr_eq(a, b) => Some(Ordering::Equal),
+            (Value::Method(a), Value::Method(b)) if Rc::ptr_eq(a, b) => Some(Ordering::Equal),
+            (a, b) if a.is_number() && b.is_number() => {
+                let (a, b) = (a.to_real().ok()?, b.to_real().ok()?);
+                if (a - b).abs() <= epsilon {
+                    Some(Ordering::Equal)
+                } else {
+                    a.partial_cmp(&b)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Formats values like `string()` without access to the variable names; struct variables show their index.
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.display(&|index| format!("<variable #{index}>")))
+    }
+}
+
+impl Value {
+    /// Formats a value the same way GameMaker's `string()` does, naming struct variables through `variable_name`
+    pub fn display<'a>(&'a self, variable_name: &'a dyn Fn(usize) -> String) -> DisplayValue<'a> {
+        DisplayValue { value: self, variable_name }
+    }
+}
+
+pub struct DisplayValue<'a> {
+    value: &'a Value,
+    variable_name: &'a dyn Fn(usize) -> String,
+}
+impl Display for DisplayValue<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.value {
+            Value::Real(val) => f.write_str(&format_real(*val)),
+            Value::Int32(val) => write!(f, "{val}"),
+            Value::Int64(val) => write!(f, "{val}"),
+            Value::Bool(val) => f.write_str(if *val { "true" } else { "false" }),
+            Value::String(string) => f.write_str(string),
+            Value::Array(array) => {
+                let array = array.borrow();
+                for (i, row) in array.rows.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    f.write_str("[ ")?;
+                    for (j, item) in row.iter().enumerate() {
+                        if j > 0 {
+                            f.write_str(",")?;
+                        }
+                        write!(f, "{}", item.display(self.variable_name))?;
+                    }
+                    f.write_str(" ]")?;
+                }
+                Ok(())
+            }
+            Value::Struct(structure) => {
+                let structure = structure.borrow();
+                let mut variables: Vec<(&usize, &Value)> = structure.variables.iter().collect();
+                variables.sort_by_key(|(index, _)| **index);
+                f.write_str("{ ")?;
+                for (i, (index, value)) in variables.into_iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{} : {}", (self.variable_name)(*index), value.display(self.variable_name))?;
+                }
+                f.write_str(" }")
+            }
+            Value::Method(method) => write!(f, "function {}", method.function),
+            Value::Undefined => f.write_str("undefined"),
+        }
+    }
+}
+
+fn format_real(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.2}")
+    }
+}
+
+
+/// The common type two numeric operands are promoted to before an arithmetic operation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumericType {
+    Int32,
+    Int64,
+    Real,
+}
+impl NumericType {
+    pub fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Int32(_) => Some(NumericType::Int32),
+            Value::Int64(_) => Some(NumericType::Int64),
+            Value::Real(_) | Value::Bool(_) => Some(NumericType::Real),
+            _ => None,
+        }
+    }
+    /// Reals win over integers; 64-bit integers win over 32-bit integers
+    pub fn promote(lhs: &Value, rhs: &Value) -> Option<Self> {
+        let a: NumericType = Self::of(lhs)?;
+        let b: NumericType = Self::of(rhs)?;
+        Some(match (a, b) {
+            (NumericType::Real, _) | (_, NumericType::Real) => NumericType::Real,
+            (NumericType::Int64, _) | (_, NumericType::Int64) => NumericType::Int64,
+            _ => NumericType::Int32,
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::structs::Struct;
+
+    #[test]
+    fn structs_show_variable_names() {
+        let structure: StructRef = Struct::new_ref(None);
+        structure.borrow_mut().variables.insert(12, Value::Real(5.0));
+        structure.borrow_mut().variables.insert(3, Value::string("hi"));
+        let names = |index: usize| if index == 12 { "count".to_string() } else { "name".to_string() };
+        assert_eq!(Value::Struct(structure.clone()).display(&names).to_string(), "{ name : hi, count : 5 }");
+        assert_eq!(Value::Struct(structure).to_string(), "{ <variable #3> : hi, <variable #12> : 5 }");
+    }
+}
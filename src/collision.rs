@@ -0,0 +1,380 @@
+use std::collections::HashSet;
+use libgm::GMData;
+use libgm::gm::{GMSprite, GMSpriteSepMaskType};
+use crate::App;
+use crate::event::{Event, EV_COLLISION};
+use crate::instance::{InstanceBuiltins, InstanceRef};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaskKind {
+    /// The bounding box; rotated instances use the box around the rotated box
+    Rectangle,
+    /// The bounding box, rotated with the instance (GMS2)
+    RotatedRectangle,
+    /// Per-pixel masks
+    Precise,
+}
+
+/// Collision data of one sprite
+#[derive(Debug, Clone)]
+pub struct SpriteMask {
+    pub kind: MaskKind,
+    /// Bounding box in sprite pixels; right and bottom are inclusive like GameMaker's
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub origin_x: f64,
+    pub origin_y: f64,
+    width: usize,
+    /// One bitmap per frame (or one for all frames), rows padded to whole bytes, most significant bit first
+    frames: Vec<Vec<u8>>,
+}
+impl SpriteMask {
+    fn load(sprite: &GMSprite) -> Self {
+        let kind: MaskKind = match sprite.sep_masks {
+            GMSpriteSepMaskType::Precise => MaskKind::Precise,
+            GMSpriteSepMaskType::RotatedRect => MaskKind::RotatedRectangle,
+            _ => MaskKind::Rectangle,
+        };
+        Self {
+            // a precise sprite without masks cannot be tested per pixel
+            kind: if kind == MaskKind::Precise && sprite.collision_masks.is_empty() { MaskKind::Rectangle } else { kind },
+            left: f64::from(sprite.bbox_left),
+            top: f64::from(sprite.bbox_top),
+            right: f64::from(sprite.bbox_right),
+            bottom: f64::from(sprite.bbox_bottom),
+            origin_x: f64::from(sprite.origin_x),
+            origin_y: f64::from(sprite.origin_y),
+            width: sprite.width as usize,
+            frames: sprite.collision_masks.iter().map(|mask| mask.data.clone()).collect(),
+        }
+    }
+
+    /// Whether the pixel is set in the frame's mask; (x, y) is in sprite pixels
+    fn pixel(&self, image_index: f64, x: f64, y: f64) -> bool {
+        if self.frames.is_empty() || x < 0.0 || y < 0.0 || x >= self.width as f64 {
+            return false
+        }
+        let frame: &[u8] = &self.frames[(image_index.floor() as i64).rem_euclid(self.frames.len() as i64) as usize];
+        let (x, y): (usize, usize) = (x as usize, y as usize);
+        let stride: usize = self.width.div_ceil(8);
+        frame.get(y * stride + x / 8).is_some_and(|byte| byte & (0x80 >> (x % 8)) != 0)
+    }
+}
+
+/// Collision masks of every sprite; index: sprite index
+#[derive(Debug, Clone, Default)]
+pub struct SpriteMasks {
+    pub masks: Vec<SpriteMask>,
+}
+impl SpriteMasks {
+    pub fn load(data: &GMData) -> Self {
+        Self { masks: data.sprites.sprites_by_index.iter().map(SpriteMask::load).collect() }
+    }
+}
+
+/// An axis-aligned box in room coordinates; right and bottom are exclusive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+}
+impl BoundingBox {
+    /// Boxes that only touch do not intersect
+    pub fn intersection(&self, other: &BoundingBox) -> Option<BoundingBox> {
+        let area: BoundingBox = BoundingBox {
+            left: self.left.max(other.left),
+            top: self.top.max(other.top),
+            right: self.right.min(other.right),
+            bottom: self.bottom.min(other.bottom),
+        };
+        (area.left < area.right && area.top < area.bottom).then_some(area)
+    }
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.left && x < self.right && y >= self.top && y < self.bottom
+    }
+    /// Centres of every pixel at least partly inside the box
+    fn pixel_centers(&self) -> impl Iterator<Item = (f64, f64)> {
+        let (left, right): (i64, i64) = (self.left.floor() as i64, self.right.ceil() as i64);
+        let (top, bottom): (i64, i64) = (self.top.floor() as i64, self.bottom.ceil() as i64);
+        (top..bottom).flat_map(move |y| (left..right).map(move |x| (x as f64 + 0.5, y as f64 + 0.5)))
+    }
+}
+
+/// An instance's mask placed in the room
+#[derive(Debug, Clone, Copy)]
+pub struct Collider<'a> {
+    mask: &'a SpriteMask,
+    image_index: f64,
+    x: f64,
+    y: f64,
+    xscale: f64,
+    yscale: f64,
+    sin: f64,
+    cos: f64,
+    pub bbox: BoundingBox,
+}
+impl<'a> Collider<'a> {
+    fn new(mask: &'a SpriteMask, builtins: &InstanceBuiltins, x: f64, y: f64) -> Option<Self> {
+        if builtins.image_xscale == 0.0 || builtins.image_yscale == 0.0 {
+            return None
+        }
+        let (sin, cos): (f64, f64) = builtins.image_angle.to_radians().sin_cos();
+        let mut collider: Collider = Collider {
+            mask,
+            image_index: builtins.image_index,
+            x,
+            y,
+            xscale: builtins.image_xscale,
+            yscale: builtins.image_yscale,
+            sin,
+            cos,
+            bbox: BoundingBox { left: 0.0, top: 0.0, right: 0.0, bottom: 0.0 },
+        };
+        let corners: [(f64, f64); 4] = [
+            collider.sprite_to_room(mask.left, mask.top),
+            collider.sprite_to_room(mask.right + 1.0, mask.top),
+            collider.sprite_to_room(mask.left, mask.bottom + 1.0),
+            collider.sprite_to_room(mask.right + 1.0, mask.bottom + 1.0),
+        ];
+        collider.bbox = BoundingBox {
+            left: corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min),
+            top: corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min),
+            right: corners.iter().map(|c| c.0).fold(f64::NEG_INFINITY, f64::max),
+            bottom: corners.iter().map(|c| c.1).fold(f64::NEG_INFINITY, f64::max),
+        };
+        Some(collider)
+    }
+
+    /// Sprite pixel to room coordinates, transformed like the sprite is drawn
+    fn sprite_to_room(&self, sprite_x: f64, sprite_y: f64) -> (f64, f64) {
+        let dx: f64 = (sprite_x - self.mask.origin_x) * self.xscale;
+        let dy: f64 = (sprite_y - self.mask.origin_y) * self.yscale;
+        (self.x + dx * self.cos + dy * self.sin, self.y - dx * self.sin + dy * self.cos)
+    }
+
+    fn room_to_sprite(&self, x: f64, y: f64) -> (f64, f64) {
+        let (dx, dy): (f64, f64) = (x - self.x, y - self.y);
+        (
+            (dx * self.cos - dy * self.sin) / self.xscale + self.mask.origin_x,
+            (dx * self.sin + dy * self.cos) / self.yscale + self.mask.origin_y,
+        )
+    }
+
+    /// Whether the collider is exactly its bounding box, so no pixels have to be tested
+    fn is_box(&self) -> bool {
+        self.mask.kind == MaskKind::Rectangle || (self.mask.kind == MaskKind::RotatedRectangle && self.sin == 0.0)
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        if !self.bbox.contains(x, y) {
+            return false
+        }
+        if self.is_box() {
+            return true
+        }
+        let (sprite_x, sprite_y): (f64, f64) = self.room_to_sprite(x, y);
+        let inside: bool = sprite_x >= self.mask.left && sprite_x < self.mask.right + 1.0
+            && sprite_y >= self.mask.top && sprite_y < self.mask.bottom + 1.0;
+        match self.mask.kind {
+            MaskKind::Precise => inside && self.mask.pixel(self.image_index, sprite_x, sprite_y),
+            _ => inside,
+        }
+    }
+
+    pub fn overlaps(&self, other: &Collider) -> bool {
+        let Some(area) = self.bbox.intersection(&other.bbox) else { return false };
+        if self.is_box() && other.is_box() {
+            return true
+        }
+        area.pixel_centers().any(|(x, y)| self.contains(x, y) && other.contains(x, y))
+    }
+
+    /// With `precise` false, the instance counts as its bounding box
+    pub fn hits(&self, shape: &Shape, precise: bool) -> bool {
+        let contains = |x: f64, y: f64| if precise { self.contains(x, y) } else { self.bbox.contains(x, y) };
+        if let Shape::Point(x, y) = *shape {
+            return contains(x, y)
+        }
+        let Some(area) = self.bbox.intersection(&shape.bbox()) else { return false };
+        if matches!(shape, Shape::Rectangle(_)) && (self.is_box() || !precise) {
+            return true
+        }
+        area.pixel_centers().any(|(x, y)| shape.contains(x, y) && contains(x, y))
+    }
+}
+
+/// Areas tested by `collision_*` and `position_*` functions
+#[derive(Debug, Clone, Copy)]
+pub enum Shape {
+    Point(f64, f64),
+    Rectangle(BoundingBox),
+    Circle { x: f64, y: f64, radius: f64 },
+    Line { x1: f64, y1: f64, x2: f64, y2: f64 },
+}
+impl Shape {
+    /// GameMaker's rectangle corners are inclusive
+    pub fn rectangle(x1: f64, y1: f64, x2: f64, y2: f64) -> Self {
+        Shape::Rectangle(BoundingBox { left: x1.min(x2), top: y1.min(y2), right: x1.max(x2) + 1.0, bottom: y1.max(y2) + 1.0 })
+    }
+
+    fn bbox(&self) -> BoundingBox {
+        match *self {
+            Shape::Point(x, y) => BoundingBox { left: x, top: y, right: x + 1.0, bottom: y + 1.0 },
+            Shape::Rectangle(bbox) => bbox,
+            Shape::Circle { x, y, radius } => BoundingBox { left: x - radius, top: y - radius, right: x + radius, bottom: y + radius },
+            // lines are half a pixel thick on each side
+            Shape::Line { x1, y1, x2, y2 } => BoundingBox {
+                left: x1.min(x2) - 0.5,
+                top: y1.min(y2) - 0.5,
+                right: x1.max(x2) + 0.5,
+                bottom: y1.max(y2) + 0.5,
+            },
+        }
+    }
+
+    fn contains(&self, px: f64, py: f64) -> bool {
+        match *self {
+            Shape::Point(x, y) => px.floor() == x.floor() && py.floor() == y.floor(),
+            Shape::Rectangle(bbox) => bbox.contains(px, py),
+            Shape::Circle { x, y, radius } => (px - x).powi(2) + (py - y).powi(2) <= radius * radius,
+            Shape::Line { x1, y1, x2, y2 } => {
+                let (dx, dy): (f64, f64) = (x2 - x1, y2 - y1);
+                let length: f64 = dx * dx + dy * dy;
+                let t: f64 = if length == 0.0 { 0.0 } else { (((px - x1) * dx + (py - y1) * dy) / length).clamp(0.0, 1.0) };
+                (px - (x1 + t * dx)).hypot(py - (y1 + t * dy)) <= 0.5
+            }
+        }
+    }
+}
+
+
+impl App {
+    /// The instance's mask (its `mask_index`, otherwise its sprite) as if it stood at (x, y)
+    pub fn collider_at(&self, id: usize, x: f64, y: f64) -> Result<Option<Collider<'_>>, String> {
+        let builtins: &InstanceBuiltins = &self.instances.get(id)?.builtins;
+        let sprite_index: i32 = if builtins.mask_index >= 0 { builtins.mask_index } else { builtins.sprite_index };
+        let Some(mask) = usize::try_from(sprite_index).ok().and_then(|i| self.masks.masks.get(i)) else { return Ok(None) };
+        Ok(Collider::new(mask, builtins, x, y))
+    }
+
+    pub fn collider(&self, id: usize) -> Result<Option<Collider<'_>>, String> {
+        let builtins: &InstanceBuiltins = &self.instances.get(id)?.builtins;
+        self.collider_at(id, builtins.x, builtins.y)
+    }
+
+    /// Living instances which might have a mask intersecting `area`
+    pub fn collision_candidates(&self, area: &BoundingBox) -> Result<Vec<usize>, String> {
+        self.refresh_grid()?;
+        let mut candidates: Vec<usize> = self.grid.borrow().query(area);
+        candidates.retain(|id| self.instances.exists(*id));
+        Ok(candidates)
+    }
+
+    /// Instances of `among` colliding with instance `id` if it stood at (x, y)
+    pub fn collisions_at(&self, id: usize, x: f64, y: f64, among: &[usize]) -> Result<Vec<usize>, String> {
+        let Some(collider) = self.collider_at(id, x, y)? else { return Ok(Vec::new()) };
+        let among: HashSet<usize> = among.iter().copied().collect();
+        let mut collisions: Vec<usize> = Vec::new();
+        for other in self.collision_candidates(&collider.bbox)? {
+            if other == id || !among.contains(&other) {
+                continue
+            }
+            if self.collider(other)?.is_some_and(|other| collider.overlaps(&other)) {
+                collisions.push(other);
+            }
+        }
+        Ok(collisions)
+    }
+
+    /// Instances of `among` whose masks (or bounding boxes, if not `precise`) touch `shape`
+    pub fn shape_collisions(&self, shape: &Shape, among: &[usize], precise: bool) -> Result<Vec<usize>, String> {
+        let among: HashSet<usize> = among.iter().copied().collect();
+        let mut collisions: Vec<usize> = Vec::new();
+        for id in self.collision_candidates(&shape.bbox())? {
+            if !among.contains(&id) {
+                continue
+            }
+            if self.collider(id)?.is_some_and(|collider| collider.hits(shape, precise)) {
+                collisions.push(id);
+            }
+        }
+        Ok(collisions)
+    }
+
+    pub fn instances_collide(&self, a: usize, b: usize) -> Result<bool, String> {
+        let (Some(a), Some(b)) = (self.collider(a)?, self.collider(b)?) else { return Ok(false) };
+        Ok(a.overlaps(&b))
+    }
+
+    /// Instances of `other_object` (or its children) other than `id` which might collide with `bbox`
+    pub fn collision_event_candidates(&self, id: usize, bbox: &BoundingBox, other_object: usize) -> Result<Vec<usize>, String> {
+        Ok(self.collision_candidates(bbox)?.into_iter()
+            .filter(|other| *other != id && self.instances.get(*other).is_ok_and(|i| self.instances.is_object_or_child(i.object_index, other_object)))
+            .collect())
+    }
+
+    /// Fires Collision events for every colliding pair whose objects (or their parents) handle it.
+    /// If either instance is solid, both are moved back to their previous positions first;
+    /// afterwards the instance moves on with its speed if that no longer collides.
+    pub fn collision_events(&mut self) -> Result<(), String> {
+        let handlers: Vec<(usize, usize)> = self.events.collision_handlers().to_vec();
+        if handlers.is_empty() {
+            return Ok(())
+        }
+        for id in self.instances.living_ids() {
+            if !self.instances.exists(id) {
+                continue
+            }
+            let object_index: usize = self.instances.get(id)?.object_index;
+            let mut other_objects: Vec<usize> = handlers.iter()
+                .filter(|(owner, _)| self.instances.is_object_or_child(object_index, *owner))
+                .map(|(_, other_object)| *other_object)
+                .collect();
+            other_objects.sort_unstable();
+            other_objects.dedup();
+
+            for other_object in other_objects {
+                let Some(collider) = self.collider(id)? else { break };
+                let bbox: BoundingBox = collider.bbox;
+                for other in self.collision_event_candidates(id, &bbox, other_object)? {
+                    if !self.instances.exists(id) {
+                        return Ok(())
+                    }
+                    if !self.instances.exists(other) || !self.instances_collide(id, other)? {
+                        continue
+                    }
+                    self.handle_collision(id, other, other_object)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_collision(&mut self, id: usize, other: usize, other_object: usize) -> Result<(), String> {
+        let solid: bool = self.instances.get(id)?.builtins.solid || self.instances.get(other)?.builtins.solid;
+        if solid {
+            for instance in [id, other] {
+                let builtins: &mut InstanceBuiltins = &mut self.instances.get_mut(instance)?.builtins;
+                builtins.x = builtins.xprevious;
+                builtins.y = builtins.yprevious;
+                self.mark_moved(instance);
+            }
+        }
+        self.fire_event_with_other(id, InstanceRef::Instance(other), Event::new(EV_COLLISION, other_object as u32))?;
+        if solid && self.instances.exists(id) && self.instances.exists(other) {
+            let builtins: &InstanceBuiltins = &self.instances.get(id)?.builtins;
+            let (x, y): (f64, f64) = (builtins.x + builtins.hspeed(), builtins.y + builtins.vspeed());
+            if self.collisions_at(id, x, y, &[other])?.is_empty() {
+                let builtins: &mut InstanceBuiltins = &mut self.instances.get_mut(id)?.builtins;
+                builtins.x = x;
+                builtins.y = y;
+                self.mark_moved(id);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+use libgm::GMData;
+use crate::App;
+use crate::code::call::CallFrame;
+use crate::code::value::Value;
+use crate::instance::{InstanceBuiltins, InstanceRef, ALARM_COUNT};
+
+/// Event types as numbered by GameMaker
+pub const EV_CREATE: u32 = 0;
+pub const EV_DESTROY: u32 = 1;
+pub const EV_ALARM: u32 = 2;
+pub const EV_STEP: u32 = 3;
+/// The subtype is the other object's index
+pub const EV_COLLISION: u32 = 4;
+/// The subtype of the keyboard events is the virtual key code
+pub const EV_KEYBOARD: u32 = 5;
+pub const EV_MOUSE: u32 = 6;
+pub const EV_OTHER: u32 = 7;
+pub const EV_DRAW: u32 = 8;
+pub const EV_KEYPRESS: u32 = 9;
+pub const EV_KEYRELEASE: u32 = 10;
+pub const EV_CLEANUP: u32 = 12;
+
+/// Subtypes of `EV_STEP`
+pub const EV_STEP_NORMAL: u32 = 0;
+pub const EV_STEP_BEGIN: u32 = 1;
+pub const EV_STEP_END: u32 = 2;
+
+/// Subtypes of `EV_DRAW`
+pub const EV_DRAW_NORMAL: u32 = 0;
+pub const EV_DRAW_GUI: u32 = 64;
+pub const EV_DRAW_BEGIN: u32 = 72;
+pub const EV_DRAW_END: u32 = 73;
+pub const EV_DRAW_GUI_BEGIN: u32 = 74;
+pub const EV_DRAW_GUI_END: u32 = 75;
+
+/// Draw events drawn once per view, in room coordinates
+pub const DRAW_WORLD: [u32; 3] = [EV_DRAW_BEGIN, EV_DRAW_NORMAL, EV_DRAW_END];
+/// Draw events drawn once per frame, in application surface coordinates
+pub const DRAW_GUI: [u32; 3] = [EV_DRAW_GUI_BEGIN, EV_DRAW_GUI, EV_DRAW_GUI_END];
+
+/// Subtypes of `EV_OTHER`
+pub const EV_GAME_START: u32 = 2;
+pub const EV_GAME_END: u32 = 3;
+pub const EV_ROOM_START: u32 = 4;
+pub const EV_ROOM_END: u32 = 5;
+pub const EV_ANIMATION_END: u32 = 7;
+pub const EV_USER0: u32 = 10;
+/// The async System event, e.g. for gamepads being connected
+pub const EV_SYSTEM_EVENT: u32 = 75;
+
+/// Index of the map `async_load` refers to during async events; it is -1 otherwise
+pub const ASYNC_LOAD_MAP: i64 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Event {
+    pub event_type: u32,
+    pub subtype: u32,
+}
+impl Event {
+    pub const fn new(event_type: u32, subtype: u32) -> Self {
+        Self { event_type, subtype }
+    }
+}
+
+/// The event a call frame is executing, needed by `event_inherited()`
+#[derive(Debug, Clone, Copy)]
+pub struct EventContext {
+    pub event: Event,
+    /// Object that defines the running event code; may be a parent of the instance's object
+    pub object_index: usize,
+}
+
+/// Code entries of every object's events
+#[derive(Debug, Clone)]
+pub struct EventTable {
+    codes: HashMap<(usize, Event), Vec<usize>>,    // key: (object index, event); value: code indices
+    /// (object index, other object index) of every Collision event
+    collisions: Vec<(usize, usize)>,
+}
+impl EventTable {
+    pub fn new(data: &GMData) -> Self {
+        let mut codes: HashMap<(usize, Event), Vec<usize>> = HashMap::new();
+        for (object_index, object) in data.game_objects.game_objects_by_index.iter().enumerate() {
+            for (event_type, events) in object.events.iter().enumerate() {
+                for event in events {
+                    let code_indices: Vec<usize> = event.actions.iter()
+                        .filter_map(|action| action.code.as_ref().map(|code| code.index))
+                        .collect();
+                    if !code_indices.is_empty() {
+                        codes.insert((object_index, Event::new(event_type as u32, event.subtype)), code_indices);
+                    }
+                }
+            }
+        }
+        let collisions: Vec<(usize, usize)> = codes.keys()
+            .filter(|(_, event)| event.event_type == EV_COLLISION)
+            .map(|(object_index, event)| (*object_index, event.subtype as usize))
+            .collect();
+        Self { codes, collisions }
+    }
+    pub fn get(&self, object_index: usize, event: Event) -> Option<&Vec<usize>> {
+        self.codes.get(&(object_index, event))
+    }
+    pub fn collision_handlers(&self) -> &[(usize, usize)] {
+        &self.collisions
+    }
+}
+
+
+impl App {
+    /// Finds the object defining `event` for `object_index`, starting at the object itself and walking up its parents.
+    fn find_event_owner(&self, object_index: usize, event: Event) -> Option<usize> {
+        let mut current: Option<usize> = Some(object_index);
+        while let Some(object) = current {
+            if self.events.get(object, event).is_some() {
+                return Some(object)
+            }
+            current = self.instances.parent_of(object);
+        }
+        None
+    }
+
+    /// Runs the event code of `owner` (or the nearest parent defining it) with `id` as `self` and `other`.
+    fn run_event_from(&mut self, id: usize, other: InstanceRef, owner: usize, event: Event) -> Result<bool, String> {
+        let Some(owner) = self.find_event_owner(owner, event) else { return Ok(false) };
+        let code_indices: Vec<usize> = self.events.get(owner, event).cloned().unwrap_or_default();
+        for code_index in code_indices {
+            let mut frame = CallFrame::new(code_index, InstanceRef::Instance(id), other.clone(), Vec::new());
+            frame.event = Some(EventContext { event, object_index: owner });
+            self.run_code(frame)?;
+        }
+        Ok(true)
+    }
+
+    /// Fires an event for a single instance; returns whether its object (or a parent) handles the event.
+    pub fn fire_event(&mut self, id: usize, event: Event) -> Result<bool, String> {
+        self.fire_event_with_other(id, InstanceRef::Instance(id), event)
+    }
+
+    pub fn fire_event_with_other(&mut self, id: usize, other: InstanceRef, event: Event) -> Result<bool, String> {
+        if !self.instances.exists(id) {
+            return Ok(false)
+        }
+        self.run_instance_event(id, other, event)
+    }
+
+    /// Fires an event even if the instance is already marked as destroyed, for its Destroy and CleanUp events
+    pub fn fire_destroy_event(&mut self, id: usize, event: Event) -> Result<bool, String> {
+        self.run_instance_event(id, InstanceRef::Instance(id), event)
+    }
+
+    fn run_instance_event(&mut self, id: usize, other: InstanceRef, event: Event) -> Result<bool, String> {
+        let object_index: usize = self.instances.get(id)?.object_index;
+        self.run_event_from(id, other, object_index, event)
+            .map_err(|e| format!("{e}\n↳ in event {}/{} of instance {id}", event.event_type, event.subtype))
+    }
+
+    /// Fires an event for every instance, in creation order.
+    /// Instances created by the event itself do not receive it; destroyed ones are skipped.
+    pub fn fire_event_all(&mut self, event: Event) -> Result<(), String> {
+        for id in self.instances.living_ids() {
+            self.fire_event(id, event)?;
+        }
+        Ok(())
+    }
+
+    /// Fires an async event for every instance with `async_load` holding `values` until the event is over
+    pub fn fire_async_event(&mut self, event: Event, values: HashMap<String, Value>) -> Result<(), String> {
+        let outer: Option<HashMap<String, Value>> = self.async_load.replace(values);
+        let result: Result<(), String> = self.fire_event_all(event);
+        self.async_load = outer;
+        result
+    }
+
+    /// Serves `ds_map_find_value` and `ds_map_exists` on `async_load` during async events.
+    /// Maps are not implemented otherwise, so these calls still count as unimplemented for every other map.
+    pub fn read_async_load(&self, name: &str, arguments: &[Value]) -> Option<Value> {
+        let values: &HashMap<String, Value> = self.async_load.as_ref()?;
+        let [map, key] = arguments else { return None };
+        if map.to_int64().ok()? != ASYNC_LOAD_MAP {
+            return None
+        }
+        let value: Option<&Value> = key.as_str().ok().and_then(|key| values.get(key));
+        match name {
+            "ds_map_find_value" => Some(value.cloned().unwrap_or(Value::Undefined)),
+            "ds_map_exists" => Some(Value::Bool(value.is_some())),
+            _ => None,
+        }
+    }
+
+    /// `event_inherited()`: runs the parent's version of the event that is currently executing
+    pub fn event_inherited(&mut self) -> Result<(), String> {
+        let frame: &CallFrame = self.call_stack.current()?;
+        let context: EventContext = frame.event
+            .ok_or_else(|| "event_inherited() can only be called from an event".to_string())?;
+        let InstanceRef::Instance(id) = frame.self_instance else {
+            return Err("event_inherited() cannot be called on a struct".to_string())
+        };
+        let other: InstanceRef = frame.other_instance.clone();
+        let Some(parent) = self.instances.parent_of(context.object_index) else { return Ok(()) };
+        self.run_event_from(id, other, parent, context.event)?;
+        Ok(())
+    }
+
+    /// One game step, in GameMaker's order
+    pub fn step(&mut self) -> Result<(), String> {
+        for instance in self.instances.list.iter_mut().filter(|i| !i.destroyed) {
+            instance.builtins.xprevious = instance.builtins.x;
+            instance.builtins.yprevious = instance.builtins.y;
+        }
+        self.update_gamepads()?;
+
+        self.fire_event_all(Event::new(EV_STEP, EV_STEP_BEGIN))?;
+        self.update_alarms()?;
+        self.keyboard_events()?;
+        self.mouse_events()?;
+        self.fire_event_all(Event::new(EV_STEP, EV_STEP_NORMAL))?;
+        self.update_motion();
+        self.collision_events()?;
+        self.update_animation()?;
+        self.scroll_room_layers();
+        self.fire_event_all(Event::new(EV_STEP, EV_STEP_END))?;
+        self.update_views();
+
+        self.instances.remove_destroyed();
+        self.keyboard.end_step();
+        self.mouse.end_step();
+        Ok(())
+    }
+
+    /// Counts every active alarm down and fires the alarm event when it reaches 0
+    fn update_alarms(&mut self) -> Result<(), String> {
+        for id in self.instances.living_ids() {
+            for alarm in 0..ALARM_COUNT {
+                let Ok(instance) = self.instances.get_mut(id) else { break };
+                let remaining: &mut i32 = &mut instance.builtins.alarm[alarm];
+                if *remaining <= 0 {
+                    continue
+                }
+                *remaining -= 1;
+                if *remaining == 0 {
+                    // the event may set the alarm again
+                    *remaining = -1;
+                    self.fire_event(id, Event::new(EV_ALARM, alarm as u32))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies friction, gravity and speed to every instance's position
+    fn update_motion(&mut self) {
+        for instance in self.instances.list.iter_mut().filter(|i| !i.destroyed) {
+            let builtins: &mut InstanceBuiltins = &mut instance.builtins;
+            if builtins.friction != 0.0 && builtins.speed() != 0.0 {
+                let speed: f64 = builtins.speed();
+                builtins.set_speed(if speed > 0.0 { (speed - builtins.friction).max(0.0) } else { (speed + builtins.friction).min(0.0) });
+            }
+            if builtins.gravity != 0.0 {
+                let radians: f64 = builtins.gravity_direction.to_radians();
+                builtins.set_hspeed(builtins.hspeed() + builtins.gravity * radians.cos());
+                builtins.set_vspeed(builtins.vspeed() - builtins.gravity * radians.sin());
+            }
+            if builtins.hspeed() != 0.0 || builtins.vspeed() != 0.0 {
+                builtins.x += builtins.hspeed();
+                builtins.y += builtins.vspeed();
+                self.grid.get_mut().mark_dirty(instance.id);
+            }
+        }
+    }
+
+    pub fn end_game(&mut self) -> Result<(), String> {
+        self.fire_event_all(Event::new(EV_OTHER, EV_ROOM_END))?;
+        self.fire_event_all(Event::new(EV_OTHER, EV_GAME_END))?;
+        Ok(())
+    }
+
+    /// Advances every instance's image index and fires Animation End when a sprite wraps around
+    fn update_animation(&mut self) -> Result<(), String> {
+        for id in self.instances.living_ids() {
+            let builtins: &InstanceBuiltins = &self.instances.get(id)?.builtins;
+            if builtins.sprite_index < 0 || builtins.image_speed == 0.0 {
+                continue
+            }
+            let frame_count: f64 = self.sprite_frame_count(i64::from(builtins.sprite_index))? as f64;
+            let builtins: &mut InstanceBuiltins = &mut self.instances.get_mut(id)?.builtins;
+            builtins.image_index += builtins.image_speed;
+            let wrapped: bool = builtins.image_index >= frame_count || builtins.image_index < 0.0;
+            if frame_count > 0.0 {
+                builtins.image_index = builtins.image_index.rem_euclid(frame_count);
+            }
+            if wrapped {
+                self.fire_event(id, Event::new(EV_OTHER, EV_ANIMATION_END))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The given draw events of every visible instance, from the highest depth to the lowest.
+    /// In the Draw event, room backgrounds, tiles and layers are drawn in between according to their depth.
+    pub fn draw_events(&mut self, subtypes: &[u32]) -> Result<(), String> {
+        let mut ids: Vec<(f64, usize)> = self.instances.list.iter()
+            .filter(|i| !i.destroyed && i.builtins.visible)
+            .map(|i| (i.builtins.depth, i.id))
+            .collect();
+        // stable, so instances of equal depth are drawn in creation order
+        ids.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        for &subtype in subtypes {
+            let mut layer: usize = 0;
+            for (depth, id) in &ids {
+                if subtype == EV_DRAW_NORMAL {
+                    // layers are drawn behind instances of the same depth
+                    while self.room_layers.layers.get(layer).is_some_and(|i| i.depth >= *depth) {
+                        self.draw_room_layer(layer);
+                        layer += 1;
+                    }
+                }
+                let handled: bool = self.fire_event(*id, Event::new(EV_DRAW, subtype))?;
+                if subtype == EV_DRAW_NORMAL && !handled && self.instances.exists(*id) {
+                    self.draw_instance_default(*id)?;
+                }
+            }
+            if subtype == EV_DRAW_NORMAL {
+                for remaining in layer..self.room_layers.layers.len() {
+                    self.draw_room_layer(remaining);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
@@ -0,0 +1,267 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use log::{error, info, warn};
+use pixels::{Pixels, SurfaceTexture};
+use winit::application::ApplicationHandler;
+use winit::dpi::PhysicalSize;
+use winit::event::WindowEvent;
+use winit::event_loop::{ActiveEventLoop, ControlFlow};
+use winit::window::{Window, WindowAttributes, WindowId};
+use crate::App;
+use crate::event::{DRAW_GUI, DRAW_WORLD};
+use crate::graphics::scaling::{present, Viewport};
+
+const DEFAULT_GAME_SPEED: f64 = 30.0;
+
+/// What to do when the machine cannot keep up with the game speed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameSkip {
+    /// Always draw after every step; the game runs slower than intended, like in GameMaker
+    Off,
+    /// Run up to this many steps before drawing a frame to catch up; time beyond that is dropped
+    CatchUp(u32),
+}
+impl FrameSkip {
+    /// Parses `off` or the maximum number of steps per drawn frame
+    pub fn parse(string: &str) -> Result<Self, String> {
+        match string.trim() {
+            "off" | "0" | "1" => Ok(FrameSkip::Off),
+            number => {
+                let max_steps: u32 = number.parse()
+                    .map_err(|e| format!("Invalid frame skip policy \"{number}\" (expected \"off\" or a number of steps): {e}"))?;
+                Ok(FrameSkip::CatchUp(max_steps))
+            }
+        }
+    }
+}
+
+/// Keeps the steps in sync with the game speed and measures the actual frame rate
+#[derive(Debug, Clone)]
+pub struct GameClock {
+    pub frame_skip: FrameSkip,
+    /// Steps per second; `room_speed`
+    speed: f64,
+    next_step: Instant,
+    fps_counter: u32,
+    fps_timer: Instant,
+}
+impl GameClock {
+    pub fn new(frame_skip: FrameSkip) -> Self {
+        let now: Instant = Instant::now();
+        Self { frame_skip, speed: DEFAULT_GAME_SPEED, next_step: now, fps_counter: 0, fps_timer: now }
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+    /// Non-positive speeds fall back to the default instead of stopping the game
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = if speed > 0.0 { speed } else { DEFAULT_GAME_SPEED };
+    }
+    pub fn step_duration(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.speed)
+    }
+}
+
+
+impl App {
+    /// Steps per second, as last set by entering a room or assigning `room_speed`
+    pub fn game_speed(&self) -> f64 {
+        self.clock.speed()
+    }
+
+    /// Applies the game speed when entering a room. In GMS2, the game speed is global and assignments
+    /// to `room_speed` outlive room changes; before, every room has its own speed.
+    pub fn enter_room_speed(&mut self, room_speed: u32, game_start: bool) {
+        let speed: f64 = match &self.data.general_info.gms2_info {
+            Some(info) if info.fps > 0.0 => {
+                if !game_start {
+                    return
+                }
+                f64::from(info.fps)
+            }
+            _ => f64::from(room_speed),
+        };
+        self.clock.set_speed(speed);
+    }
+
+    /// Runs as many steps as are due, then draws one frame. Returns whether a frame was drawn.
+    fn tick(&mut self) -> Result<bool, String> {
+        let now: Instant = Instant::now();
+        if now < self.clock.next_step {
+            return Ok(false)
+        }
+
+        let max_steps: u32 = match self.clock.frame_skip {
+            FrameSkip::Off => 1,
+            FrameSkip::CatchUp(max_steps) => max_steps.max(1),
+        };
+        let mut steps: u32 = 0;
+        while steps < max_steps && Instant::now() >= self.clock.next_step {
+            // a room change requested by a catch-up step happens before the next step runs,
+            // the one requested by the last step after drawing as usual
+            if steps > 0 {
+                self.change_room_if_pending()?;
+            }
+            self.step()?;
+            // read after every step so that assignments to `room_speed` apply right away
+            self.clock.next_step += self.clock.step_duration();
+            steps += 1;
+        }
+        // never try to make up for time the policy did not allow to catch up on
+        let now: Instant = Instant::now();
+        if self.clock.next_step < now {
+            self.clock.next_step = now;
+        }
+
+        self.render_frame()?;
+        self.change_room_if_pending()?;
+
+        self.clock.fps_counter += 1;
+        let elapsed: Duration = now.duration_since(self.clock.fps_timer);
+        if elapsed >= Duration::from_secs(1) {
+            self.fps = f64::from(self.clock.fps_counter) / elapsed.as_secs_f64();
+            self.clock.fps_counter = 0;
+            self.clock.fps_timer = now;
+        }
+        Ok(true)
+    }
+
+    /// Draws the room once per visible view (or once without views), then the GUI on top
+    fn render_frame(&mut self) -> Result<(), String> {
+        let color: u32 = if self.current_room.draw_background_color { self.current_room.background_color } else { 0 };
+        let views: Vec<usize> = self.views.drawn();
+        self.canvas.set_view(None);
+        if views.is_empty() {
+            self.canvas.clear(color);
+            self.draw_events(&DRAW_WORLD)?;
+        } else {
+            // parts of the surface not covered by any port
+            self.canvas.clear(0);
+        }
+        for view in views {
+            self.view_current = view;
+            self.canvas.set_view(Some(self.views.views[view].transform()));
+            self.canvas.clear(color);
+            self.draw_events(&DRAW_WORLD)?;
+        }
+        self.view_current = 0;
+        self.canvas.set_view(None);
+        self.draw_events(&DRAW_GUI)?;
+
+        let viewport: Viewport = self.viewport();
+        if let Some(pixels) = &mut self.pixels {
+            present(&self.canvas, pixels.frame_mut(), self.window_width, viewport);
+            pixels.render().map_err(|e| format!("Could not render frame: {e}"))?;
+        }
+        Ok(())
+    }
+
+    /// Where the application surface is drawn in the window
+    pub fn viewport(&self) -> Viewport {
+        Viewport::fit(self.canvas.width, self.canvas.height, self.window_width, self.window_height, self.scale_mode)
+    }
+
+    /// Stops the game loop because of an error; it is returned from `main` once the event loop exits.
+    fn fail(&mut self, event_loop: &ActiveEventLoop, error: String) {
+        error!("{error}");
+        self.error = Some(error);
+        event_loop.exit();
+    }
+}
+
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        info!("Application resumed");
+        if self.window.is_some() {
+            return
+        }
+        let window_attributes = WindowAttributes::default()
+            .with_title(&self.window_title)
+            .with_inner_size(PhysicalSize::new(self.window_width, self.window_height));
+
+        let window: Arc<Window> = match event_loop.create_window(window_attributes) {
+            Ok(window) => Arc::new(window),
+            Err(e) => return self.fail(event_loop, format!("Could not create window: {e}")),
+        };
+        let size: PhysicalSize<u32> = window.inner_size();
+        self.window_width = size.width;
+        self.window_height = size.height;
+
+        // the surface shares ownership of the window, so `Pixels` does not borrow from `App`
+        let surface_texture: SurfaceTexture<Arc<Window>> = SurfaceTexture::new(size.width, size.height, window.clone());
+        // the buffer has the size of the window; the application surface is scaled into it by `present`
+        let pixels: Pixels = match Pixels::new(size.width, size.height, surface_texture) {
+            Ok(pixels) => pixels,
+            Err(e) => return self.fail(event_loop, format!("Failed to create Pixels: {e}")),
+        };
+
+        self.window = Some(window);
+        self.pixels = Some(pixels);
+        self.clock = GameClock::new(self.clock.frame_skip);
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => {
+                info!("The close button was pressed; stopping");
+                if let Err(e) = self.end_game() {
+                    warn!("Error while ending the game: {e}");
+                }
+                event_loop.exit();
+            }
+            WindowEvent::Resized(size) => {
+                // minimised windows have no size
+                if size.width == 0 || size.height == 0 {
+                    return
+                }
+                self.window_width = size.width;
+                self.window_height = size.height;
+                let Some(pixels) = &mut self.pixels else { return };
+                if let Err(e) = pixels.resize_surface(size.width, size.height) {
+                    return self.fail(event_loop, format!("Could not resize surface: {e}"))
+                }
+                if let Err(e) = pixels.resize_buffer(size.width, size.height) {
+                    self.fail(event_loop, format!("Could not resize buffer: {e}"));
+                }
+            }
+            WindowEvent::KeyboardInput { event, .. } => self.handle_key_event(&event),
+            WindowEvent::CursorMoved { position, .. } => {
+                self.mouse.window_x = position.x;
+                self.mouse.window_y = position.y;
+            }
+            WindowEvent::MouseInput { state, button, .. } => self.handle_mouse_button(state, button),
+            WindowEvent::MouseWheel { delta, .. } => self.handle_mouse_wheel(delta),
+            WindowEvent::Focused(false) => {
+                self.keyboard.release_all();
+                self.mouse.release_all();
+            }
+            _ => (),
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if let Err(e) = self.tick() {
+            return self.fail(event_loop, e)
+        }
+        event_loop.set_control_flow(ControlFlow::WaitUntil(self.clock.next_step));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_rate_follows_room_speed() {
+        let mut clock: GameClock = GameClock::new(FrameSkip::Off);
+        assert_eq!(clock.step_duration(), Duration::from_secs_f64(1.0 / 30.0));
+        clock.set_speed(60.0);
+        assert_eq!(clock.speed(), 60.0);
+        assert_eq!(clock.step_duration(), Duration::from_secs_f64(1.0 / 60.0));
+        clock.set_speed(0.0);
+        assert_eq!(clock.speed(), 30.0);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::graphics::texture::{Texture, TextureItem};
+
+/// GameMaker colours are stored as BGR
+pub fn bgr_to_rgb(color: u32) -> [u8; 3] {
+    [color as u8, (color >> 8) as u8, (color >> 16) as u8]
+}
+
+/// How to place a texture item: position, origin and transformation like `draw_sprite_ext`
+#[derive(Debug, Clone, Copy)]
+pub struct DrawParams {
+    pub x: f64,
+    pub y: f64,
+    pub origin_x: f64,
+    pub origin_y: f64,
+    pub xscale: f64,
+    pub yscale: f64,
+    /// Degrees, counterclockwise
+    pub angle: f64,
+    /// BGR colour every pixel is multiplied with
+    pub blend: u32,
+    pub alpha: f64,
+    /// Only draw this part of the frame: (left, top, width, height) in frame coordinates
+    pub region: Option<(f64, f64, f64, f64)>,
+}
+impl DrawParams {
+    pub fn at(x: f64, y: f64) -> Self {
+        Self { x, y, origin_x: 0.0, origin_y: 0.0, xscale: 1.0, yscale: 1.0, angle: 0.0, blend: 0xFFFFFF, alpha: 1.0, region: None }
+    }
+}
+
+/// A corner of a primitive: position in room coordinates and BGR colour
+#[derive(Debug, Clone, Copy)]
+pub struct Vertex {
+    pub x: f64,
+    pub y: f64,
+    pub color: u32,
+    pub alpha: f64,
+}
+impl Vertex {
+    pub fn new(x: f64, y: f64, color: u32, alpha: f64) -> Self {
+        Self { x, y, color, alpha }
+    }
+}
+
+/// Edge function: positive if `p` is on the inner side of the edge from `a` to `b`
+fn edge(a: (f64, f64), b: (f64, f64), p: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+/// Pixels exactly on a top or left edge belong to the triangle, others do not,
+/// so triangles sharing an edge never blend a pixel twice.
+fn is_top_left(a: (f64, f64), b: (f64, f64)) -> bool {
+    let (dx, dy): (f64, f64) = (b.0 - a.0, b.1 - a.1);
+    (dy == 0.0 && dx > 0.0) || dy < 0.0
+}
+
+/// Maps the room area seen by a view onto its port on the canvas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewTransform {
+    pub view_x: f64,
+    pub view_y: f64,
+    pub view_width: f64,
+    pub view_height: f64,
+    pub port_x: f64,
+    pub port_y: f64,
+    pub port_width: f64,
+    pub port_height: f64,
+}
+
+/// The software framebuffer everything is drawn into (GameMaker's application surface);
+/// scaled into the window once per frame.
+#[derive(Debug, Clone)]
+pub struct Canvas {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,    // RGBA
+    /// The view being drawn; without one, room coordinates are canvas coordinates
+    view: Option<ViewTransform>,
+    /// Pixels outside of this are never touched: (left, top, right, bottom)
+    clip: (i64, i64, i64, i64),
+}
+impl Canvas {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+            view: None,
+            clip: (0, 0, i64::from(width), i64::from(height)),
+        }
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width != self.width || height != self.height {
+            *self = Self::new(width, height);
+        }
+    }
+
+    /// Draws into the port of `view` from now on, or onto the whole canvas without a view (used for the GUI)
+    pub fn set_view(&mut self, view: Option<ViewTransform>) {
+        let (width, height): (i64, i64) = (i64::from(self.width), i64::from(self.height));
+        self.clip = match view {
+            Some(view) => (
+                (view.port_x.floor() as i64).clamp(0, width),
+                (view.port_y.floor() as i64).clamp(0, height),
+                ((view.port_x + view.port_width).ceil() as i64).clamp(0, width),
+                ((view.port_y + view.port_height).ceil() as i64).clamp(0, height),
+            ),
+            None => (0, 0, width, height),
+        };
+        self.view = view;
+    }
+
+    /// The part of the room that can currently be drawn to: (left, top, width, height) in room coordinates
+    pub fn visible_area(&self) -> (f64, f64, f64, f64) {
+        match self.view {
+            Some(view) => (view.view_x, view.view_y, view.view_width, view.view_height),
+            None => (0.0, 0.0, f64::from(self.width), f64::from(self.height)),
+        }
+    }
+
+    /// Converts room coordinates and scales into canvas ones. This is exact as long as
+    /// the view has the aspect ratio of its port; otherwise rotated images are slightly off.
+    pub fn to_canvas(&self, params: &DrawParams) -> DrawParams {
+        let Some(view) = self.view else { return *params };
+        let xscale: f64 = view.port_width / view.view_width;
+        let yscale: f64 = view.port_height / view.view_height;
+        DrawParams {
+            x: (params.x - view.view_x) * xscale + view.port_x,
+            y: (params.y - view.view_y) * yscale + view.port_y,
+            xscale: params.xscale * xscale,
+            yscale: params.yscale * yscale,
+            ..*params
+        }
+    }
+
+    /// Converts a point in room coordinates into canvas coordinates
+    pub fn point_to_canvas(&self, x: f64, y: f64) -> (f64, f64) {
+        let Some(view) = self.view else { return (x, y) };
+        (
+            (x - view.view_x) * view.port_width / view.view_width + view.port_x,
+            (y - view.view_y) * view.port_height / view.view_height + view.port_y,
+        )
+    }
+
+    /// How many canvas pixels one room pixel covers horizontally
+    pub fn view_scale(&self) -> f64 {
+        self.view.map_or(1.0, |view| view.port_width / view.view_width)
+    }
+
+    /// Fills a triangle given in room coordinates, interpolating the vertex colours
+    pub fn fill_triangle(&mut self, vertices: [Vertex; 3]) {
+        let mut points: [(f64, f64); 3] = vertices.map(|vertex| self.point_to_canvas(vertex.x, vertex.y));
+        let mut vertices: [Vertex; 3] = vertices;
+        let mut area: f64 = edge(points[0], points[1], points[2]);
+        if area == 0.0 {
+            return
+        }
+        if area < 0.0 {
+            points.swap(1, 2);
+            vertices.swap(1, 2);
+            area = -area;
+        }
+        let colors: [[f64; 4]; 3] = vertices.map(|vertex| {
+            let [r, g, b] = bgr_to_rgb(vertex.color);
+            [f64::from(r), f64::from(g), f64::from(b), vertex.alpha.clamp(0.0, 1.0) * 255.0]
+        });
+
+        let (clip_left, clip_top, clip_right, clip_bottom) = self.clip;
+        let min_x: i64 = (points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min).floor() as i64).max(clip_left);
+        let min_y: i64 = (points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min).floor() as i64).max(clip_top);
+        let max_x: i64 = (points.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max).ceil() as i64).min(clip_right);
+        let max_y: i64 = (points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max).ceil() as i64).min(clip_bottom);
+        let edges: [((f64, f64), (f64, f64)); 3] = [(points[1], points[2]), (points[2], points[0]), (points[0], points[1])];
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let center: (f64, f64) = (x as f64 + 0.5, y as f64 + 0.5);
+                let weights: [f64; 3] = edges.map(|(a, b)| edge(a, b, center));
+                let inside: bool = weights.iter().zip(&edges)
+                    .all(|(weight, (a, b))| *weight > 0.0 || (*weight == 0.0 && is_top_left(*a, *b)));
+                if !inside {
+                    continue
+                }
+                let channel = |i: usize| -> u8 {
+                    ((weights[0] * colors[0][i] + weights[1] * colors[1][i] + weights[2] * colors[2][i]) / area).round() as u8
+                };
+                self.blend_pixel(x, y, [channel(0), channel(1), channel(2), channel(3)]);
+            }
+        }
+    }
+
+    /// Fills the current view's port, or the whole canvas
+    pub fn clear(&mut self, color: u32) {
+        let [r, g, b] = bgr_to_rgb(color);
+        let (left, top, right, bottom) = self.clip;
+        for y in top..bottom {
+            let start: usize = (y as usize * self.width as usize + left as usize) * 4;
+            let end: usize = (y as usize * self.width as usize + right as usize) * 4;
+            for pixel in self.pixels[start..end].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&[r, g, b, 255]);
+            }
+        }
+    }
+
+    /// Alpha-blends a colour onto the pixel; coordinates outside the clipping area are ignored
+    pub fn blend_pixel(&mut self, x: i64, y: i64, [r, g, b, a]: [u8; 4]) {
+        let (left, top, right, bottom) = self.clip;
+        if a == 0 || x < left || y < top || x >= right || y >= bottom {
+            return
+        }
+        let i: usize = (y as usize * self.width as usize + x as usize) * 4;
+        let pixel: &mut [u8] = &mut self.pixels[i..i + 4];
+        if a == 255 {
+            pixel.copy_from_slice(&[r, g, b, 255]);
+            return
+        }
+        let alpha: u32 = u32::from(a);
+        for (destination, source) in pixel.iter_mut().zip([r, g, b]) {
+            *destination = ((u32::from(source) * alpha + u32::from(*destination) * (255 - alpha)) / 255) as u8;
+        }
+    }
+
+    /// Draws a texture item by mapping every covered canvas pixel back onto the texture page,
+    /// so scaling and rotation never leave gaps.
+    pub fn draw_texture_item(&mut self, texture: &Texture, item: &TextureItem, params: &DrawParams) {
+        let params: &DrawParams = &self.to_canvas(params);
+        if params.xscale == 0.0 || params.yscale == 0.0 || params.alpha <= 0.0 || item.target_width == 0 || item.target_height == 0 {
+            return
+        }
+        // the visible part of the frame in frame coordinates
+        let mut left: f64 = f64::from(item.target_x);
+        let mut top: f64 = f64::from(item.target_y);
+        let mut right: f64 = left + f64::from(item.target_width);
+        let mut bottom: f64 = top + f64::from(item.target_height);
+        if let Some((region_left, region_top, region_width, region_height)) = params.region {
+            left = left.max(region_left);
+            top = top.max(region_top);
+            right = right.min(region_left + region_width);
+            bottom = bottom.min(region_top + region_height);
+        }
+        if left >= right || top >= bottom {
+            return
+        }
+
+        let (sin, cos): (f64, f64) = params.angle.to_radians().sin_cos();
+        let frame_to_canvas = |fx: f64, fy: f64| -> (f64, f64) {
+            let dx: f64 = (fx - params.origin_x) * params.xscale;
+            let dy: f64 = (fy - params.origin_y) * params.yscale;
+            // y points down, so a counterclockwise rotation flips the sign of the sine
+            (params.x + dx * cos + dy * sin, params.y - dx * sin + dy * cos)
+        };
+        let corners: [(f64, f64); 4] = [frame_to_canvas(left, top), frame_to_canvas(right, top), frame_to_canvas(left, bottom), frame_to_canvas(right, bottom)];
+        let (clip_left, clip_top, clip_right, clip_bottom) = self.clip;
+        let min_x: i64 = (corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min).floor() as i64).max(clip_left);
+        let min_y: i64 = (corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min).floor() as i64).max(clip_top);
+        let max_x: i64 = (corners.iter().map(|c| c.0).fold(f64::NEG_INFINITY, f64::max).ceil() as i64).min(clip_right);
+        let max_y: i64 = (corners.iter().map(|c| c.1).fold(f64::NEG_INFINITY, f64::max).ceil() as i64).min(clip_bottom);
+
+        let [blend_r, blend_g, blend_b] = bgr_to_rgb(params.blend);
+        let alpha: f64 = params.alpha.min(1.0);
+        let x_ratio: f64 = f64::from(item.source_width) / f64::from(item.target_width);
+        let y_ratio: f64 = f64::from(item.source_height) / f64::from(item.target_height);
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                // sample at the pixel centre
+                let dx: f64 = x as f64 + 0.5 - params.x;
+                let dy: f64 = y as f64 + 0.5 - params.y;
+                let fx: f64 = (dx * cos - dy * sin) / params.xscale + params.origin_x;
+                let fy: f64 = (dx * sin + dy * cos) / params.yscale + params.origin_y;
+                if fx < left || fx >= right || fy < top || fy >= bottom {
+                    continue
+                }
+                let source_x: u32 = item.source_x + ((fx - f64::from(item.target_x)) * x_ratio) as u32;
+                let source_y: u32 = item.source_y + ((fy - f64::from(item.target_y)) * y_ratio) as u32;
+                let [r, g, b, a] = texture.pixel(source_x, source_y);
+                let color: [u8; 4] = [
+                    (u32::from(r) * u32::from(blend_r) / 255) as u8,
+                    (u32::from(g) * u32::from(blend_g) / 255) as u8,
+                    (u32::from(b) * u32::from(blend_b) / 255) as u8,
+                    (f64::from(a) * alpha) as u8,
+                ];
+                self.blend_pixel(x, y, color);
+            }
+        }
+    }
+}
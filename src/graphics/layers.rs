@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use libgm::GMData;
+use libgm::gm::{GMBackground, GMRoom, GMRoomLayerData, GMSprite};
+use crate::App;
+use crate::graphics::canvas::DrawParams;
+use crate::graphics::texture::TextureItem;
+
+/// GMS2 tilemap cells: tile index in the lower bits, transformation flags in the upper ones
+const TILE_INDEX_MASK: u32 = 0x0007FFFF;
+const TILE_MIRROR: u32 = 0x10000000;
+const TILE_FLIP: u32 = 0x20000000;
+const TILE_ROTATE: u32 = 0x40000000;
+
+/// A room background (GMS1) or background layer (GMS2)
+#[derive(Debug, Clone)]
+pub struct Background {
+    pub item: Option<TextureItem>,
+    /// GMS2: fills the layer if there is no image, otherwise the image is blended with it
+    pub color: Option<u32>,
+    pub x: f64,
+    pub y: f64,
+    pub hspeed: f64,
+    pub vspeed: f64,
+    pub tile_horizontally: bool,
+    pub tile_vertically: bool,
+    pub stretch: bool,
+    pub alpha: f64,
+}
+
+/// One tile: a part of a background's texture item
+#[derive(Debug, Clone, Copy)]
+pub struct Tile {
+    pub item: TextureItem,
+    pub params: DrawParams,
+}
+
+#[derive(Debug, Clone)]
+pub enum LayerContent {
+    Background(Background),
+    Tiles(Vec<Tile>),
+}
+
+#[derive(Debug, Clone)]
+pub struct RoomLayer {
+    /// Higher depths are drawn first; GMS1 backgrounds are behind (or, as foregrounds, in front of) everything
+    pub depth: f64,
+    pub visible: bool,
+    pub content: LayerContent,
+}
+
+/// Everything a room draws besides its instances
+#[derive(Debug, Clone, Default)]
+pub struct RoomLayers {
+    pub layers: Vec<RoomLayer>,
+    /// Depth of every GMS2 layer by name and by ID, for `instance_create_layer`
+    pub depths_by_name: HashMap<String, f64>,
+    pub depths_by_id: HashMap<u32, f64>,
+    /// Instances placed on GMS2 instance layers take the layer's depth; key: instance ID
+    pub instance_depths: HashMap<usize, f64>,
+}
+
+fn texture_item(data: &GMData, background: &GMBackground) -> Result<Option<TextureItem>, String> {
+    let Some(item) = &background.texture else { return Ok(None) };
+    Ok(Some(TextureItem::from_gm(item.resolve(&data.texture_page_items.texture_page_items_by_index)?)))
+}
+
+fn sprite_item(data: &GMData, sprite: &GMSprite, frame: usize) -> Result<Option<TextureItem>, String> {
+    let Some(Some(item)) = sprite.textures.get(frame) else { return Ok(None) };
+    Ok(Some(TextureItem::from_gm(item.resolve(&data.texture_page_items.texture_page_items_by_index)?)))
+}
+
+impl RoomLayers {
+    pub fn load(data: &GMData, room: &GMRoom) -> Result<Self, String> {
+        let mut room_layers: RoomLayers = RoomLayers::default();
+
+        // GMS1 backgrounds
+        for background in room.backgrounds.iter().filter(|i| i.enabled) {
+            let Some(definition) = &background.background_definition else { continue };
+            let definition: &GMBackground = definition.resolve(&data.backgrounds.backgrounds_by_index)?;
+            room_layers.layers.push(RoomLayer {
+                depth: if background.foreground { f64::NEG_INFINITY } else { f64::INFINITY },
+                visible: true,
+                content: LayerContent::Background(Background {
+                    item: texture_item(data, definition)?,
+                    color: None,
+                    x: f64::from(background.x),
+                    y: f64::from(background.y),
+                    hspeed: f64::from(background.speed_x),
+                    vspeed: f64::from(background.speed_y),
+                    tile_horizontally: background.tile_x,
+                    tile_vertically: background.tile_y,
+                    stretch: background.stretch,
+                    alpha: 1.0,
+                }),
+            });
+        }
+
+        // GMS1 tiles, grouped into one layer per depth
+        let mut tiles_by_depth: HashMap<i32, Vec<Tile>> = HashMap::new();
+        for tile in &room.tiles {
+            let definition: &GMBackground = tile.background_definition.resolve(&data.backgrounds.backgrounds_by_index)?;
+            let Some(item) = texture_item(data, definition)? else { continue };
+            let (source_x, source_y): (f64, f64) = (f64::from(tile.source_x), f64::from(tile.source_y));
+            let params: DrawParams = DrawParams {
+                origin_x: source_x,
+                origin_y: source_y,
+                xscale: f64::from(tile.scale_x),
+                yscale: f64::from(tile.scale_y),
+                blend: tile.color & 0xFFFFFF,
+                alpha: f64::from(tile.color >> 24) / 255.0,
+                region: Some((source_x, source_y, f64::from(tile.width), f64::from(tile.height))),
+                ..DrawParams::at(f64::from(tile.x), f64::from(tile.y))
+            };
+            tiles_by_depth.entry(tile.tile_depth).or_default().push(Tile { item, params });
+        }
+        for (depth, tiles) in tiles_by_depth {
+            room_layers.layers.push(RoomLayer { depth: f64::from(depth), visible: true, content: LayerContent::Tiles(tiles) });
+        }
+
+        // GMS2 layers
+        for layer in &room.layers {
+            let depth: f64 = f64::from(layer.layer_depth);
+            room_layers.depths_by_name.insert(layer.layer_name.resolve(&data.strings.strings_by_index)?.clone(), depth);
+            room_layers.depths_by_id.insert(layer.layer_id, depth);
+            let content: LayerContent = match &layer.data {
+                GMRoomLayerData::Instances(instances) => {
+                    for id in &instances.instances {
+                        room_layers.instance_depths.insert(*id as usize, depth);
+                    }
+                    continue
+                }
+                GMRoomLayerData::Background(background) => {
+                    let item: Option<TextureItem> = match &background.sprite {
+                        Some(sprite) => sprite_item(data, sprite.resolve(&data.sprites.sprites_by_index)?, background.first_frame as usize)?,
+                        None => None,
+                    };
+                    LayerContent::Background(Background {
+                        item,
+                        color: Some(background.color & 0xFFFFFF),
+                        x: f64::from(layer.x_offset),
+                        y: f64::from(layer.y_offset),
+                        hspeed: f64::from(layer.horizontal_speed),
+                        vspeed: f64::from(layer.vertical_speed),
+                        tile_horizontally: background.tiled_horizontally,
+                        tile_vertically: background.tiled_vertically,
+                        stretch: background.stretch,
+                        alpha: f64::from(background.color >> 24) / 255.0,
+                    })
+                }
+                GMRoomLayerData::Tiles(tilemap) => {
+                    let Some(tileset) = &tilemap.background else { continue };
+                    let tileset: &GMBackground = tileset.resolve(&data.backgrounds.backgrounds_by_index)?;
+                    let Some(item) = texture_item(data, tileset)? else { continue };
+                    LayerContent::Tiles(decode_tilemap(tileset, item, &tilemap.tile_data, f64::from(layer.x_offset), f64::from(layer.y_offset)))
+                }
+                _ => continue,
+            };
+            room_layers.layers.push(RoomLayer { depth, visible: layer.is_visible, content });
+        }
+
+        room_layers.layers.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+        Ok(room_layers)
+    }
+}
+
+/// Turns a GMS2 tilemap into tiles; mirrored, flipped and rotated tiles are transformed around their centre.
+fn decode_tilemap(tileset: &GMBackground, item: TextureItem, rows: &[Vec<u32>], offset_x: f64, offset_y: f64) -> Vec<Tile> {
+    let tile_width: f64 = f64::from(tileset.gms2_tile_width);
+    let tile_height: f64 = f64::from(tileset.gms2_tile_height);
+    let border_x: f64 = f64::from(tileset.gms2_output_border_x);
+    let border_y: f64 = f64::from(tileset.gms2_output_border_y);
+    let columns: u32 = tileset.gms2_tile_columns.max(1);
+
+    let mut tiles: Vec<Tile> = Vec::new();
+    for (row, cells) in rows.iter().enumerate() {
+        for (column, cell) in cells.iter().enumerate() {
+            let tile_index: u32 = cell & TILE_INDEX_MASK;
+            if tile_index == 0 {
+                continue
+            }
+            let source_x: f64 = f64::from(tile_index % columns) * (tile_width + 2.0 * border_x) + border_x;
+            let source_y: f64 = f64::from(tile_index / columns) * (tile_height + 2.0 * border_y) + border_y;
+            let params: DrawParams = DrawParams {
+                origin_x: source_x + tile_width / 2.0,
+                origin_y: source_y + tile_height / 2.0,
+                xscale: if cell & TILE_MIRROR != 0 { -1.0 } else { 1.0 },
+                yscale: if cell & TILE_FLIP != 0 { -1.0 } else { 1.0 },
+                // tiles are rotated clockwise
+                angle: if cell & TILE_ROTATE != 0 { -90.0 } else { 0.0 },
+                region: Some((source_x, source_y, tile_width, tile_height)),
+                ..DrawParams::at(
+                    offset_x + column as f64 * tile_width + tile_width / 2.0,
+                    offset_y + row as f64 * tile_height + tile_height / 2.0,
+                )
+            };
+            tiles.push(Tile { item, params });
+        }
+    }
+    tiles
+}
+
+
+impl App {
+    /// Moves scrolling backgrounds; called once per step
+    pub fn scroll_room_layers(&mut self) {
+        for layer in &mut self.room_layers.layers {
+            if let LayerContent::Background(background) = &mut layer.content {
+                background.x += background.hspeed;
+                background.y += background.vspeed;
+            }
+        }
+    }
+
+    pub fn draw_room_layer(&mut self, index: usize) {
+        let Some(layer) = self.room_layers.layers.get(index) else { return };
+        if !layer.visible {
+            return
+        }
+        match &layer.content {
+            LayerContent::Tiles(tiles) => {
+                for tile in tiles {
+                    let Some(texture) = self.texture_pages.pages.get(tile.item.page) else { continue };
+                    self.canvas.draw_texture_item(texture, &tile.item, &tile.params);
+                }
+            }
+            LayerContent::Background(background) => {
+                if let Some(color) = background.color {
+                    if background.item.is_none() {
+                        self.canvas.clear(color);
+                    }
+                }
+                let Some(item) = &background.item else { return };
+                let Some(texture) = self.texture_pages.pages.get(item.page) else { return };
+                let width: f64 = f64::from(item.bounding_width);
+                let height: f64 = f64::from(item.bounding_height);
+                if width <= 0.0 || height <= 0.0 {
+                    return
+                }
+                let (xscale, yscale): (f64, f64) = if background.stretch {
+                    (f64::from(self.current_room.width) / width, f64::from(self.current_room.height) / height)
+                } else {
+                    (1.0, 1.0)
+                };
+                let (tile_width, tile_height): (f64, f64) = (width * xscale, height * yscale);
+
+                // with tiling, start one tile before the visible area so that scrolled backgrounds have no gaps
+                let (left, top, visible_width, visible_height) = self.canvas.visible_area();
+                let (start_x, count_x): (f64, usize) = if background.tile_horizontally {
+                    (left + (background.x - left).rem_euclid(tile_width) - tile_width, (visible_width / tile_width).ceil() as usize + 2)
+                } else {
+                    (background.x, 1)
+                };
+                let (start_y, count_y): (f64, usize) = if background.tile_vertically {
+                    (top + (background.y - top).rem_euclid(tile_height) - tile_height, (visible_height / tile_height).ceil() as usize + 2)
+                } else {
+                    (background.y, 1)
+                };
+                for row in 0..count_y {
+                    for column in 0..count_x {
+                        let params: DrawParams = DrawParams {
+                            xscale,
+                            yscale,
+                            blend: background.color.unwrap_or(0xFFFFFF),
+                            alpha: background.alpha,
+                            ..DrawParams::at(start_x + column as f64 * tile_width, start_y + row as f64 * tile_height)
+                        };
+                        self.canvas.draw_texture_item(texture, item, &params);
+                    }
+                }
+            }
+        }
+    }
+}
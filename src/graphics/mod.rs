@@ -0,0 +1,10 @@
+pub mod canvas;
+pub mod layers;
+pub mod primitives;
+pub mod scaling;
+pub mod sprite;
+pub mod state;
+pub mod text;
+pub mod texture;
+pub mod view;
+mod qoi;
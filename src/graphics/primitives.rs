@@ -0,0 +1,193 @@
+use std::f64::consts::TAU;
+use crate::App;
+use crate::graphics::canvas::Vertex;
+use crate::graphics::text::mix_colors;
+
+/// Kinds of `draw_primitive_begin`, numbered like GameMaker's `pr_*` constants
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrimitiveKind {
+    PointList,
+    LineList,
+    LineStrip,
+    TriangleList,
+    TriangleStrip,
+    TriangleFan,
+}
+impl PrimitiveKind {
+    pub fn from_int(value: i64) -> Result<Self, String> {
+        match value {
+            1 => Ok(PrimitiveKind::PointList),
+            2 => Ok(PrimitiveKind::LineList),
+            3 => Ok(PrimitiveKind::LineStrip),
+            4 => Ok(PrimitiveKind::TriangleList),
+            5 => Ok(PrimitiveKind::TriangleStrip),
+            6 => Ok(PrimitiveKind::TriangleFan),
+            other => Err(format!("Invalid primitive type {other}")),
+        }
+    }
+}
+
+/// A primitive between `draw_primitive_begin` and `draw_primitive_end`
+#[derive(Debug, Clone)]
+pub struct Primitive {
+    pub kind: PrimitiveKind,
+    pub vertices: Vec<Vertex>,
+}
+
+/// `draw_healthbar` directions: the side the bar grows from
+const HEALTHBAR_LEFT: i64 = 0;
+const HEALTHBAR_RIGHT: i64 = 1;
+const HEALTHBAR_TOP: i64 = 2;
+
+
+impl App {
+    /// Fills the pixels from (x1, y1) to (x2, y2), both inclusive like in GameMaker.
+    /// Colours are top left, top right, bottom right, bottom left.
+    pub fn fill_rectangle(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, colors: [u32; 4], alpha: f64) {
+        let (left, right): (f64, f64) = (x1.min(x2), x1.max(x2) + 1.0);
+        let (top, bottom): (f64, f64) = (y1.min(y2), y1.max(y2) + 1.0);
+        let [top_left, top_right, bottom_right, bottom_left] = colors;
+        let corners: [Vertex; 4] = [
+            Vertex::new(left, top, top_left, alpha),
+            Vertex::new(right, top, top_right, alpha),
+            Vertex::new(right, bottom, bottom_right, alpha),
+            Vertex::new(left, bottom, bottom_left, alpha),
+        ];
+        self.canvas.fill_triangle([corners[0], corners[1], corners[2]]);
+        self.canvas.fill_triangle([corners[0], corners[2], corners[3]]);
+    }
+
+    /// A one pixel wide outline along the edges of `fill_rectangle`
+    pub fn outline_rectangle(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, colors: [u32; 4], alpha: f64) {
+        let (left, right): (f64, f64) = (x1.min(x2), x1.max(x2));
+        let (top, bottom): (f64, f64) = (y1.min(y2), y1.max(y2));
+        let [top_left, top_right, bottom_right, bottom_left] = colors;
+        self.fill_rectangle(left, top, right, top, [top_left, top_right, top_right, top_left], alpha);
+        self.fill_rectangle(left, bottom, right, bottom, [bottom_left, bottom_right, bottom_right, bottom_left], alpha);
+        if bottom - top >= 2.0 {
+            self.fill_rectangle(left, top + 1.0, left, bottom - 1.0, [top_left, top_left, bottom_left, bottom_left], alpha);
+            self.fill_rectangle(right, top + 1.0, right, bottom - 1.0, [top_right, top_right, bottom_right, bottom_right], alpha);
+        }
+    }
+
+    /// A line from the centre of pixel `from` to the centre of pixel `to`, as a quad `width` pixels wide
+    pub fn draw_line_between(&mut self, from: Vertex, to: Vertex, width: f64) {
+        let (dx, dy): (f64, f64) = (to.x - from.x, to.y - from.y);
+        let length: f64 = dx.hypot(dy);
+        let half: f64 = width.max(1.0 / self.canvas.view_scale()) / 2.0;
+        // a zero-length line is a dot; longer ones cover their end pixels completely
+        let (ux, uy): (f64, f64) = if length == 0.0 { (1.0, 0.0) } else { (dx / length, dy / length) };
+        let (nx, ny): (f64, f64) = (-uy * half, ux * half);
+        let (ex, ey): (f64, f64) = (ux * half, uy * half);
+        let (fx, fy): (f64, f64) = (from.x + 0.5 - ex, from.y + 0.5 - ey);
+        let (tx, ty): (f64, f64) = (to.x + 0.5 + ex, to.y + 0.5 + ey);
+        let corners: [Vertex; 4] = [
+            Vertex { x: fx + nx, y: fy + ny, ..from },
+            Vertex { x: tx + nx, y: ty + ny, ..to },
+            Vertex { x: tx - nx, y: ty - ny, ..to },
+            Vertex { x: fx - nx, y: fy - ny, ..from },
+        ];
+        self.canvas.fill_triangle([corners[0], corners[1], corners[2]]);
+        self.canvas.fill_triangle([corners[0], corners[2], corners[3]]);
+    }
+
+    /// Points around an ellipse, as many as the circle precision
+    fn ellipse_points(&self, x: f64, y: f64, radius_x: f64, radius_y: f64) -> Vec<(f64, f64)> {
+        let precision: u32 = self.draw_state.circle_precision;
+        (0..precision)
+            .map(|i| {
+                let angle: f64 = TAU * f64::from(i) / f64::from(precision);
+                (x + radius_x * angle.cos(), y - radius_y * angle.sin())
+            })
+            .collect()
+    }
+
+    /// `inner` is the colour at the centre, `outer` the one at the edge
+    pub fn draw_ellipse_with(&mut self, (x, y, radius_x, radius_y): (f64, f64, f64, f64), inner: u32, outer: u32, outline: bool) {
+        let alpha: f64 = self.draw_state.alpha;
+        let points: Vec<(f64, f64)> = self.ellipse_points(x, y, radius_x, radius_y);
+        for (i, point) in points.iter().enumerate() {
+            let next: (f64, f64) = points[(i + 1) % points.len()];
+            if outline {
+                self.draw_line_between(Vertex::new(point.0, point.1, outer, alpha), Vertex::new(next.0, next.1, outer, alpha), 1.0);
+            } else {
+                self.canvas.fill_triangle([
+                    Vertex::new(x, y, inner, alpha),
+                    Vertex::new(point.0, point.1, outer, alpha),
+                    Vertex::new(next.0, next.1, outer, alpha),
+                ]);
+            }
+        }
+    }
+
+    pub fn draw_triangle_with(&mut self, vertices: [Vertex; 3], outline: bool) {
+        if outline {
+            for (from, to) in [(0, 1), (1, 2), (2, 0)] {
+                self.draw_line_between(vertices[from], vertices[to], 1.0);
+            }
+        } else {
+            self.canvas.fill_triangle(vertices);
+        }
+    }
+
+    /// `amount` is 0 to 100; the bar colour goes from `min_color` to `max_color` with it
+    pub fn draw_healthbar_with(&mut self, (x1, y1, x2, y2): (f64, f64, f64, f64), amount: f64, colors: (u32, u32, u32), direction: i64, show_back: bool, show_border: bool) {
+        let alpha: f64 = self.draw_state.alpha;
+        let (back_color, min_color, max_color) = colors;
+        let amount: f64 = amount.clamp(0.0, 100.0) / 100.0;
+        if show_back {
+            self.fill_rectangle(x1, y1, x2, y2, [back_color; 4], alpha);
+        }
+        if amount > 0.0 {
+            let bar_color: u32 = mix_colors(min_color, max_color, amount);
+            let (width, height): (f64, f64) = ((x2 - x1) * amount, (y2 - y1) * amount);
+            let (left, top, right, bottom): (f64, f64, f64, f64) = match direction {
+                HEALTHBAR_LEFT => (x1, y1, x1 + width, y2),
+                HEALTHBAR_RIGHT => (x2 - width, y1, x2, y2),
+                HEALTHBAR_TOP => (x1, y1, x2, y1 + height),
+                _ => (x1, y2 - height, x2, y2),
+            };
+            self.fill_rectangle(left, top, right, bottom, [bar_color; 4], alpha);
+        }
+        if show_border {
+            self.outline_rectangle(x1, y1, x2, y2, [0; 4], alpha);
+        }
+    }
+
+    /// Draws the primitive started by `draw_primitive_begin`
+    pub fn draw_primitive(&mut self, primitive: Primitive) {
+        let vertices: &[Vertex] = &primitive.vertices;
+        match primitive.kind {
+            PrimitiveKind::PointList => {
+                for vertex in vertices {
+                    self.fill_rectangle(vertex.x, vertex.y, vertex.x, vertex.y, [vertex.color; 4], vertex.alpha);
+                }
+            }
+            PrimitiveKind::LineList => {
+                for pair in vertices.chunks_exact(2) {
+                    self.draw_line_between(pair[0], pair[1], 1.0);
+                }
+            }
+            PrimitiveKind::LineStrip => {
+                for pair in vertices.windows(2) {
+                    self.draw_line_between(pair[0], pair[1], 1.0);
+                }
+            }
+            PrimitiveKind::TriangleList => {
+                for triangle in vertices.chunks_exact(3) {
+                    self.canvas.fill_triangle([triangle[0], triangle[1], triangle[2]]);
+                }
+            }
+            PrimitiveKind::TriangleStrip => {
+                for triangle in vertices.windows(3) {
+                    self.canvas.fill_triangle([triangle[0], triangle[1], triangle[2]]);
+                }
+            }
+            PrimitiveKind::TriangleFan => {
+                for pair in vertices.get(1..).unwrap_or_default().windows(2) {
+                    self.canvas.fill_triangle([vertices[0], pair[0], pair[1]]);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! GameMaker's QOI variant. It is not compatible with regular QOI: the header is `fioq`
+//! (`qoif` reversed), the opcodes differ and the whole image may additionally be BZip2-compressed (`2zoq`).
+
+use std::io::Read;
+use bzip2::read::BzDecoder;
+
+pub const QOI_MAGIC: &[u8; 4] = b"fioq";
+pub const BZ2_QOI_MAGIC: &[u8; 4] = b"2zoq";
+
+const QOI_HEADER_SIZE: usize = 12;
+
+const QOI_INDEX: u8 = 0x00;     // 00xxxxxx
+const QOI_RUN_8: u8 = 0x40;     // 010xxxxx
+const QOI_RUN_16: u8 = 0x60;    // 011xxxxx
+const QOI_DIFF_8: u8 = 0x80;    // 10xxxxxx
+const QOI_DIFF_16: u8 = 0xC0;   // 110xxxxx
+const QOI_DIFF_24: u8 = 0xE0;   // 1110xxxx
+const QOI_COLOR: u8 = 0xF0;     // 1111xxxx
+
+const MASK_2: u8 = 0xC0;
+const MASK_3: u8 = 0xE0;
+const MASK_4: u8 = 0xF0;
+
+/// Sign-extends the `bits` lowest bits of `value`; differences are stored in two's complement
+fn signed(value: u32, bits: u32) -> u8 {
+    (((value << (32 - bits)) as i32) >> (32 - bits)) as u8
+}
+
+fn read_u16(data: &[u8], position: usize) -> Result<u16, String> {
+    data.get(position..position + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| "QOI image header is truncated".to_string())
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+    end: usize,
+}
+impl Reader<'_> {
+    fn has_more(&self) -> bool {
+        self.position < self.end
+    }
+    fn read_byte(&mut self) -> Result<u8, String> {
+        let byte: u8 = *self.data.get(self.position).ok_or_else(|| "QOI image data is truncated".to_string())?;
+        self.position += 1;
+        Ok(byte)
+    }
+}
+
+/// Decodes a `fioq` image into RGBA pixels; returns (width, height, pixels).
+pub fn decode_qoi(data: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
+    if !data.starts_with(QOI_MAGIC) {
+        return Err("Invalid QOI header".to_string())
+    }
+    let width: usize = usize::from(read_u16(data, 4)?);
+    let height: usize = usize::from(read_u16(data, 6)?);
+    let length: usize = data.get(8..12)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+        .ok_or_else(|| "QOI image header is truncated".to_string())?;
+    let mut reader = Reader { data, position: QOI_HEADER_SIZE, end: (QOI_HEADER_SIZE + length).min(data.len()) };
+
+    let mut pixels: Vec<u8> = vec![0; width * height * 4];
+    let mut index: [[u8; 4]; 64] = [[0; 4]; 64];
+    let (mut r, mut g, mut b, mut a): (u8, u8, u8, u8) = (0, 0, 0, 255);
+    let mut run: usize = 0;
+
+    for pixel in pixels.chunks_exact_mut(4) {
+        if run > 0 {
+            run -= 1;
+        } else if reader.has_more() {
+            let b1: u8 = reader.read_byte()?;
+            if b1 & MASK_2 == QOI_INDEX {
+                [r, g, b, a] = index[usize::from(b1 ^ QOI_INDEX)];
+            } else if b1 & MASK_3 == QOI_RUN_8 {
+                run = usize::from(b1 & 0x1F);
+            } else if b1 & MASK_3 == QOI_RUN_16 {
+                run = ((usize::from(b1 & 0x1F) << 8) | usize::from(reader.read_byte()?)) + 32;
+            } else if b1 & MASK_2 == QOI_DIFF_8 {
+                let merged: u32 = u32::from(b1);
+                r = r.wrapping_add(signed((merged >> 4) & 0x3, 2));
+                g = g.wrapping_add(signed((merged >> 2) & 0x3, 2));
+                b = b.wrapping_add(signed(merged & 0x3, 2));
+            } else if b1 & MASK_3 == QOI_DIFF_16 {
+                let merged: u32 = u32::from(b1) << 8 | u32::from(reader.read_byte()?);
+                r = r.wrapping_add(signed((merged >> 8) & 0x1F, 5));
+                g = g.wrapping_add(signed((merged >> 4) & 0xF, 4));
+                b = b.wrapping_add(signed(merged & 0xF, 4));
+            } else if b1 & MASK_4 == QOI_DIFF_24 {
+                let merged: u32 = u32::from(b1) << 16 | u32::from(reader.read_byte()?) << 8 | u32::from(reader.read_byte()?);
+                r = r.wrapping_add(signed((merged >> 15) & 0x1F, 5));
+                g = g.wrapping_add(signed((merged >> 10) & 0x1F, 5));
+                b = b.wrapping_add(signed((merged >> 5) & 0x1F, 5));
+                a = a.wrapping_add(signed(merged & 0x1F, 5));
+            } else if b1 & MASK_4 == QOI_COLOR {
+                if b1 & 8 != 0 { r = reader.read_byte()?; }
+                if b1 & 4 != 0 { g = reader.read_byte()?; }
+                if b1 & 2 != 0 { b = reader.read_byte()?; }
+                if b1 & 1 != 0 { a = reader.read_byte()?; }
+            }
+            index[usize::from((r ^ g ^ b ^ a) & 63)] = [r, g, b, a];
+        }
+        pixel.copy_from_slice(&[r, g, b, a]);
+    }
+
+    Ok((width as u32, height as u32, pixels))
+}
+
+/// Decodes a `2zoq` image: a BZip2-compressed `fioq` image.
+/// Since GameMaker 2022.5 the header also contains the uncompressed size.
+pub fn decode_bz2_qoi(data: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
+    if !data.starts_with(BZ2_QOI_MAGIC) {
+        return Err("Invalid BZ2-QOI header".to_string())
+    }
+    let header_size: usize = if data.get(8..).is_some_and(|rest| rest.starts_with(b"BZh")) { 8 } else { 12 };
+    let compressed: &[u8] = data.get(header_size..)
+        .ok_or_else(|| "BZ2-QOI image header is truncated".to_string())?;
+    let mut decompressed: Vec<u8> = Vec::new();
+    BzDecoder::new(compressed).read_to_end(&mut decompressed)
+        .map_err(|e| format!("Could not decompress BZ2-QOI image: {e}"))?;
+    decode_qoi(&decompressed)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4x1 image: one full colour, then one pixel of each diff kind, using the extreme
+    /// negative and positive values of the fields so that the sign bits matter
+    const IMAGE: &[u8] = &[
+        b'f', b'i', b'o', b'q', 4, 0, 1, 0, 11, 0, 0, 0,
+        0xFF, 10, 20, 30, 255,  // colour (10, 20, 30, 255)
+        0x9E,                   // diff 8: r +1, g -1, b -2
+        0xD0, 0x78,             // diff 16: r -16, g +7, b -8
+        0xE7, 0xFC, 0x70,       // diff 24: r +15, g -1, b +3, a -16
+    ];
+
+    /// UndertaleModTool's QoiConverter, e.g. `(b1 & 48) << 26 >> 30` for the red difference of diff 8
+    fn reference_difference(merged: i32, mask: i32, shift_left: u32, shift_right: u32) -> u8 {
+        ((merged & mask) << shift_left >> shift_right) as u8
+    }
+
+    #[test]
+    fn decodes_twos_complement_differences() {
+        assert_eq!(signed((0x9E >> 4) & 0x3, 2), reference_difference(0x9E, 48, 26, 30));
+        assert_eq!(signed((0xD078 >> 8) & 0x1F, 5), reference_difference(0xD078, 7936, 19, 27));
+        assert_eq!(signed(0xE7FC70 & 0x1F, 5), reference_difference(0xE7FC70, 31, 27, 27));
+
+        let (width, height, pixels) = decode_qoi(IMAGE).unwrap();
+        assert_eq!((width, height), (4, 1));
+        assert_eq!(pixels, [
+            10, 20, 30, 255,
+            11, 19, 28, 255,
+            251, 26, 20, 255,
+            10, 25, 23, 239,
+        ]);
+    }
+
+    #[test]
+    fn repeats_runs_and_indexed_colors() {
+        // red 200, a run of two more, red 7, then red 200 again from the index (slot 200 ^ 255 = 55)
+        let image: &[u8] = &[b'f', b'i', b'o', b'q', 5, 0, 1, 0, 6, 0, 0, 0, 0xF8, 200, 0x41, 0xF8, 7, 55];
+        let (_, _, pixels) = decode_qoi(image).unwrap();
+        let reds: Vec<u8> = pixels.chunks_exact(4).map(|pixel| pixel[0]).collect();
+        assert_eq!(reds, [200, 200, 200, 7, 200]);
+    }
+
+    #[test]
+    fn rejects_other_headers() {
+        assert!(decode_qoi(b"qoif\0\0\0\0\0\0\0\0").is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use crate::graphics::canvas::Canvas;
+
+/// How the application surface is fitted into the window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScaleMode {
+    /// As large as possible while keeping the aspect ratio; the rest of the window is black
+    Letterbox,
+    /// Like `Letterbox`, but only by whole multiples so that every pixel stays square and sharp
+    Integer,
+    /// Fills the whole window, ignoring the aspect ratio (GameMaker's "full scale")
+    Stretch,
+}
+impl ScaleMode {
+    pub fn parse(string: &str) -> Result<Self, String> {
+        match string.trim() {
+            "letterbox" => Ok(ScaleMode::Letterbox),
+            "integer" => Ok(ScaleMode::Integer),
+            "stretch" => Ok(ScaleMode::Stretch),
+            other => Err(format!("Invalid scaling mode \"{other}\" (expected \"letterbox\", \"integer\" or \"stretch\")")),
+        }
+    }
+}
+
+/// Where the application surface ends up in the window, in window pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+impl Viewport {
+    pub fn fit(surface_width: u32, surface_height: u32, window_width: u32, window_height: u32, mode: ScaleMode) -> Self {
+        if mode == ScaleMode::Stretch || surface_width == 0 || surface_height == 0 {
+            return Self { x: 0, y: 0, width: window_width, height: window_height }
+        }
+        let mut scale: f64 = (f64::from(window_width) / f64::from(surface_width))
+            .min(f64::from(window_height) / f64::from(surface_height));
+        // a window smaller than the surface can only be letterboxed
+        if mode == ScaleMode::Integer && scale >= 1.0 {
+            scale = scale.floor();
+        }
+        let width: u32 = ((f64::from(surface_width) * scale) as u32).min(window_width);
+        let height: u32 = ((f64::from(surface_height) * scale) as u32).min(window_height);
+        Self { x: (window_width - width) / 2, y: (window_height - height) / 2, width, height }
+    }
+
+    /// Converts window coordinates to application surface coordinates; they are outside of it in the black bars
+    pub fn to_surface(&self, surface_width: u32, surface_height: u32, x: f64, y: f64) -> (f64, f64) {
+        if self.width == 0 || self.height == 0 {
+            return (0.0, 0.0)
+        }
+        (
+            (x - f64::from(self.x)) * f64::from(surface_width) / f64::from(self.width),
+            (y - f64::from(self.y)) * f64::from(surface_height) / f64::from(self.height),
+        )
+    }
+}
+
+/// Copies the canvas into the window's frame (RGBA, `frame_width` pixels wide) with nearest-neighbour scaling
+pub fn present(canvas: &Canvas, frame: &mut [u8], frame_width: u32, viewport: Viewport) {
+    frame.fill(0);
+    for pixel in frame.chunks_exact_mut(4) {
+        pixel[3] = 255;
+    }
+    if canvas.width == 0 || canvas.height == 0 {
+        return
+    }
+    let columns: Vec<usize> = (0..viewport.width)
+        .map(|x| (u64::from(x) * u64::from(canvas.width) / u64::from(viewport.width)) as usize)
+        .collect();
+    for y in 0..viewport.height {
+        let source_y: usize = (u64::from(y) * u64::from(canvas.height) / u64::from(viewport.height)) as usize;
+        let source_row: &[u8] = &canvas.pixels[source_y * canvas.width as usize * 4..][..canvas.width as usize * 4];
+        let start: usize = ((viewport.y + y) as usize * frame_width as usize + viewport.x as usize) * 4;
+        let Some(row) = frame.get_mut(start..start + viewport.width as usize * 4) else { break };
+        for (pixel, source_x) in row.chunks_exact_mut(4).zip(&columns) {
+            pixel.copy_from_slice(&source_row[source_x * 4..source_x * 4 + 4]);
+        }
+    }
+}
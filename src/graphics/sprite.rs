@@ -0,0 +1,69 @@
+use std::sync::Arc;
+use libgm::GMData;
+use libgm::gm::{GMSprite, GMTexturePageItem};
+use crate::App;
+use crate::graphics::canvas::DrawParams;
+use crate::graphics::texture::TextureItem;
+use crate::instance::InstanceBuiltins;
+
+impl App {
+    pub fn sprite(&self, sprite_index: i64) -> Result<&GMSprite, String> {
+        usize::try_from(sprite_index).ok()
+            .and_then(|index| self.data.sprites.sprites_by_index.get(index))
+            .ok_or_else(|| format!("Sprite {sprite_index} does not exist"))
+    }
+
+    pub fn sprite_frame_count(&self, sprite_index: i64) -> Result<usize, String> {
+        Ok(self.sprite(sprite_index)?.textures.len())
+    }
+
+    /// The texture item of a frame; the image index wraps around like in GameMaker
+    fn sprite_frame(&self, sprite: &GMSprite, image_index: f64) -> Result<Option<TextureItem>, String> {
+        if sprite.textures.is_empty() {
+            return Ok(None)
+        }
+        let frame: usize = (image_index.floor() as i64).rem_euclid(sprite.textures.len() as i64) as usize;
+        let Some(item) = &sprite.textures[frame] else { return Ok(None) };
+        let item: &GMTexturePageItem = item.resolve(&self.data.texture_page_items.texture_page_items_by_index)?;
+        Ok(Some(TextureItem::from_gm(item)))
+    }
+
+    /// Draws a sprite frame; `params` has the sprite's origin filled in unless only a part of it is drawn.
+    pub fn draw_sprite_with(&mut self, sprite_index: i64, image_index: f64, mut params: DrawParams) -> Result<(), String> {
+        let data: Arc<GMData> = self.data.clone();
+        let sprite: &GMSprite = usize::try_from(sprite_index).ok()
+            .and_then(|index| data.sprites.sprites_by_index.get(index))
+            .ok_or_else(|| format!("Sprite {sprite_index} does not exist"))?;
+        let Some(item) = self.sprite_frame(sprite, image_index)? else { return Ok(()) };
+        if params.region.is_none() {
+            params.origin_x = f64::from(sprite.origin_x);
+            params.origin_y = f64::from(sprite.origin_y);
+        }
+        let Some(texture) = self.texture_pages.pages.get(item.page) else {
+            return Err(format!("Texture page {} of sprite {sprite_index} does not exist", item.page))
+        };
+        self.canvas.draw_texture_item(texture, &item, &params);
+        Ok(())
+    }
+
+    /// What an instance without a Draw event looks like: its sprite with all of its image properties
+    pub fn draw_instance_default(&mut self, id: usize) -> Result<(), String> {
+        let builtins: InstanceBuiltins = self.instances.get(id)?.builtins.clone();
+        if builtins.sprite_index < 0 {
+            return Ok(())
+        }
+        self.draw_sprite_with(i64::from(builtins.sprite_index), builtins.image_index, instance_params(&builtins))
+    }
+}
+
+/// Draw parameters taken from an instance's built-in variables
+pub fn instance_params(builtins: &InstanceBuiltins) -> DrawParams {
+    DrawParams {
+        xscale: builtins.image_xscale,
+        yscale: builtins.image_yscale,
+        angle: builtins.image_angle,
+        blend: builtins.image_blend,
+        alpha: builtins.image_alpha,
+        ..DrawParams::at(builtins.x, builtins.y)
+    }
+}
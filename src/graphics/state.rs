@@ -0,0 +1,67 @@
+use crate::graphics::primitives::Primitive;
+
+/// GameMaker's `c_black`: the initial draw colour
+pub const DEFAULT_DRAW_COLOR: u32 = 0x000000;
+/// Number of segments circles are made of
+pub const DEFAULT_CIRCLE_PRECISION: u32 = 24;
+
+/// `fa_left`/`fa_center`/`fa_right` and `fa_top`/`fa_middle`/`fa_bottom`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+}
+impl Align {
+    pub fn from_int(value: i64) -> Result<Self, String> {
+        match value {
+            0 => Ok(Align::Start),
+            1 => Ok(Align::Center),
+            2 => Ok(Align::End),
+            other => Err(format!("Invalid text alignment {other}")),
+        }
+    }
+    pub fn to_int(self) -> i64 {
+        match self {
+            Align::Start => 0,
+            Align::Center => 1,
+            Align::End => 2,
+        }
+    }
+    /// How much of `size` lies before the anchor
+    pub fn offset(self, size: f64) -> f64 {
+        match self {
+            Align::Start => 0.0,
+            Align::Center => (size / 2.0).floor(),
+            Align::End => size,
+        }
+    }
+}
+
+/// Settings changed by `draw_set_*` which apply to every following draw call
+#[derive(Debug, Clone)]
+pub struct DrawState {
+    /// Font index, or -1 for the default font
+    pub font: i64,
+    pub halign: Align,
+    pub valign: Align,
+    /// BGR
+    pub color: u32,
+    pub alpha: f64,
+    pub circle_precision: u32,
+    /// Vertices collected since `draw_primitive_begin`
+    pub primitive: Option<Primitive>,
+}
+impl Default for DrawState {
+    fn default() -> Self {
+        Self {
+            font: -1,
+            halign: Align::Start,
+            valign: Align::Start,
+            color: DEFAULT_DRAW_COLOR,
+            alpha: 1.0,
+            circle_precision: DEFAULT_CIRCLE_PRECISION,
+            primitive: None,
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use libgm::GMData;
+use libgm::gm::{GMFont, GMTexturePageItem};
+use log::warn;
+use crate::App;
+use crate::graphics::canvas::DrawParams;
+use crate::graphics::state::{Align, DrawState};
+use crate::graphics::texture::TextureItem;
+
+/// One character of a bitmap font; the rectangle is relative to the font's texture item
+#[derive(Debug, Clone)]
+pub struct Glyph {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Distance the cursor advances after this character
+    pub shift: f64,
+    /// Horizontal distance between the cursor and the glyph's image
+    pub offset: f64,
+    /// Extra advance when following a given character
+    pub kerning: HashMap<char, f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Font {
+    pub item: TextureItem,
+    pub glyphs: HashMap<char, Glyph>,
+    pub line_height: f64,
+    pub xscale: f64,
+    pub yscale: f64,
+}
+impl Font {
+    fn load(data: &GMData, font: &GMFont) -> Result<Self, String> {
+        let item: &GMTexturePageItem = font.texture.resolve(&data.texture_page_items.texture_page_items_by_index)?;
+        let mut glyphs: HashMap<char, Glyph> = HashMap::new();
+        for glyph in &font.glyphs {
+            let Some(character) = char::from_u32(u32::from(glyph.character)) else { continue };
+            let kerning: HashMap<char, f64> = glyph.kernings.iter()
+                .filter_map(|kerning| Some((char::from_u32(kerning.other_char as u32)?, f64::from(kerning.amount))))
+                .collect();
+            glyphs.insert(character, Glyph {
+                x: f64::from(glyph.x),
+                y: f64::from(glyph.y),
+                width: f64::from(glyph.width),
+                height: f64::from(glyph.height),
+                shift: f64::from(glyph.shift_modifier),
+                offset: f64::from(glyph.offset),
+                kerning,
+            });
+        }
+        // GameMaker measures lines by the height of "M"
+        let line_height: f64 = match glyphs.get(&'M') {
+            Some(glyph) => glyph.height,
+            None => glyphs.values().map(|glyph| glyph.height).fold(0.0, f64::max),
+        };
+        Ok(Self {
+            item: TextureItem::from_gm(item),
+            glyphs,
+            line_height,
+            xscale: f64::from(font.scale_x),
+            yscale: f64::from(font.scale_y),
+        })
+    }
+
+    pub fn line_width(&self, line: &str) -> f64 {
+        let mut width: f64 = 0.0;
+        let mut previous: Option<char> = None;
+        for character in line.chars() {
+            if let Some(glyph) = self.glyphs.get(&character) {
+                width += glyph.shift + kerning(glyph, previous);
+            }
+            previous = Some(character);
+        }
+        width
+    }
+
+    /// Breaks a line at spaces so that no part is wider than `max_width`; a single long word is never split.
+    fn wrap(&self, line: String, max_width: f64) -> Vec<String> {
+        if max_width < 0.0 {
+            return vec![line]
+        }
+        let mut lines: Vec<String> = Vec::new();
+        let mut current: String = String::new();
+        for word in line.split(' ') {
+            let candidate: String = if current.is_empty() { word.to_string() } else { format!("{current} {word}") };
+            if !current.is_empty() && self.line_width(&candidate) > max_width {
+                lines.push(std::mem::replace(&mut current, word.to_string()));
+            } else {
+                current = candidate;
+            }
+        }
+        lines.push(current);
+        lines
+    }
+
+    /// The lines a text is drawn as
+    pub fn layout(&self, text: &str, max_width: f64, hash_newlines: bool) -> Vec<String> {
+        split_lines(text, hash_newlines).into_iter()
+            .flat_map(|line| self.wrap(line, max_width))
+            .collect()
+    }
+
+    /// Width and height of a text; a negative separation uses the font's line height
+    pub fn text_size(&self, text: &str, separation: f64, max_width: f64, hash_newlines: bool) -> (f64, f64) {
+        let lines: Vec<String> = self.layout(text, max_width, hash_newlines);
+        let line_height: f64 = if separation < 0.0 { self.line_height } else { separation };
+        let width: f64 = lines.iter().map(|line| self.line_width(line)).fold(0.0, f64::max);
+        (width, line_height * lines.len() as f64)
+    }
+}
+
+fn kerning(glyph: &Glyph, previous: Option<char>) -> f64 {
+    previous.and_then(|previous| glyph.kerning.get(&previous)).copied().unwrap_or(0.0)
+}
+
+/// Line breaks are `\n`, `\r\n` and `\r`; before GMS2 `#` is one too, unless written as `\#`.
+pub fn split_lines(text: &str, hash_newlines: bool) -> Vec<String> {
+    let mut lines: Vec<String> = vec![String::new()];
+    let mut characters = text.chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '\r' => {
+                characters.next_if_eq(&'\n');
+                lines.push(String::new());
+            }
+            '\n' => lines.push(String::new()),
+            '#' if hash_newlines => lines.push(String::new()),
+            '\\' if hash_newlines && characters.next_if_eq(&'#').is_some() => lines.last_mut().unwrap().push('#'),
+            other => lines.last_mut().unwrap().push(other),
+        }
+    }
+    lines
+}
+
+/// Mixes two BGR colours; `amount` 0 is `a`, 1 is `b`
+pub fn mix_colors(a: u32, b: u32, amount: f64) -> u32 {
+    let mut color: u32 = 0;
+    for shift in [0, 8, 16] {
+        let from: f64 = f64::from((a >> shift) & 0xFF);
+        let to: f64 = f64::from((b >> shift) & 0xFF);
+        color |= ((from + (to - from) * amount).round() as u32 & 0xFF) << shift;
+    }
+    color
+}
+
+
+/// Every font of the game; fonts which cannot be loaded are `None`
+#[derive(Debug, Clone, Default)]
+pub struct Fonts {
+    pub fonts: Vec<Option<Font>>,
+}
+impl Fonts {
+    pub fn load(data: &GMData) -> Self {
+        let fonts: Vec<Option<Font>> = data.fonts.fonts_by_index.iter()
+            .enumerate()
+            .map(|(i, font)| Font::load(data, font).inspect_err(|e| warn!("Could not load font {i}: {e}")).ok())
+            .collect();
+        Self { fonts }
+    }
+
+    /// The runner has no built-in font, so the default font (-1) is the game's first one
+    pub fn get(&self, index: i64) -> Option<&Font> {
+        if index < 0 {
+            return self.fonts.iter().flatten().next()
+        }
+        usize::try_from(index).ok().and_then(|i| self.fonts.get(i)).and_then(Option::as_ref)
+    }
+}
+
+/// Everything about a `draw_text*` call besides position and string
+#[derive(Debug, Clone, Copy)]
+pub struct TextParams {
+    /// Distance between lines; negative uses the font's line height
+    pub separation: f64,
+    /// Lines are wrapped to this width; negative disables wrapping
+    pub max_width: f64,
+    pub xscale: f64,
+    pub yscale: f64,
+    pub angle: f64,
+    /// BGR gradient: top left, top right, bottom right, bottom left
+    pub colors: [u32; 4],
+    pub alpha: f64,
+}
+impl TextParams {
+    pub fn new(state: &DrawState) -> Self {
+        Self { separation: -1.0, max_width: -1.0, xscale: 1.0, yscale: 1.0, angle: 0.0, colors: [state.color; 4], alpha: state.alpha }
+    }
+}
+
+
+impl App {
+    /// Size of a text in the current font, unscaled
+    pub fn text_size(&self, text: &str, separation: f64, max_width: f64) -> (f64, f64) {
+        match self.fonts.get(self.draw_state.font) {
+            Some(font) => font.text_size(text, separation, max_width, self.hash_newlines),
+            None => (0.0, 0.0),
+        }
+    }
+
+    /// Draws a text in the current font and alignment; the gradient is approximated per glyph.
+    pub fn draw_text_with(&mut self, x: f64, y: f64, text: &str, params: &TextParams) {
+        let Some(font) = self.fonts.get(self.draw_state.font) else { return };
+        let Some(texture) = self.texture_pages.pages.get(font.item.page) else { return };
+        let lines: Vec<String> = font.layout(text, params.max_width, self.hash_newlines);
+        let line_height: f64 = if params.separation < 0.0 { font.line_height } else { params.separation };
+        let total_width: f64 = lines.iter().map(|line| font.line_width(line)).fold(0.0, f64::max);
+        let total_height: f64 = line_height * lines.len() as f64;
+        let top: f64 = -self.draw_state.valign.offset(total_height);
+        let box_left: f64 = -self.draw_state.halign.offset(total_width);
+
+        for (row, line) in lines.iter().enumerate() {
+            let line_top: f64 = top + row as f64 * line_height;
+            let mut cursor: f64 = -self.draw_state.halign.offset(font.line_width(line));
+            let mut previous: Option<char> = None;
+            for character in line.chars() {
+                let Some(glyph) = font.glyphs.get(&character) else {
+                    previous = Some(character);
+                    continue
+                };
+                cursor += kerning(glyph, previous);
+                if glyph.width > 0.0 && glyph.height > 0.0 {
+                    let left: f64 = cursor + glyph.offset;
+                    let horizontal: f64 = if total_width > 0.0 { (left + glyph.width / 2.0 - box_left) / total_width } else { 0.0 };
+                    let vertical: f64 = if total_height > 0.0 { (line_top + glyph.height / 2.0 - top) / total_height } else { 0.0 };
+                    let [top_left, top_right, bottom_right, bottom_left] = params.colors;
+                    let blend: u32 = mix_colors(
+                        mix_colors(top_left, top_right, horizontal.clamp(0.0, 1.0)),
+                        mix_colors(bottom_left, bottom_right, horizontal.clamp(0.0, 1.0)),
+                        vertical.clamp(0.0, 1.0),
+                    );
+                    // placing the origin relative to the glyph keeps scaling and rotation around the text's anchor
+                    let draw_params: DrawParams = DrawParams {
+                        origin_x: glyph.x - left,
+                        origin_y: glyph.y - line_top,
+                        xscale: params.xscale * font.xscale,
+                        yscale: params.yscale * font.yscale,
+                        angle: params.angle,
+                        blend,
+                        alpha: params.alpha,
+                        region: Some((glyph.x, glyph.y, glyph.width, glyph.height)),
+                        ..DrawParams::at(x, y)
+                    };
+                    self.canvas.draw_texture_item(texture, &font.item, &draw_params);
+                }
+                cursor += glyph.shift;
+                previous = Some(character);
+            }
+        }
+    }
+}
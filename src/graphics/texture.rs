@@ -0,0 +1,112 @@
+use libgm::GMData;
+use log::warn;
+use crate::graphics::qoi::{decode_bz2_qoi, decode_qoi, BZ2_QOI_MAGIC, QOI_MAGIC};
+
+const PNG_MAGIC: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+/// A decoded texture page
+#[derive(Debug, Clone)]
+pub struct Texture {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,    // RGBA
+}
+impl Texture {
+    pub fn decode(data: &[u8]) -> Result<Self, String> {
+        let (width, height, pixels) = if data.starts_with(PNG_MAGIC) {
+            decode_png(data)?
+        } else if data.starts_with(QOI_MAGIC) {
+            decode_qoi(data)?
+        } else if data.starts_with(BZ2_QOI_MAGIC) {
+            decode_bz2_qoi(data)?
+        } else {
+            let header: &[u8] = &data[..data.len().min(4)];
+            return Err(format!("Unknown texture format with header {header:02X?}"))
+        };
+        Ok(Self { width, height, pixels })
+    }
+    pub fn empty() -> Self {
+        Self { width: 0, height: 0, pixels: Vec::new() }
+    }
+    /// Transparent outside of the texture
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        if x >= self.width || y >= self.height {
+            return [0; 4]
+        }
+        let i: usize = (y as usize * self.width as usize + x as usize) * 4;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]]
+    }
+}
+
+fn decode_png(data: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
+    let mut decoder: png::Decoder<&[u8]> = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader: png::Reader<&[u8]> = decoder.read_info().map_err(|e| format!("Could not read PNG header: {e}"))?;
+    let mut buffer: Vec<u8> = vec![0; reader.output_buffer_size()];
+    let info: png::OutputInfo = reader.next_frame(&mut buffer).map_err(|e| format!("Could not decode PNG: {e}"))?;
+    buffer.truncate(info.buffer_size());
+
+    let pixels: Vec<u8> = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer.chunks_exact(3).flat_map(|i| [i[0], i[1], i[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => buffer.chunks_exact(2).flat_map(|i| [i[0], i[0], i[0], i[1]]).collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|i| [*i, *i, *i, 255]).collect(),
+        png::ColorType::Indexed => return Err("Indexed PNG was not expanded".to_string()),
+    };
+    Ok((info.width, info.height, pixels))
+}
+
+
+/// Every texture page of the game, decoded once at startup
+#[derive(Debug, Clone)]
+pub struct TexturePages {
+    pub pages: Vec<Texture>,
+}
+impl TexturePages {
+    /// Pages which cannot be decoded are logged and left empty, so the game can still run without them
+    pub fn load(data: &GMData) -> Self {
+        let pages: Vec<Texture> = data.embedded_textures.texture_pages_by_index.iter()
+            .enumerate()
+            .map(|(i, page)| Texture::decode(&page.texture_data).unwrap_or_else(|e| {
+                warn!("Could not decode texture page {i}: {e}");
+                Texture::empty()
+            }))
+            .collect();
+        Self { pages }
+    }
+}
+
+/// The part of a texture page holding one sprite frame (or background), trimmed to its visible pixels
+#[derive(Debug, Clone, Copy)]
+pub struct TextureItem {
+    pub page: usize,
+    pub source_x: u32,
+    pub source_y: u32,
+    pub source_width: u32,
+    pub source_height: u32,
+    /// Where the trimmed pixels go within the untrimmed frame
+    pub target_x: u32,
+    pub target_y: u32,
+    pub target_width: u32,
+    pub target_height: u32,
+    /// Size of the untrimmed frame
+    pub bounding_width: u32,
+    pub bounding_height: u32,
+}
+impl TextureItem {
+    pub fn from_gm(item: &libgm::gm::GMTexturePageItem) -> Self {
+        Self {
+            page: item.texture_page.index,
+            source_x: u32::from(item.source_x),
+            source_y: u32::from(item.source_y),
+            source_width: u32::from(item.source_width),
+            source_height: u32::from(item.source_height),
+            target_x: u32::from(item.target_x),
+            target_y: u32::from(item.target_y),
+            target_width: u32::from(item.target_width),
+            target_height: u32::from(item.target_height),
+            bounding_width: u32::from(item.bounding_width),
+            bounding_height: u32::from(item.bounding_height),
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use libgm::gm::GMRoom;
+use crate::App;
+use crate::graphics::canvas::ViewTransform;
+
+/// GameMaker rooms always have 8 views (GMS2: cameras assigned to 8 viewports)
+pub const VIEW_COUNT: usize = 8;
+
+/// A room view. GMS2 cameras are not separate objects here: camera `i` is the camera of view `i`.
+#[derive(Debug, Clone, Default)]
+pub struct View {
+    pub visible: bool,
+    /// The part of the room that is seen
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Where it is drawn on the application surface
+    pub port_x: f64,
+    pub port_y: f64,
+    pub port_width: f64,
+    pub port_height: f64,
+    /// Minimum distance the followed instance keeps from the view's edges
+    pub border_x: f64,
+    pub border_y: f64,
+    /// Maximum scroll speed while following; negative means no limit
+    pub speed_x: f64,
+    pub speed_y: f64,
+    /// Instance ID or object index to follow
+    pub target: Option<usize>,
+}
+impl View {
+    pub fn transform(&self) -> ViewTransform {
+        ViewTransform {
+            view_x: self.x,
+            view_y: self.y,
+            view_width: self.width,
+            view_height: self.height,
+            port_x: self.port_x,
+            port_y: self.port_y,
+            port_width: self.port_width,
+            port_height: self.port_height,
+        }
+    }
+
+    fn is_drawable(&self) -> bool {
+        self.visible && self.width > 0.0 && self.height > 0.0 && self.port_width > 0.0 && self.port_height > 0.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Views {
+    pub enabled: bool,
+    pub views: Vec<View>,
+}
+impl Default for Views {
+    fn default() -> Self {
+        Self { enabled: false, views: vec![View::default(); VIEW_COUNT] }
+    }
+}
+impl Views {
+    pub fn load(room: &GMRoom) -> Self {
+        let mut views: Vec<View> = room.views.iter()
+            .take(VIEW_COUNT)
+            .map(|view| View {
+                visible: view.enabled,
+                x: f64::from(view.view_x),
+                y: f64::from(view.view_y),
+                width: f64::from(view.view_width),
+                height: f64::from(view.view_height),
+                port_x: f64::from(view.port_x),
+                port_y: f64::from(view.port_y),
+                port_width: f64::from(view.port_width),
+                port_height: f64::from(view.port_height),
+                border_x: f64::from(view.border_x),
+                border_y: f64::from(view.border_y),
+                speed_x: f64::from(view.speed_x),
+                speed_y: f64::from(view.speed_y),
+                target: view.object.as_ref().map(|object| object.index),
+            })
+            .collect();
+        views.resize(VIEW_COUNT, View::default());
+        Self { enabled: room.flags.enable_views, views }
+    }
+
+    /// Indices of the views to draw; empty if the room is drawn without views
+    pub fn drawn(&self) -> Vec<usize> {
+        if !self.enabled {
+            return Vec::new()
+        }
+        (0..self.views.len()).filter(|i| self.views[*i].is_drawable()).collect()
+    }
+
+    /// Size of the application surface: the bounding box of all visible ports, or the room without views
+    pub fn surface_size(&self, room_width: u32, room_height: u32) -> (u32, u32) {
+        let drawn: Vec<usize> = self.drawn();
+        if drawn.is_empty() {
+            return (room_width.max(1), room_height.max(1))
+        }
+        let right: f64 = drawn.iter().map(|i| self.views[*i].port_x + self.views[*i].port_width).fold(1.0, f64::max);
+        let bottom: f64 = drawn.iter().map(|i| self.views[*i].port_y + self.views[*i].port_height).fold(1.0, f64::max);
+        (right.ceil() as u32, bottom.ceil() as u32)
+    }
+
+    pub fn get(&self, index: i64) -> Result<&View, String> {
+        usize::try_from(index).ok().and_then(|i| self.views.get(i))
+            .ok_or_else(|| format!("View {index} out of range (there are {VIEW_COUNT} views)"))
+    }
+
+    pub fn get_mut(&mut self, index: i64) -> Result<&mut View, String> {
+        usize::try_from(index).ok().and_then(|i| self.views.get_mut(i))
+            .ok_or_else(|| format!("View {index} out of range (there are {VIEW_COUNT} views)"))
+    }
+}
+
+/// Moves `position` (the view's left or top edge) so that `target` stays `border` away from the edges,
+/// scrolling at most `speed` and never past the room.
+fn follow_axis(position: f64, size: f64, target: f64, border: f64, speed: f64, room_size: f64) -> f64 {
+    let mut wanted: f64 = position;
+    if target - border < position {
+        wanted = target - border;
+    } else if target + border > position + size {
+        wanted = target + border - size;
+    }
+    if speed >= 0.0 {
+        wanted = position + (wanted - position).clamp(-speed, speed);
+    }
+    wanted.min(room_size - size).max(0.0)
+}
+
+
+impl App {
+    /// Scrolls every view that follows an instance; called once per step
+    pub fn update_views(&mut self) {
+        if !self.views.enabled {
+            return
+        }
+        let room_width: f64 = f64::from(self.current_room.width);
+        let room_height: f64 = f64::from(self.current_room.height);
+        for index in 0..self.views.views.len() {
+            let Some(target) = self.views.views[index].target else { continue };
+            // an instance ID, otherwise the first instance of an object
+            let id: Option<usize> = if self.instances.exists(target) {
+                Some(target)
+            } else {
+                self.instances.ids_of_object(target).first().copied()
+            };
+            let Some(Ok(instance)) = id.map(|id| self.instances.get(id)) else { continue };
+            let (x, y): (f64, f64) = (instance.builtins.x, instance.builtins.y);
+            let view: &mut View = &mut self.views.views[index];
+            view.x = follow_axis(view.x, view.width, x, view.border_x, view.speed_x, room_width);
+            view.y = follow_axis(view.y, view.height, y, view.border_y, view.speed_y, room_height);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use gilrs::{Axis, Button, EventType, GamepadId, Gilrs};
+use crate::input::gamepad::{GamepadBackend, GamepadEvent};
+
+/// Real controllers through gilrs (evdev on Linux, XInput on Windows)
+pub struct GilrsBackend {
+    gilrs: Gilrs,
+    /// Device number of every gamepad seen so far; gilrs' IDs are opaque
+    devices: HashMap<GamepadId, usize>,
+    /// Gamepads which were already connected when the runner started
+    initial: Vec<GamepadEvent>,
+}
+impl GilrsBackend {
+    pub fn new() -> Result<Self, String> {
+        let gilrs: Gilrs = Gilrs::new().map_err(|e| format!("Could not initialise gilrs: {e}"))?;
+        let mut backend = Self { gilrs, devices: HashMap::new(), initial: Vec::new() };
+        let connected: Vec<(GamepadId, String)> = backend.gilrs.gamepads()
+            .map(|(id, gamepad)| (id, gamepad.name().to_string()))
+            .collect();
+        for (id, description) in connected {
+            let device: usize = backend.device(id);
+            backend.initial.push(GamepadEvent::Connected { device, description });
+        }
+        Ok(backend)
+    }
+
+    fn device(&mut self, id: GamepadId) -> usize {
+        let next: usize = self.devices.len();
+        *self.devices.entry(id).or_insert(next)
+    }
+}
+
+impl std::fmt::Debug for GilrsBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GilrsBackend").field("devices", &self.devices).finish()
+    }
+}
+
+/// Index of the `gp_*` button; triggers are `gp_shoulderlb`/`gp_shoulderrb`
+fn button_index(button: Button) -> Option<usize> {
+    Some(match button {
+        Button::South => 0,
+        Button::East => 1,
+        Button::West => 2,
+        Button::North => 3,
+        Button::LeftTrigger => 4,
+        Button::RightTrigger => 5,
+        Button::LeftTrigger2 => 6,
+        Button::RightTrigger2 => 7,
+        Button::Select => 8,
+        Button::Start => 9,
+        Button::LeftThumb => 10,
+        Button::RightThumb => 11,
+        Button::DPadUp => 12,
+        Button::DPadDown => 13,
+        Button::DPadLeft => 14,
+        Button::DPadRight => 15,
+        _ => return None,
+    })
+}
+
+/// Index of the `gp_axis*` axis and its sign; gilrs' Y axes point up, GameMaker's down
+fn axis_index(axis: Axis) -> Option<(usize, f64)> {
+    match axis {
+        Axis::LeftStickX => Some((0, 1.0)),
+        Axis::LeftStickY => Some((1, -1.0)),
+        Axis::RightStickX => Some((2, 1.0)),
+        Axis::RightStickY => Some((3, -1.0)),
+        _ => None,
+    }
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn name(&self) -> &'static str {
+        "gilrs"
+    }
+
+    fn poll(&mut self) -> Vec<GamepadEvent> {
+        let mut events: Vec<GamepadEvent> = std::mem::take(&mut self.initial);
+        while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+            let device: usize = self.device(id);
+            let translated: Option<GamepadEvent> = match event {
+                EventType::Connected => Some(GamepadEvent::Connected { device, description: self.gilrs.gamepad(id).name().to_string() }),
+                EventType::Disconnected => Some(GamepadEvent::Disconnected { device }),
+                EventType::ButtonPressed(button, _) => button_index(button).map(|button| GamepadEvent::Button { device, button, value: 1.0 }),
+                EventType::ButtonReleased(button, _) => button_index(button).map(|button| GamepadEvent::Button { device, button, value: 0.0 }),
+                EventType::ButtonChanged(button, value, _) => button_index(button).map(|button| GamepadEvent::Button { device, button, value: f64::from(value) }),
+                EventType::AxisChanged(axis, value, _) => axis_index(axis).map(|(axis, sign)| GamepadEvent::Axis { device, axis, value: f64::from(value) * sign }),
+                _ => None,
+            };
+            events.extend(translated);
+        }
+        events
+    }
+}
@@ -0,0 +1,220 @@
+#[cfg(feature = "gilrs")]
+pub mod gilrs_backend;
+pub mod virtual_pad;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use log::{info, warn};
+use crate::App;
+use crate::code::value::Value;
+use crate::event::{Event, EV_OTHER, EV_SYSTEM_EVENT};
+
+/// GameMaker offers this many gamepad slots (4 XInput and 8 DirectInput ones on Windows)
+pub const GAMEPAD_SLOTS: usize = 12;
+
+/// `gp_face1`: the first of the button constants, which are numbered consecutively
+pub const GP_FACE1: u32 = 0x8001;
+/// `gp_axislh`: the first of the axis constants
+pub const GP_AXISLH: u32 = 0x8011;
+pub const BUTTON_COUNT: usize = 16;
+pub const AXIS_COUNT: usize = 4;
+
+/// Button and axis names as used by GameMaker's `gp_*` constants, without the prefix
+const BUTTON_NAMES: [&str; BUTTON_COUNT] = [
+    "face1", "face2", "face3", "face4", "shoulderl", "shoulderr", "shoulderlb", "shoulderrb",
+    "select", "start", "stickl", "stickr", "padu", "padd", "padl", "padr",
+];
+const AXIS_NAMES: [&str; AXIS_COUNT] = ["axislh", "axislv", "axisrh", "axisrv"];
+
+/// Index of a button (0 is `gp_face1`) from its `gp_*` name, with or without the prefix
+pub fn button_by_name(name: &str) -> Option<usize> {
+    let name: &str = name.strip_prefix("gp_").unwrap_or(name);
+    BUTTON_NAMES.iter().position(|button| *button == name)
+}
+
+/// Index of an axis (0 is `gp_axislh`) from its `gp_*` name, with or without the prefix
+pub fn axis_by_name(name: &str) -> Option<usize> {
+    let name: &str = name.strip_prefix("gp_").unwrap_or(name);
+    AXIS_NAMES.iter().position(|axis| *axis == name)
+}
+
+/// A change reported by a backend; `device` identifies the gamepad within its backend.
+/// Axis values go from -1 to 1, with positive values to the right and down like in GameMaker.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent {
+    Connected { device: usize, description: String },
+    Disconnected { device: usize },
+    /// Button index (0 is `gp_face1`) and how far it is pressed, from 0 to 1
+    Button { device: usize, button: usize, value: f64 },
+    Axis { device: usize, axis: usize, value: f64 },
+}
+
+/// A source of gamepad input: real controllers or the scripted virtual pad
+pub trait GamepadBackend: Debug {
+    fn name(&self) -> &'static str;
+    /// Everything that happened since the last call; called once per step
+    fn poll(&mut self) -> Vec<GamepadEvent>;
+}
+
+/// The state of one gamepad slot
+#[derive(Debug, Clone)]
+pub struct Gamepad {
+    pub connected: bool,
+    pub description: String,
+    pub buttons: [f64; BUTTON_COUNT],
+    pub axes: [f64; AXIS_COUNT],
+    pressed: HashSet<usize>,
+    released: HashSet<usize>,
+    /// `gamepad_set_axis_deadzone`: smaller axis values read as 0
+    pub deadzone: f64,
+    /// `gamepad_set_button_threshold`: how far a button has to be pressed to count as held
+    pub button_threshold: f64,
+}
+impl Default for Gamepad {
+    fn default() -> Self {
+        Self {
+            connected: false,
+            description: String::new(),
+            buttons: [0.0; BUTTON_COUNT],
+            axes: [0.0; AXIS_COUNT],
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+            deadzone: 0.15,
+            button_threshold: 0.5,
+        }
+    }
+}
+impl Gamepad {
+    fn is_held(&self, button: usize) -> bool {
+        self.buttons[button] >= self.button_threshold
+    }
+
+    fn set_button(&mut self, button: usize, value: f64) {
+        let was_held: bool = self.is_held(button);
+        self.buttons[button] = value.clamp(0.0, 1.0);
+        match (was_held, self.is_held(button)) {
+            (false, true) => { self.pressed.insert(button); }
+            (true, false) => { self.released.insert(button); }
+            _ => {}
+        }
+    }
+
+    pub fn check(&self, button: usize) -> bool {
+        self.connected && self.is_held(button)
+    }
+    pub fn check_pressed(&self, button: usize) -> bool {
+        self.connected && self.pressed.contains(&button)
+    }
+    pub fn check_released(&self, button: usize) -> bool {
+        self.connected && self.released.contains(&button)
+    }
+
+    pub fn axis_value(&self, axis: usize) -> f64 {
+        let value: f64 = self.axes[axis];
+        if !self.connected || value.abs() < self.deadzone { 0.0 } else { value }
+    }
+}
+
+/// Every gamepad slot and the backends filling them.
+/// Devices get the lowest free slot when they connect and keep it until they disconnect.
+#[derive(Debug)]
+pub struct Gamepads {
+    backends: Vec<Box<dyn GamepadBackend>>,
+    pub slots: Vec<Gamepad>,
+    /// Slot of every connected device; key: (backend index, device)
+    devices: HashMap<(usize, usize), usize>,
+}
+impl Gamepads {
+    pub fn new() -> Self {
+        Self { backends: Vec::new(), slots: vec![Gamepad::default(); GAMEPAD_SLOTS], devices: HashMap::new() }
+    }
+
+    pub fn add_backend(&mut self, backend: Box<dyn GamepadBackend>) {
+        info!("Using gamepad backend {}", backend.name());
+        self.backends.push(backend);
+    }
+
+    pub fn is_supported(&self) -> bool {
+        !self.backends.is_empty()
+    }
+
+    pub fn get(&self, slot: i64) -> Option<&Gamepad> {
+        usize::try_from(slot).ok().and_then(|slot| self.slots.get(slot))
+    }
+    pub fn get_mut(&mut self, slot: i64) -> Option<&mut Gamepad> {
+        usize::try_from(slot).ok().and_then(|slot| self.slots.get_mut(slot))
+    }
+
+    /// Applies the input of every backend; returns the slots which were connected (true) or disconnected (false)
+    pub fn poll(&mut self) -> Vec<(usize, bool)> {
+        for slot in &mut self.slots {
+            slot.pressed.clear();
+            slot.released.clear();
+        }
+        let mut changes: Vec<(usize, bool)> = Vec::new();
+        for backend_index in 0..self.backends.len() {
+            for event in self.backends[backend_index].poll() {
+                if let Some(change) = self.apply(backend_index, event) {
+                    changes.push(change);
+                }
+            }
+        }
+        changes
+    }
+
+    fn apply(&mut self, backend: usize, event: GamepadEvent) -> Option<(usize, bool)> {
+        match event {
+            GamepadEvent::Connected { device, description } => {
+                if self.devices.contains_key(&(backend, device)) {
+                    return None
+                }
+                let Some(slot) = self.slots.iter().position(|slot| !slot.connected) else {
+                    warn!("No free gamepad slot for {description}");
+                    return None
+                };
+                info!("Gamepad {description} connected in slot {slot}");
+                // the game may have configured the slot before anything was connected
+                let (deadzone, button_threshold): (f64, f64) = (self.slots[slot].deadzone, self.slots[slot].button_threshold);
+                self.slots[slot] = Gamepad { connected: true, description, deadzone, button_threshold, ..Gamepad::default() };
+                self.devices.insert((backend, device), slot);
+                Some((slot, true))
+            }
+            GamepadEvent::Disconnected { device } => {
+                let slot: usize = self.devices.remove(&(backend, device))?;
+                info!("Gamepad {} disconnected from slot {slot}", self.slots[slot].description);
+                self.slots[slot].connected = false;
+                Some((slot, false))
+            }
+            GamepadEvent::Button { device, button, value } => {
+                let slot: usize = *self.devices.get(&(backend, device))?;
+                if button < BUTTON_COUNT {
+                    self.slots[slot].set_button(button, value);
+                }
+                None
+            }
+            GamepadEvent::Axis { device, axis, value } => {
+                let slot: usize = *self.devices.get(&(backend, device))?;
+                if let Some(stored) = self.slots[slot].axes.get_mut(axis) {
+                    *stored = value.clamp(-1.0, 1.0);
+                }
+                None
+            }
+        }
+    }
+}
+
+
+impl App {
+    /// Reads the gamepads before the step; connections and disconnections fire the async System event
+    pub fn update_gamepads(&mut self) -> Result<(), String> {
+        for (slot, connected) in self.gamepads.poll() {
+            let event_type: &str = if connected { "gamepad discovered" } else { "gamepad lost" };
+            let async_load: HashMap<String, Value> = HashMap::from([
+                ("event_type".to_string(), Value::string(event_type)),
+                ("pad_index".to_string(), Value::Real(slot as f64)),
+            ]);
+            self.fire_async_event(Event::new(EV_OTHER, EV_SYSTEM_EVENT), async_load)?;
+        }
+        Ok(())
+    }
+}
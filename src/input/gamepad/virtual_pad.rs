@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::input::gamepad::{axis_by_name, button_by_name, GamepadBackend, GamepadEvent};
+
+/// A gamepad driven by a script instead of a controller, so games can be played and tested headlessly.
+///
+/// Every line of the script is `<step> <command> [arguments]`; `#` starts a comment.
+/// The step counts from 0, the first step of the game. Commands:
+/// - `connect [description]`
+/// - `disconnect`
+/// - `press <button>` and `release <button>`, e.g. `press face1`
+/// - `button <button> <value>` for analogue buttons like triggers
+/// - `axis <axis> <value>`, e.g. `axis axislh -1`
+#[derive(Debug, Clone)]
+pub struct VirtualGamepad {
+    /// Events by the step they happen at
+    script: BTreeMap<u64, Vec<GamepadEvent>>,
+    step: u64,
+}
+impl VirtualGamepad {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let script: String = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read virtual gamepad script {path:?}: {e}"))?;
+        Self::parse(&script).map_err(|e| format!("{e}\n↳ in virtual gamepad script {path:?}"))
+    }
+
+    pub fn parse(script: &str) -> Result<Self, String> {
+        let mut events: BTreeMap<u64, Vec<GamepadEvent>> = BTreeMap::new();
+        for (number, line) in script.lines().enumerate() {
+            let line: &str = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue
+            }
+            let (step, event) = parse_line(line).map_err(|e| format!("{e} in line {}", number + 1))?;
+            events.entry(step).or_default().push(event);
+        }
+        Ok(Self { script: events, step: 0 })
+    }
+}
+
+fn parse_line(line: &str) -> Result<(u64, GamepadEvent), String> {
+    // the virtual pad is always the first and only device of its backend
+    const DEVICE: usize = 0;
+    let mut words = line.split_whitespace();
+    let step: &str = words.next().unwrap_or_default();
+    let step: u64 = step.parse().map_err(|e| format!("Invalid step \"{step}\": {e}"))?;
+    let command: &str = words.next().ok_or("Missing command")?;
+    let mut argument = || words.next().ok_or_else(|| format!("Missing argument for {command}"));
+    let button = |name: &str| button_by_name(name).ok_or_else(|| format!("Invalid gamepad button \"{name}\""));
+    let value = |value: &str| value.parse::<f64>().map_err(|e| format!("Invalid value \"{value}\": {e}"));
+
+    let event: GamepadEvent = match command {
+        "connect" => {
+            let description: String = line.splitn(3, char::is_whitespace).nth(2).unwrap_or("Virtual Gamepad").trim().to_string();
+            GamepadEvent::Connected { device: DEVICE, description }
+        }
+        "disconnect" => GamepadEvent::Disconnected { device: DEVICE },
+        "press" => GamepadEvent::Button { device: DEVICE, button: button(argument()?)?, value: 1.0 },
+        "release" => GamepadEvent::Button { device: DEVICE, button: button(argument()?)?, value: 0.0 },
+        "button" => GamepadEvent::Button { device: DEVICE, button: button(argument()?)?, value: value(argument()?)? },
+        "axis" => {
+            let name: &str = argument()?;
+            let axis: usize = axis_by_name(name).ok_or_else(|| format!("Invalid gamepad axis \"{name}\""))?;
+            GamepadEvent::Axis { device: DEVICE, axis, value: value(argument()?)? }
+        }
+        other => return Err(format!("Invalid command \"{other}\"")),
+    };
+    Ok((step, event))
+}
+
+impl GamepadBackend for VirtualGamepad {
+    fn name(&self) -> &'static str {
+        "virtual"
+    }
+
+    fn poll(&mut self) -> Vec<GamepadEvent> {
+        let events: Vec<GamepadEvent> = self.script.remove(&self.step).unwrap_or_default();
+        self.step += 1;
+        events
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::gamepad::{Gamepad, Gamepads};
+
+    const SCRIPT: &str = "\
+        # a short run to the left
+        0 connect Test Pad
+        0 press face1
+        1 axis axislh -0.5
+        1 axis axislv 0.1  # inside the deadzone
+        2 release face1
+        2 button shoulderlb 0.3
+        3 disconnect
+    ";
+
+    fn gamepads() -> Gamepads {
+        let mut gamepads: Gamepads = Gamepads::new();
+        gamepads.add_backend(Box::new(VirtualGamepad::parse(SCRIPT).unwrap()));
+        gamepads
+    }
+
+    #[test]
+    fn plays_the_script_step_by_step() {
+        let mut gamepads: Gamepads = gamepads();
+        let face1: usize = button_by_name("gp_face1").unwrap();
+        let shoulderlb: usize = button_by_name("shoulderlb").unwrap();
+
+        assert_eq!(gamepads.poll(), [(0, true)]);
+        let pad: &Gamepad = gamepads.get(0).unwrap();
+        assert_eq!(pad.description, "Test Pad");
+        assert!(pad.check(face1) && pad.check_pressed(face1) && !pad.check_released(face1));
+
+        assert!(gamepads.poll().is_empty());
+        let pad: &Gamepad = gamepads.get(0).unwrap();
+        assert!(pad.check(face1) && !pad.check_pressed(face1));
+        assert_eq!(pad.axis_value(0), -0.5);
+        assert_eq!(pad.axis_value(1), 0.0);
+
+        gamepads.poll();
+        let pad: &Gamepad = gamepads.get(0).unwrap();
+        assert!(!pad.check(face1) && pad.check_released(face1));
+        // analogue buttons only count as held past the threshold
+        assert_eq!(pad.buttons[shoulderlb], 0.3);
+        assert!(!pad.check(shoulderlb));
+
+        assert_eq!(gamepads.poll(), [(0, false)]);
+        let pad: &Gamepad = gamepads.get(0).unwrap();
+        assert!(!pad.check(face1));
+        assert_eq!(pad.axis_value(0), 0.0);
+    }
+
+    #[test]
+    fn rejects_invalid_lines() {
+        assert!(VirtualGamepad::parse("0 press face9").is_err());
+        assert!(VirtualGamepad::parse("0 axis axislh").is_err());
+        assert!(VirtualGamepad::parse("first connect").is_err());
+        assert!(VirtualGamepad::parse("0 jump").is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::HashSet;
+use winit::event::{ElementState, KeyEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+use crate::App;
+use crate::event::{Event, EV_KEYBOARD, EV_KEYPRESS, EV_KEYRELEASE};
+
+/// GameMaker's virtual key codes which do not stand for a single key
+pub const VK_NOKEY: u32 = 0;
+pub const VK_ANYKEY: u32 = 1;
+const VK_BACKSPACE: u32 = 8;
+const VK_SHIFT: u32 = 16;
+const VK_CONTROL: u32 = 17;
+const VK_ALT: u32 = 18;
+
+/// `keyboard_string` keeps only the last characters typed
+const KEYBOARD_STRING_LENGTH: usize = 1024;
+
+/// The GameMaker virtual key code (Windows' VK_* codes) of a physical key
+pub fn virtual_key(code: KeyCode) -> Option<u32> {
+    Some(match code {
+        KeyCode::Backspace => VK_BACKSPACE,
+        KeyCode::Tab => 9,
+        KeyCode::Enter | KeyCode::NumpadEnter => 13,
+        KeyCode::Pause => 19,
+        KeyCode::CapsLock => 20,
+        KeyCode::Escape => 27,
+        KeyCode::Space => 32,
+        KeyCode::PageUp => 33,
+        KeyCode::PageDown => 34,
+        KeyCode::End => 35,
+        KeyCode::Home => 36,
+        KeyCode::ArrowLeft => 37,
+        KeyCode::ArrowUp => 38,
+        KeyCode::ArrowRight => 39,
+        KeyCode::ArrowDown => 40,
+        KeyCode::PrintScreen => 44,
+        KeyCode::Insert => 45,
+        KeyCode::Delete => 46,
+        KeyCode::Digit0 => 48,
+        KeyCode::Digit1 => 49,
+        KeyCode::Digit2 => 50,
+        KeyCode::Digit3 => 51,
+        KeyCode::Digit4 => 52,
+        KeyCode::Digit5 => 53,
+        KeyCode::Digit6 => 54,
+        KeyCode::Digit7 => 55,
+        KeyCode::Digit8 => 56,
+        KeyCode::Digit9 => 57,
+        KeyCode::KeyA => 65,
+        KeyCode::KeyB => 66,
+        KeyCode::KeyC => 67,
+        KeyCode::KeyD => 68,
+        KeyCode::KeyE => 69,
+        KeyCode::KeyF => 70,
+        KeyCode::KeyG => 71,
+        KeyCode::KeyH => 72,
+        KeyCode::KeyI => 73,
+        KeyCode::KeyJ => 74,
+        KeyCode::KeyK => 75,
+        KeyCode::KeyL => 76,
+        KeyCode::KeyM => 77,
+        KeyCode::KeyN => 78,
+        KeyCode::KeyO => 79,
+        KeyCode::KeyP => 80,
+        KeyCode::KeyQ => 81,
+        KeyCode::KeyR => 82,
+        KeyCode::KeyS => 83,
+        KeyCode::KeyT => 84,
+        KeyCode::KeyU => 85,
+        KeyCode::KeyV => 86,
+        KeyCode::KeyW => 87,
+        KeyCode::KeyX => 88,
+        KeyCode::KeyY => 89,
+        KeyCode::KeyZ => 90,
+        KeyCode::Numpad0 => 96,
+        KeyCode::Numpad1 => 97,
+        KeyCode::Numpad2 => 98,
+        KeyCode::Numpad3 => 99,
+        KeyCode::Numpad4 => 100,
+        KeyCode::Numpad5 => 101,
+        KeyCode::Numpad6 => 102,
+        KeyCode::Numpad7 => 103,
+        KeyCode::Numpad8 => 104,
+        KeyCode::Numpad9 => 105,
+        KeyCode::NumpadMultiply => 106,
+        KeyCode::NumpadAdd => 107,
+        KeyCode::NumpadSubtract => 109,
+        KeyCode::NumpadDecimal => 110,
+        KeyCode::NumpadDivide => 111,
+        KeyCode::F1 => 112,
+        KeyCode::F2 => 113,
+        KeyCode::F3 => 114,
+        KeyCode::F4 => 115,
+        KeyCode::F5 => 116,
+        KeyCode::F6 => 117,
+        KeyCode::F7 => 118,
+        KeyCode::F8 => 119,
+        KeyCode::F9 => 120,
+        KeyCode::F10 => 121,
+        KeyCode::F11 => 122,
+        KeyCode::F12 => 123,
+        KeyCode::NumLock => 144,
+        KeyCode::ScrollLock => 145,
+        KeyCode::ShiftLeft => 160,
+        KeyCode::ShiftRight => 161,
+        KeyCode::ControlLeft => 162,
+        KeyCode::ControlRight => 163,
+        KeyCode::AltLeft => 164,
+        KeyCode::AltRight => 165,
+        KeyCode::Semicolon => 186,
+        KeyCode::Equal => 187,
+        KeyCode::Comma => 188,
+        KeyCode::Minus => 189,
+        KeyCode::Period => 190,
+        KeyCode::Slash => 191,
+        KeyCode::Backquote => 192,
+        KeyCode::BracketLeft => 219,
+        KeyCode::Backslash => 220,
+        KeyCode::BracketRight => 221,
+        KeyCode::Quote => 222,
+        _ => return None,
+    })
+}
+
+/// `vk_shift`, `vk_control` and `vk_alt` for their left and right variants
+fn generic_key(key: u32) -> Option<u32> {
+    match key {
+        160 | 161 => Some(VK_SHIFT),
+        162 | 163 => Some(VK_CONTROL),
+        164 | 165 => Some(VK_ALT),
+        _ => None,
+    }
+}
+
+/// Keys held, pressed and released, as GameMaker virtual key codes.
+/// Pressed and released keys are collected between steps and forgotten after the next step.
+#[derive(Debug, Clone, Default)]
+pub struct Keyboard {
+    held: HashSet<u32>,
+    pressed: HashSet<u32>,
+    released: HashSet<u32>,
+    /// `keyboard_key`: the key held down most recently, 0 if none
+    pub current_key: u32,
+    /// `keyboard_lastkey`
+    pub last_key: u32,
+    /// `keyboard_lastchar`
+    pub last_char: String,
+    /// `keyboard_string`: the text typed recently; backspace deletes from it
+    pub string: String,
+}
+impl Keyboard {
+    pub fn press(&mut self, key: u32) {
+        let keys: Vec<u32> = [Some(key), generic_key(key)].into_iter().flatten().collect();
+        for key in keys {
+            if self.held.insert(key) {
+                self.pressed.insert(key);
+            }
+        }
+        self.current_key = key;
+        self.last_key = key;
+    }
+
+    pub fn release(&mut self, key: u32) {
+        if self.held.remove(&key) {
+            self.released.insert(key);
+        }
+        // `vk_shift` is held as long as either shift key is
+        if let Some(generic) = generic_key(key) {
+            let other_side_held: bool = self.held.iter().any(|held| generic_key(*held) == Some(generic));
+            if !other_side_held && self.held.remove(&generic) {
+                self.released.insert(generic);
+            }
+        }
+        if self.current_key == key {
+            self.current_key = 0;
+        }
+    }
+
+    /// Releases every key, e.g. when the window loses focus and would not report the key-up events
+    pub fn release_all(&mut self) {
+        let held: Vec<u32> = self.held.iter().copied().collect();
+        for key in held {
+            self.release(key);
+        }
+    }
+
+    /// Appends typed text to `keyboard_string`; control characters like backspace are handled by key
+    pub fn type_text(&mut self, text: &str) {
+        for character in text.chars().filter(|character| !character.is_control()) {
+            self.string.push(character);
+            self.last_char = character.to_string();
+        }
+        let length: usize = self.string.chars().count();
+        if length > KEYBOARD_STRING_LENGTH {
+            self.string = self.string.chars().skip(length - KEYBOARD_STRING_LENGTH).collect();
+        }
+    }
+
+    /// Forgets the keys pressed and released since the last step
+    pub fn end_step(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+
+    /// `keyboard_clear`: the key counts as neither held, pressed nor released until it is pressed again
+    pub fn clear(&mut self, key: u32) {
+        self.held.remove(&key);
+        self.pressed.remove(&key);
+        self.released.remove(&key);
+    }
+
+    fn check_in(keys: &HashSet<u32>, key: u32) -> bool {
+        match key {
+            VK_NOKEY => keys.is_empty(),
+            VK_ANYKEY => !keys.is_empty(),
+            key => keys.contains(&key),
+        }
+    }
+    pub fn check(&self, key: u32) -> bool {
+        Self::check_in(&self.held, key)
+    }
+    pub fn check_pressed(&self, key: u32) -> bool {
+        Self::check_in(&self.pressed, key)
+    }
+    pub fn check_released(&self, key: u32) -> bool {
+        Self::check_in(&self.released, key)
+    }
+
+    /// Subtypes of the events of one kind: every key in ascending order, then `vk_anykey` or `vk_nokey`
+    fn event_keys(keys: &HashSet<u32>) -> Vec<u32> {
+        let mut subtypes: Vec<u32> = keys.iter().copied().collect();
+        subtypes.sort_unstable();
+        subtypes.push(if keys.is_empty() { VK_NOKEY } else { VK_ANYKEY });
+        subtypes
+    }
+}
+
+
+impl App {
+    /// Records a key event from the window; the game sees it at its next step
+    pub fn handle_key_event(&mut self, event: &KeyEvent) {
+        if event.state == ElementState::Pressed {
+            if let Some(text) = &event.text {
+                self.keyboard.type_text(text);
+            }
+        }
+        let PhysicalKey::Code(code) = event.physical_key else { return };
+        let Some(key) = virtual_key(code) else { return };
+        // held backspace keeps deleting, like typing repeats
+        if key == VK_BACKSPACE && event.state == ElementState::Pressed {
+            self.keyboard.string.pop();
+        }
+        match event.state {
+            ElementState::Pressed if !event.repeat => self.keyboard.press(key),
+            ElementState::Pressed => {}
+            ElementState::Released => self.keyboard.release(key),
+        }
+    }
+
+    /// Fires the Keyboard events for held keys, then Key Press and Key Release, like GameMaker after the alarms
+    pub fn keyboard_events(&mut self) -> Result<(), String> {
+        let kinds: [(u32, Vec<u32>); 3] = [
+            (EV_KEYBOARD, Keyboard::event_keys(&self.keyboard.held)),
+            (EV_KEYPRESS, Keyboard::event_keys(&self.keyboard.pressed)),
+            (EV_KEYRELEASE, Keyboard::event_keys(&self.keyboard.released)),
+        ];
+        for (event_type, keys) in kinds {
+            for key in keys {
+                self.fire_event_all(Event::new(event_type, key))?;
+            }
+        }
+        Ok(())
+    }
+}
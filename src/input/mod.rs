@@ -0,0 +1,3 @@
+pub mod gamepad;
+pub mod keyboard;
+pub mod mouse;
@@ -0,0 +1,230 @@
+use std::collections::HashSet;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta};
+use crate::App;
+use crate::collision::Shape;
+use crate::event::{Event, EV_MOUSE};
+use crate::graphics::view::View;
+
+/// `mb_any` and `mb_none`; the other `mb_*` constants are the buttons' numbers
+pub const MB_ANY: i64 = -1;
+pub const MB_NONE: i64 = 0;
+const MB_LEFT: u32 = 1;
+const MB_RIGHT: u32 = 2;
+const MB_MIDDLE: u32 = 3;
+
+/// Subtypes of `EV_MOUSE`. The button events exist for left, right and middle, in this order.
+const EV_MOUSE_BUTTON: u32 = 0;
+const EV_MOUSE_NO_BUTTON: u32 = 3;
+const EV_MOUSE_PRESSED: u32 = 4;
+const EV_MOUSE_RELEASED: u32 = 7;
+const EV_MOUSE_ENTER: u32 = 10;
+const EV_MOUSE_LEAVE: u32 = 11;
+const EV_GLOBAL_BUTTON: u32 = 50;
+const EV_GLOBAL_PRESSED: u32 = 53;
+const EV_GLOBAL_RELEASED: u32 = 56;
+const EV_MOUSE_WHEEL_UP: u32 = 60;
+const EV_MOUSE_WHEEL_DOWN: u32 = 61;
+
+/// The GameMaker number of a mouse button (`mb_left` … `mb_side2`)
+pub fn button_number(button: MouseButton) -> Option<u32> {
+    match button {
+        MouseButton::Left => Some(MB_LEFT),
+        MouseButton::Right => Some(MB_RIGHT),
+        MouseButton::Middle => Some(MB_MIDDLE),
+        MouseButton::Back => Some(4),
+        MouseButton::Forward => Some(5),
+        MouseButton::Other(_) => None,
+    }
+}
+
+/// Cursor position and buttons held, pressed and released.
+/// Like keys, presses, releases and wheel movement are collected between steps and forgotten after the next step.
+#[derive(Debug, Clone, Default)]
+pub struct Mouse {
+    /// Position in the window, in physical pixels
+    pub window_x: f64,
+    pub window_y: f64,
+    held: HashSet<u32>,
+    pressed: HashSet<u32>,
+    released: HashSet<u32>,
+    wheel_up: bool,
+    wheel_down: bool,
+    /// `mouse_button`: the button held down most recently, 0 if none
+    pub current_button: u32,
+    /// `mouse_lastbutton`
+    pub last_button: u32,
+    /// Instances which were under the cursor at the last step, for Mouse Enter and Mouse Leave
+    hovered: HashSet<usize>,
+}
+impl Mouse {
+    pub fn press(&mut self, button: u32) {
+        if self.held.insert(button) {
+            self.pressed.insert(button);
+        }
+        self.current_button = button;
+        self.last_button = button;
+    }
+
+    pub fn release(&mut self, button: u32) {
+        if self.held.remove(&button) {
+            self.released.insert(button);
+        }
+        if self.current_button == button {
+            self.current_button = 0;
+        }
+    }
+
+    pub fn release_all(&mut self) {
+        let held: Vec<u32> = self.held.iter().copied().collect();
+        for button in held {
+            self.release(button);
+        }
+    }
+
+    pub fn scroll(&mut self, delta: f64) {
+        if delta > 0.0 {
+            self.wheel_up = true;
+        } else if delta < 0.0 {
+            self.wheel_down = true;
+        }
+    }
+
+    pub fn end_step(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.wheel_up = false;
+        self.wheel_down = false;
+    }
+
+    /// `mouse_clear`: the button counts as neither held, pressed nor released until it is pressed again
+    pub fn clear(&mut self, button: i64) {
+        if button == MB_ANY {
+            self.held.clear();
+            self.pressed.clear();
+            self.released.clear();
+        } else if let Ok(button) = u32::try_from(button) {
+            self.held.remove(&button);
+            self.pressed.remove(&button);
+            self.released.remove(&button);
+        }
+    }
+
+    fn check_in(buttons: &HashSet<u32>, button: i64) -> bool {
+        match button {
+            MB_ANY => !buttons.is_empty(),
+            MB_NONE => buttons.is_empty(),
+            button => u32::try_from(button).is_ok_and(|button| buttons.contains(&button)),
+        }
+    }
+    pub fn check(&self, button: i64) -> bool {
+        Self::check_in(&self.held, button)
+    }
+    pub fn check_pressed(&self, button: i64) -> bool {
+        Self::check_in(&self.pressed, button)
+    }
+    pub fn check_released(&self, button: i64) -> bool {
+        Self::check_in(&self.released, button)
+    }
+    pub fn wheel_up(&self) -> bool {
+        self.wheel_up
+    }
+    pub fn wheel_down(&self) -> bool {
+        self.wheel_down
+    }
+}
+
+
+impl App {
+    pub fn handle_mouse_button(&mut self, state: ElementState, button: MouseButton) {
+        let Some(button) = button_number(button) else { return };
+        match state {
+            ElementState::Pressed => self.mouse.press(button),
+            ElementState::Released => self.mouse.release(button),
+        }
+    }
+
+    pub fn handle_mouse_wheel(&mut self, delta: MouseScrollDelta) {
+        match delta {
+            MouseScrollDelta::LineDelta(_, y) => self.mouse.scroll(f64::from(y)),
+            MouseScrollDelta::PixelDelta(position) => self.mouse.scroll(position.y),
+        }
+    }
+
+    /// The cursor on the application surface, which is also where the GUI is drawn
+    pub fn mouse_surface_position(&self) -> (f64, f64) {
+        self.viewport().to_surface(self.canvas.width, self.canvas.height, self.mouse.window_x, self.mouse.window_y)
+    }
+
+    /// `mouse_x`/`mouse_y`: the cursor in the room, seen through the view whose port it is over
+    /// (or the first visible view if it is over none)
+    pub fn mouse_room_position(&self) -> (f64, f64) {
+        let (x, y): (f64, f64) = self.mouse_surface_position();
+        let drawn: Vec<usize> = self.views.drawn();
+        let view_index: Option<&usize> = drawn.iter()
+            .find(|i| {
+                let view: &View = &self.views.views[**i];
+                x >= view.port_x && x < view.port_x + view.port_width && y >= view.port_y && y < view.port_y + view.port_height
+            })
+            .or(drawn.first());
+        let Some(view_index) = view_index else { return (x, y) };
+        let view: &View = &self.views.views[*view_index];
+        (
+            view.x + (x - view.port_x) * view.width / view.port_width,
+            view.y + (y - view.port_y) * view.height / view.port_height,
+        )
+    }
+
+    /// Fires the Mouse events after the keyboard events: first the ones for instances under the cursor,
+    /// then Mouse Enter/Leave, then the global button and wheel events.
+    pub fn mouse_events(&mut self) -> Result<(), String> {
+        let (x, y): (f64, f64) = self.mouse_room_position();
+        let hovered: Vec<usize> = self.shape_collisions(&Shape::Point(x, y), &self.instances.living_ids(), true)?;
+        let buttons: [u32; 3] = [MB_LEFT, MB_RIGHT, MB_MIDDLE];
+
+        for id in &hovered {
+            if self.mouse.held.is_empty() {
+                self.fire_event(*id, Event::new(EV_MOUSE, EV_MOUSE_NO_BUTTON))?;
+            }
+            for (offset, button) in (0..).zip(buttons) {
+                if self.mouse.held.contains(&button) {
+                    self.fire_event(*id, Event::new(EV_MOUSE, EV_MOUSE_BUTTON + offset))?;
+                }
+                if self.mouse.pressed.contains(&button) {
+                    self.fire_event(*id, Event::new(EV_MOUSE, EV_MOUSE_PRESSED + offset))?;
+                }
+                if self.mouse.released.contains(&button) {
+                    self.fire_event(*id, Event::new(EV_MOUSE, EV_MOUSE_RELEASED + offset))?;
+                }
+            }
+        }
+
+        let previous: HashSet<usize> = std::mem::replace(&mut self.mouse.hovered, hovered.iter().copied().collect());
+        for id in hovered.iter().filter(|id| !previous.contains(id)) {
+            self.fire_event(*id, Event::new(EV_MOUSE, EV_MOUSE_ENTER))?;
+        }
+        let mut left: Vec<usize> = previous.into_iter().filter(|id| !self.mouse.hovered.contains(id)).collect();
+        left.sort_unstable();
+        for id in left {
+            self.fire_event(id, Event::new(EV_MOUSE, EV_MOUSE_LEAVE))?;
+        }
+
+        for (offset, button) in (0..).zip(buttons) {
+            if self.mouse.held.contains(&button) {
+                self.fire_event_all(Event::new(EV_MOUSE, EV_GLOBAL_BUTTON + offset))?;
+            }
+            if self.mouse.pressed.contains(&button) {
+                self.fire_event_all(Event::new(EV_MOUSE, EV_GLOBAL_PRESSED + offset))?;
+            }
+            if self.mouse.released.contains(&button) {
+                self.fire_event_all(Event::new(EV_MOUSE, EV_GLOBAL_RELEASED + offset))?;
+            }
+        }
+        if self.mouse.wheel_up {
+            self.fire_event_all(Event::new(EV_MOUSE, EV_MOUSE_WHEEL_UP))?;
+        }
+        if self.mouse.wheel_down {
+            self.fire_event_all(Event::new(EV_MOUSE, EV_MOUSE_WHEEL_DOWN))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use libgm::GMData;
+use crate::App;
+use crate::code::structs::StructRef;
+use crate::code::value::Value;
+use crate::event::{Event, EV_CLEANUP, EV_CREATE, EV_DESTROY};
+
+/// Special instance values used by `with`, `other.x` etc.
+pub const SELF: i64 = -1;
+pub const OTHER: i64 = -2;
+pub const ALL: i64 = -3;
+pub const NOONE: i64 = -4;
+pub const GLOBAL: i64 = -5;
+pub const LOCAL: i64 = -7;
+
+/// Instance IDs start here; anything below is an object index
+pub const FIRST_INSTANCE_ID: usize = 100000;
+
+/// What `self` and `other` can refer to
+#[derive(Debug, Clone)]
+pub enum InstanceRef {
+    Instance(usize),    // instance id
+    Struct(StructRef),
+}
+impl InstanceRef {
+    pub fn to_value(&self) -> Value {
+        match self {
+            InstanceRef::Instance(id) => Value::Real(*id as f64),
+            InstanceRef::Struct(structure) => Value::Struct(structure.clone()),
+        }
+    }
+    pub fn is_same(&self, other: &InstanceRef) -> bool {
+        match (self, other) {
+            (InstanceRef::Instance(a), InstanceRef::Instance(b)) => a == b,
+            (InstanceRef::Struct(a), InstanceRef::Struct(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Instance {
+    pub id: usize,
+    pub object_index: usize,
+    pub builtins: InstanceBuiltins,
+    pub variables: HashMap<usize, Value>,   // key: variable index
+    /// Destroyed instances stay in the list until the end of the step, so code still running on them keeps working
+    pub destroyed: bool,
+}
+
+/// Number of alarms every instance has
+pub const ALARM_COUNT: usize = 12;
+
+/// Built-in instance variables; these are native fields because the runner itself works with them every frame.
+#[derive(Debug, Clone)]
+pub struct InstanceBuiltins {
+    pub x: f64,
+    pub y: f64,
+    pub xprevious: f64,
+    pub yprevious: f64,
+    pub xstart: f64,
+    pub ystart: f64,
+    hspeed: f64,
+    vspeed: f64,
+    speed: f64,
+    direction: f64,
+    pub friction: f64,
+    pub gravity: f64,
+    pub gravity_direction: f64,
+    pub sprite_index: i32,
+    pub image_index: f64,
+    pub image_speed: f64,
+    pub image_xscale: f64,
+    pub image_yscale: f64,
+    pub image_angle: f64,
+    pub image_alpha: f64,
+    pub image_blend: u32,
+    pub mask_index: i32,
+    pub depth: f64,
+    pub visible: bool,
+    pub solid: bool,
+    pub persistent: bool,
+    /// Steps until the alarm event fires; -1 if inactive
+    pub alarm: [i32; ALARM_COUNT],
+}
+impl InstanceBuiltins {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self {
+            x,
+            y,
+            xprevious: x,
+            yprevious: y,
+            xstart: x,
+            ystart: y,
+            hspeed: 0.0,
+            vspeed: 0.0,
+            speed: 0.0,
+            direction: 0.0,
+            friction: 0.0,
+            gravity: 0.0,
+            gravity_direction: 270.0,
+            sprite_index: -1,
+            image_index: 0.0,
+            image_speed: 1.0,
+            image_xscale: 1.0,
+            image_yscale: 1.0,
+            image_angle: 0.0,
+            image_alpha: 1.0,
+            image_blend: 0xFFFFFF,
+            mask_index: -1,
+            depth: 0.0,
+            visible: true,
+            solid: false,
+            persistent: false,
+            alarm: [-1; ALARM_COUNT],
+        }
+    }
+
+    pub fn hspeed(&self) -> f64 { self.hspeed }
+    pub fn vspeed(&self) -> f64 { self.vspeed }
+    pub fn speed(&self) -> f64 { self.speed }
+    pub fn direction(&self) -> f64 { self.direction }
+
+    // the motion variables are two views of the same vector, so every setter updates the other view
+    pub fn set_hspeed(&mut self, hspeed: f64) {
+        self.hspeed = hspeed;
+        self.update_polar();
+    }
+    pub fn set_vspeed(&mut self, vspeed: f64) {
+        self.vspeed = vspeed;
+        self.update_polar();
+    }
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed;
+        self.update_cartesian();
+    }
+    pub fn set_direction(&mut self, direction: f64) {
+        self.direction = direction.rem_euclid(360.0);
+        self.update_cartesian();
+    }
+
+    fn update_cartesian(&mut self) {
+        let radians: f64 = self.direction.to_radians();
+        self.hspeed = self.speed * radians.cos();
+        // y grows downwards
+        self.vspeed = -self.speed * radians.sin();
+    }
+    /// The direction is kept when the speed becomes 0
+    fn update_polar(&mut self) {
+        self.speed = self.hspeed.hypot(self.vspeed);
+        if self.speed != 0.0 {
+            self.direction = (-self.vspeed).atan2(self.hspeed).to_degrees().rem_euclid(360.0);
+        }
+    }
+}
+
+/// Properties of a game object which its instances start with
+#[derive(Debug, Clone)]
+struct ObjectInfo {
+    parent: Option<usize>,
+    sprite_index: i32,
+    mask_index: i32,
+    depth: f64,
+    visible: bool,
+    solid: bool,
+    persistent: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Instances {
+    /// In creation order. Adding or removing instances has to go through the methods so that `slots` stays in sync.
+    pub list: Vec<Instance>,
+    /// Position of every instance in `list`; key: instance ID
+    slots: HashMap<usize, usize>,
+    next_id: usize,
+    /// index: object index
+    objects: Vec<ObjectInfo>,
+}
+impl Instances {
+    pub fn new(data: &GMData) -> Self {
+        let objects: Vec<ObjectInfo> = data.game_objects.game_objects_by_index.iter()
+            .map(|object| ObjectInfo {
+                parent: object.parent.as_ref().map(|parent| parent.index),
+                sprite_index: object.sprite.as_ref().map_or(-1, |sprite| sprite.index as i32),
+                mask_index: object.texture_mask.as_ref().map_or(-1, |sprite| sprite.index as i32),
+                depth: f64::from(object.depth),
+                visible: object.visible,
+                solid: object.solid,
+                persistent: object.persistent,
+            })
+            .collect();
+        Self { list: Vec::new(), slots: HashMap::new(), next_id: FIRST_INSTANCE_ID, objects }
+    }
+    pub fn create(&mut self, object_index: usize, x: f64, y: f64) -> Result<usize, String> {
+        let id: usize = self.next_id;
+        self.create_with_id(id, object_index, x, y)?;
+        Ok(id)
+    }
+    /// Instances placed in rooms come with their IDs
+    pub fn create_with_id(&mut self, id: usize, object_index: usize, x: f64, y: f64) -> Result<(), String> {
+        let object: &ObjectInfo = self.objects.get(object_index)
+            .ok_or_else(|| format!("Cannot create instance of nonexistent object {object_index}"))?;
+        if self.slots.contains_key(&id) {
+            return Err(format!("Cannot create instance with id {id} because it already exists"))
+        }
+        let mut builtins: InstanceBuiltins = InstanceBuiltins::new(x, y);
+        builtins.sprite_index = object.sprite_index;
+        builtins.mask_index = object.mask_index;
+        builtins.depth = object.depth;
+        builtins.visible = object.visible;
+        builtins.solid = object.solid;
+        builtins.persistent = object.persistent;
+        self.slots.insert(id, self.list.len());
+        self.list.push(Instance { id, object_index, builtins, variables: HashMap::new(), destroyed: false });
+        self.next_id = self.next_id.max(id + 1);
+        Ok(())
+    }
+    pub fn get(&self, id: usize) -> Result<&Instance, String> {
+        let slot: usize = *self.slots.get(&id).ok_or_else(|| format!("Instance with id {id} does not exist"))?;
+        Ok(&self.list[slot])
+    }
+    pub fn get_mut(&mut self, id: usize) -> Result<&mut Instance, String> {
+        let slot: usize = *self.slots.get(&id).ok_or_else(|| format!("Instance with id {id} does not exist"))?;
+        Ok(&mut self.list[slot])
+    }
+    pub fn exists(&self, id: usize) -> bool {
+        self.slots.get(&id).is_some_and(|&slot| !self.list[slot].destroyed)
+    }
+
+    /// Whether `object_index` is `ancestor` or inherits from it
+    pub fn is_object_or_child(&self, object_index: usize, ancestor: usize) -> bool {
+        let mut current: Option<usize> = Some(object_index);
+        // the depth limit protects against parent loops in broken data files
+        for _ in 0..=self.objects.len() {
+            match current {
+                Some(object) if object == ancestor => return true,
+                Some(object) => current = self.parent_of(object),
+                None => return false,
+            }
+        }
+        false
+    }
+    pub fn parent_of(&self, object_index: usize) -> Option<usize> {
+        self.objects.get(object_index).and_then(|object| object.parent)
+    }
+
+    /// IDs of all living instances of an object, including instances of its child objects
+    pub fn ids_of_object(&self, object_index: usize) -> Vec<usize> {
+        self.list.iter()
+            .filter(|i| !i.destroyed && self.is_object_or_child(i.object_index, object_index))
+            .map(|i| i.id)
+            .collect()
+    }
+    pub fn living_ids(&self) -> Vec<usize> {
+        self.list.iter().filter(|i| !i.destroyed).map(|i| i.id).collect()
+    }
+
+    /// Marks an instance as destroyed; it is removed by `remove_destroyed` at the end of the step.
+    /// Returns false if it was already destroyed.
+    pub fn destroy(&mut self, id: usize) -> Result<bool, String> {
+        let instance: &mut Instance = self.get_mut(id)?;
+        let newly_destroyed: bool = !instance.destroyed;
+        instance.destroyed = true;
+        Ok(newly_destroyed)
+    }
+    pub fn remove_destroyed(&mut self) {
+        let count: usize = self.list.len();
+        self.list.retain(|i| !i.destroyed);
+        if self.list.len() != count {
+            self.update_slots();
+        }
+    }
+
+    /// Removes every instance, e.g. to sort out the persistent ones when leaving a room
+    pub fn take_all(&mut self) -> Vec<Instance> {
+        self.slots.clear();
+        std::mem::take(&mut self.list)
+    }
+    /// Adds instances which already exist, e.g. those of a persistent room that is entered again
+    pub fn extend(&mut self, instances: Vec<Instance>) {
+        self.list.extend(instances);
+        self.update_slots();
+    }
+    fn update_slots(&mut self) {
+        self.slots = self.list.iter().enumerate().map(|(slot, instance)| (instance.id, slot)).collect();
+    }
+
+    /// Resolves the target of `with (target)`: an instance ID, an object index or one of the special values.
+    pub fn resolve_target(&self, target: &Value, self_instance: &InstanceRef, other_instance: &InstanceRef) -> Result<Vec<InstanceRef>, String> {
+        if let Value::Struct(structure) = target {
+            return Ok(vec![InstanceRef::Struct(structure.clone())])
+        }
+        let target: i64 = target.to_int64()?;
+        let ids: Vec<usize> = match target {
+            SELF => return Ok(vec![self_instance.clone()]),
+            OTHER => return Ok(vec![other_instance.clone()]),
+            ALL => self.living_ids(),
+            NOONE => Vec::new(),
+            id if id >= FIRST_INSTANCE_ID as i64 => {
+                let id: usize = id as usize;
+                if self.exists(id) { vec![id] } else { Vec::new() }
+            }
+            object_index if object_index >= 0 => self.ids_of_object(object_index as usize),
+            other => return Err(format!("Invalid instance target {other}")),
+        };
+        Ok(ids.into_iter().map(InstanceRef::Instance).collect())
+    }
+}
+
+
+impl App {
+    /// `depth` overrides the object's depth before the Create event runs, so Create code sees the final depth
+    pub fn create_instance(&mut self, x: f64, y: f64, object_index: usize, depth: Option<f64>) -> Result<usize, String> {
+        let id: usize = self.instances.create(object_index, x, y)?;
+        if let Some(depth) = depth {
+            self.instances.get_mut(id)?.builtins.depth = depth;
+        }
+        self.mark_moved(id);
+        log::debug!("Created instance {id} of object {}", self.object_name(object_index));
+        self.fire_event(id, Event::new(EV_CREATE, 0))?;
+        Ok(id)
+    }
+
+    /// Destruction is deferred until the end of the step; until then the instance no longer "exists" for the game.
+    pub fn destroy_instance(&mut self, id: usize, execute_event: bool) -> Result<(), String> {
+        if !self.instances.exists(id) {
+            return Ok(())
+        }
+        // marked first so that `instance_destroy()` inside these events is a no-op;
+        // the instance stays readable until `remove_destroyed`
+        self.instances.destroy(id)?;
+        self.mark_moved(id);
+        if execute_event {
+            self.fire_destroy_event(id, Event::new(EV_DESTROY, 0))?;
+        }
+        self.fire_destroy_event(id, Event::new(EV_CLEANUP, 0))?;
+        log::debug!("Destroyed instance {id}");
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_instances(object_count: usize) -> Instances {
+        let object = ObjectInfo { parent: None, sprite_index: -1, mask_index: -1, depth: 0.0, visible: true, solid: false, persistent: false };
+        Instances { list: Vec::new(), slots: HashMap::new(), next_id: FIRST_INSTANCE_ID, objects: vec![object; object_count] }
+    }
+
+    #[test]
+    fn slots_follow_removed_and_restored_instances() {
+        let mut instances: Instances = new_instances(1);
+        let ids: Vec<usize> = (0..4).map(|i| instances.create(0, f64::from(i), 0.0).unwrap()).collect();
+        instances.destroy(ids[1]).unwrap();
+        assert!(!instances.exists(ids[1]));
+        assert!(instances.get(ids[1]).is_ok(), "destroyed instances stay readable until they are removed");
+
+        instances.remove_destroyed();
+        assert!(instances.get(ids[1]).is_err());
+        assert_eq!(instances.get(ids[3]).unwrap().builtins.x, 3.0);
+
+        let taken: Vec<Instance> = instances.take_all();
+        assert!(!instances.exists(ids[0]));
+        instances.extend(taken.into_iter().rev().collect());
+        for (i, id) in [ids[0], ids[2], ids[3]].into_iter().enumerate() {
+            assert_eq!(instances.get(id).unwrap().id, id, "instance {i}");
+        }
+        assert!(instances.create_with_id(ids[2], 0, 0.0, 0.0).is_err());
+    }
+}
@@ -1,31 +1,107 @@
 mod code;
+mod collision;
+mod event;
+mod game_loop;
+mod graphics;
+mod input;
+mod instance;
+mod room;
+mod spatial;
 
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
 use biologischer_log::{init_logger, CustomLogger};
 use libgm::{parse_data_file, read_data_file, GMData};
 use libgm::gm::GMRoom;
 use log::info;
 use pixels::Pixels;
+use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::Window;
 use code::run::Stack;
+use crate::code::builtins::{report_unimplemented, Builtins};
+use crate::code::builtins::math::Random;
+use crate::code::builtin_variables::{resolve_builtin_variables, BuiltinVariable};
+use crate::code::call::{resolve_function_targets, CallStack, FunctionTarget};
 use crate::code::run::Variables;
+use crate::code::structs::StructRef;
+use crate::code::value::{Value, DEFAULT_EPSILON};
+use crate::collision::SpriteMasks;
+use crate::event::EventTable;
+use crate::game_loop::{FrameSkip, GameClock};
+use crate::graphics::canvas::Canvas;
+use crate::graphics::layers::RoomLayers;
+use crate::graphics::scaling::ScaleMode;
+use crate::graphics::state::DrawState;
+use crate::graphics::text::Fonts;
+use crate::graphics::texture::TexturePages;
+use crate::graphics::view::Views;
+use crate::input::gamepad::Gamepads;
+use crate::input::gamepad::virtual_pad::VirtualGamepad;
+use crate::input::keyboard::Keyboard;
+use crate::input::mouse::Mouse;
+use crate::instance::{Instance, Instances};
+use crate::spatial::SpatialGrid;
 
 #[derive(Debug)]
 pub struct App {
     logger: Arc<CustomLogger>,
 
-    window: Option<Window>,
+    window: Option<Arc<Window>>,
     pixels: Option<Pixels<'static>>,
+    canvas: Canvas,
+    texture_pages: TexturePages,
+    masks: SpriteMasks,
+    fonts: Fonts,
+    draw_state: DrawState,
+    scale_mode: ScaleMode,
+    keyboard: Keyboard,
+    mouse: Mouse,
+    gamepads: Gamepads,
 
-    data: GMData,
+    data: Arc<GMData>,
+    functions: Vec<FunctionTarget>,
+    /// What every variable refers to if it is a built-in one; indexed by variable index
+    builtin_variables: Vec<Option<BuiltinVariable>>,
+    builtins: Builtins,
+    random: Random,
+    math_epsilon: f64,
+    /// Pre-GMS2.3 arrays: copy-on-write and 2D indices encoded into one number
+    legacy_arrays: bool,
+    /// Before GMS2, `#` is a line break in drawn text
+    hash_newlines: bool,
     window_title: String,
     window_width: u32,
     window_height: u32,
     current_room: GMRoom,
+    room_index: usize,
+    room_layers: RoomLayers,
+    views: Views,
+    /// View being drawn; 0 outside of the Draw events
+    view_current: usize,
+    /// Room to change to at the end of the frame
+    pending_room: Option<usize>,
+    /// Instances of persistent rooms which were left; key: room index
+    room_states: HashMap<usize, Vec<Instance>>,
+    /// Frames actually drawn during the last second
+    fps: f64,
+    start_time: Instant,
     stack: Stack,
+    call_stack: CallStack,
     variables: Variables,
+    instances: Instances,
+    /// Bounding boxes of the instances for collision queries; updated lazily, hence the `RefCell`
+    grid: RefCell<SpatialGrid>,
+    events: EventTable,
+    /// Static variables of GMS2.3 functions; key: code index
+    statics: HashMap<usize, StructRef>,
+    /// Values of the async event being fired, read through the `async_load` map
+    async_load: Option<HashMap<String, Value>>,
+    clock: GameClock,
+    /// Error which stopped the game loop
+    error: Option<String>,
 }
 
 
@@ -51,26 +127,104 @@ fn main() -> Result<(), String> {
     let first_room_id: usize = data.general_info.room_order[0] as usize;
     let first_room: GMRoom = data.rooms.rooms_by_index[first_room_id].clone();
     let window_title: String = data.general_info.display_name.resolve(&data.strings.strings_by_index)?.to_owned();
+    let functions: Vec<FunctionTarget> = resolve_function_targets(&data)?;
+    let builtin_variables: Vec<Option<BuiltinVariable>> = resolve_builtin_variables(&data)?;
+    let legacy_arrays: bool = data.general_info.version.major < 2
+        || (data.general_info.version.major == 2 && data.general_info.version.minor < 3);
+    let instances = Instances::new(&data);
+    let events = EventTable::new(&data);
+    let texture_pages = TexturePages::load(&data);
+    info!("Decoded {} texture pages", texture_pages.pages.len());
+    let fonts = Fonts::load(&data);
+    let masks = SpriteMasks::load(&data);
+    let hash_newlines: bool = data.general_info.bytecode_version < 17;
+    let builtins = Builtins::new();
+    report_unimplemented(&data, &functions, &builtins);
+    let frame_skip: FrameSkip = match std::env::var("ACORN_FRAME_SKIP") {
+        Ok(policy) => FrameSkip::parse(&policy)?,
+        Err(_) => FrameSkip::Off,
+    };
+    info!("Frame skip policy: {frame_skip:?}");
+    let scale_mode: ScaleMode = match std::env::var("ACORN_SCALING") {
+        Ok(mode) => ScaleMode::parse(&mode)?,
+        Err(_) => ScaleMode::Letterbox,
+    };
+    info!("Scaling mode: {scale_mode:?}");
+    let mut gamepads: Gamepads = Gamepads::new();
+    #[cfg(feature = "gilrs")]
+    match input::gamepad::gilrs_backend::GilrsBackend::new() {
+        Ok(backend) => gamepads.add_backend(Box::new(backend)),
+        Err(e) => log::warn!("Gamepads are not available: {e}"),
+    }
+    if let Ok(path) = std::env::var("ACORN_VIRTUAL_GAMEPAD") {
+        gamepads.add_backend(Box::new(VirtualGamepad::load(Path::new(&path))?));
+    }
 
     let mut app = App {
         logger,
         window: None,
         pixels: None,
+        canvas: Canvas::new(data.general_info.default_window_width, data.general_info.default_window_height),
+        texture_pages,
+        masks,
+        fonts,
+        draw_state: DrawState::default(),
+        scale_mode,
+        keyboard: Keyboard::default(),
+        mouse: Mouse::default(),
+        gamepads,
         window_title,
         window_width: data.general_info.default_window_width,
         window_height: data.general_info.default_window_height,
         current_room: first_room,
-        data,
+        room_index: first_room_id,
+        room_layers: RoomLayers::default(),
+        views: Views::default(),
+        view_current: 0,
+        pending_room: None,
+        room_states: HashMap::new(),
+        fps: 0.0,
+        start_time: Instant::now(),
+        data: Arc::new(data),
+        functions,
+        builtin_variables,
+        builtins,
+        random: Random::new(0),
+        math_epsilon: DEFAULT_EPSILON,
+        legacy_arrays,
+        hash_newlines,
         stack: Stack::new(),
+        call_stack: CallStack::new(),
         variables: Variables {
             globals: HashMap::new(),
-            instances: HashMap::new(),
-            locals: HashMap::new(),
         },
+        instances,
+        grid: RefCell::new(SpatialGrid::default()),
+        events,
+        statics: HashMap::new(),
+        async_load: None,
+        clock: GameClock::new(frame_skip),
+        error: None,
     };
 
-    app.run_code(0, 0)?;
+    if let Ok(count) = std::env::var("ACORN_BENCHMARK_GRID") {
+        let count: usize = count.parse().map_err(|e| format!("Invalid instance count {count:?} for ACORN_BENCHMARK_GRID: {e}"))?;
+        app.benchmark_grid(count)?;
+        app.logger.shutdown();
+        return Ok(())
+    }
+
+    app.start_game()?;
+
+    let event_loop: EventLoop<()> = EventLoop::new()
+        .map_err(|e| format!("Could not create event loop: {e}"))?;
+    event_loop.set_control_flow(ControlFlow::Poll);
+    event_loop.run_app(&mut app)
+        .map_err(|e| format!("An error has occurred in the event loop: {e}"))?;
 
     app.logger.shutdown();
-    Ok(())
+    match app.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
 }
\ No newline at end of file
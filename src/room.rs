@@ -0,0 +1,137 @@
+use libgm::gm::GMRoom;
+use crate::App;
+use crate::code::call::CallFrame;
+use crate::code::structs::Struct;
+use crate::graphics::layers::RoomLayers;
+use crate::graphics::view::Views;
+use crate::event::{Event, EV_CREATE, EV_GAME_START, EV_OTHER, EV_ROOM_END, EV_ROOM_START};
+use crate::instance::{Instance, InstanceBuiltins, InstanceRef};
+
+impl App {
+    pub fn start_game(&mut self) -> Result<(), String> {
+        let first_room: usize = *self.data.general_info.room_order.first()
+            .ok_or_else(|| "The game does not have any rooms".to_string())? as usize;
+        self.load_room(first_room, true)
+    }
+
+    /// Position of the current room in the room order
+    fn room_order_position(&self) -> Result<usize, String> {
+        self.data.general_info.room_order.iter()
+            .position(|room| *room as usize == self.room_index)
+            .ok_or_else(|| format!("Current room {} is not in the room order", self.room_index))
+    }
+
+    /// Room `offset` places away from the current one in the room order
+    pub fn relative_room(&self, offset: isize) -> Result<Option<usize>, String> {
+        let position: usize = self.room_order_position()?;
+        Ok(position.checked_add_signed(offset)
+            .and_then(|position| self.data.general_info.room_order.get(position))
+            .map(|room| *room as usize))
+    }
+
+    /// Changes the room at the end of the current frame, like GameMaker does
+    pub fn goto_room(&mut self, room_index: usize) -> Result<(), String> {
+        if room_index >= self.data.rooms.rooms_by_index.len() {
+            return Err(format!("Cannot go to nonexistent room {room_index}"))
+        }
+        self.pending_room = Some(room_index);
+        Ok(())
+    }
+
+    pub fn change_room_if_pending(&mut self) -> Result<(), String> {
+        let Some(room_index) = self.pending_room.take() else { return Ok(()) };
+        self.leave_room()?;
+        self.load_room(room_index, false)
+    }
+
+    /// Fires Room End and removes every non-persistent instance; a persistent room keeps them for the next visit.
+    fn leave_room(&mut self) -> Result<(), String> {
+        self.fire_event_all(Event::new(EV_OTHER, EV_ROOM_END))?;
+        self.instances.remove_destroyed();
+
+        let (persistent, left_behind): (Vec<Instance>, Vec<Instance>) = self.instances.take_all()
+            .into_iter()
+            .partition(|instance| instance.builtins.persistent);
+        self.instances.extend(persistent);
+        if self.current_room.persistent {
+            self.room_states.insert(self.room_index, left_behind);
+        }
+        Ok(())
+    }
+
+    /// Enters a room: creates its instances (or restores them if the room is persistent and was visited before),
+    /// then runs the creation codes and the Game Start and Room Start events.
+    fn load_room(&mut self, room_index: usize, game_start: bool) -> Result<(), String> {
+        let room: GMRoom = self.data.rooms.rooms_by_index.get(room_index).cloned()
+            .ok_or_else(|| format!("Room index {room_index} out of bounds (length {})", self.data.rooms.rooms_by_index.len()))?;
+        let room_name: String = room.name.resolve(&self.data.strings.strings_by_index)?.clone();
+        log::info!("Entering room {room_name} ({room_index})");
+        self.room_layers = RoomLayers::load(&self.data, &room)
+            .map_err(|e| format!("{e}\n↳ while loading layers of room {room_name}"))?;
+        self.views = Views::load(&room);
+        let (surface_width, surface_height): (u32, u32) = self.views.surface_size(room.width, room.height);
+        self.canvas.resize(surface_width, surface_height);
+        self.room_index = room_index;
+        self.enter_room_speed(room.speed, game_start);
+        self.current_room = room;
+        self.reset_grid();
+
+        if let Some(instances) = self.room_states.remove(&room_index) {
+            for instance in &instances {
+                self.mark_moved(instance.id);
+            }
+            self.instances.extend(instances);
+        } else {
+            self.create_room_instances()
+                .map_err(|e| format!("{e}\n↳ while creating instances of room {room_name}"))?;
+        }
+
+        if game_start {
+            self.fire_event_all(Event::new(EV_OTHER, EV_GAME_START))?;
+        }
+        if let Some(code) = &self.current_room.creation_code {
+            // GameMaker runs room creation code on a dummy instance
+            let dummy: InstanceRef = InstanceRef::Struct(Struct::new_ref(None));
+            self.run_code(CallFrame::new(code.index, dummy.clone(), dummy, Vec::new()))
+                .map_err(|e| format!("{e}\n↳ in creation code of room {room_name}"))?;
+        }
+        self.fire_event_all(Event::new(EV_OTHER, EV_ROOM_START))?;
+        self.instances.remove_destroyed();
+        Ok(())
+    }
+
+    /// Every instance is created before any Create event runs; each instance's creation code follows its Create event.
+    fn create_room_instances(&mut self) -> Result<(), String> {
+        let mut created: Vec<(usize, Option<usize>)> = Vec::new();    // (instance id, creation code index)
+        for placed in self.current_room.game_objects.clone() {
+            let id: usize = placed.instance_id as usize;
+            // persistent instances from an earlier room keep existing instead of being created twice
+            if self.instances.exists(id) {
+                continue
+            }
+            self.instances.create_with_id(id, placed.object_definition.index, f64::from(placed.x), f64::from(placed.y))?;
+            let builtins: &mut InstanceBuiltins = &mut self.instances.get_mut(id)?.builtins;
+            builtins.image_xscale = f64::from(placed.scale_x);
+            builtins.image_yscale = f64::from(placed.scale_y);
+            builtins.image_angle = f64::from(placed.rotation);
+            builtins.image_blend = placed.color & 0xFFFFFF;
+            builtins.image_alpha = f64::from(placed.color >> 24) / 255.0;
+            if let Some(depth) = self.room_layers.instance_depths.get(&id) {
+                builtins.depth = *depth;
+            }
+            self.mark_moved(id);
+            created.push((id, placed.creation_code.as_ref().map(|code| code.index)));
+        }
+
+        for (id, creation_code) in created {
+            self.fire_event(id, Event::new(EV_CREATE, 0))?;
+            let Some(code_index) = creation_code else { continue };
+            if self.instances.exists(id) {
+                self.run_code(CallFrame::new(code_index, InstanceRef::Instance(id), InstanceRef::Instance(id), Vec::new()))
+                    .map_err(|e| format!("{e}\n↳ in creation code of instance {id}"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
@@ -0,0 +1,324 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use log::{info, warn};
+use crate::App;
+use crate::code::builtins::math::Random;
+use crate::collision::BoundingBox;
+use crate::instance::InstanceBuiltins;
+
+/// Side length of a grid cell in room pixels; most sprites fit into one or a few cells
+const CELL_SIZE: f64 = 64.0;
+/// Instances covering more cells than this are kept in a separate list which every query returns
+const MAX_CELLS_PER_ENTRY: i64 = 64;
+
+type Cell = (i64, i64);
+
+/// Cells an area covers: (left, top, right, bottom), all inclusive
+fn cell_range(area: &BoundingBox) -> (i64, i64, i64, i64) {
+    (
+        (area.left / CELL_SIZE).floor() as i64,
+        (area.top / CELL_SIZE).floor() as i64,
+        (area.right / CELL_SIZE).floor() as i64,
+        (area.bottom / CELL_SIZE).floor() as i64,
+    )
+}
+
+/// Uniform grid over the room, mapping cells to the instances whose bounding boxes touch them.
+/// Instances that moved are only marked; their entries are updated right before the next query.
+#[derive(Debug, Clone)]
+pub struct SpatialGrid {
+    cells: HashMap<Cell, Vec<usize>>,
+    /// Cell range of every instance in the grid; key: instance ID
+    entries: HashMap<usize, (i64, i64, i64, i64)>,
+    oversized: HashSet<usize>,
+    dirty: HashSet<usize>,
+    max_cells_per_entry: i64,
+}
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+            oversized: HashSet::new(),
+            dirty: HashSet::new(),
+            max_cells_per_entry: MAX_CELLS_PER_ENTRY,
+        }
+    }
+}
+impl SpatialGrid {
+    /// Every instance counts as oversized, so every query returns every instance, like testing all pairs
+    pub fn without_cells() -> Self {
+        Self { max_cells_per_entry: 0, ..Self::default() }
+    }
+
+    pub fn insert(&mut self, id: usize, area: &BoundingBox) {
+        let range: (i64, i64, i64, i64) = cell_range(area);
+        let (left, top, right, bottom) = range;
+        if (right - left + 1) * (bottom - top + 1) > self.max_cells_per_entry {
+            self.oversized.insert(id);
+        } else {
+            for y in top..=bottom {
+                for x in left..=right {
+                    self.cells.entry((x, y)).or_default().push(id);
+                }
+            }
+        }
+        self.entries.insert(id, range);
+    }
+
+    pub fn remove(&mut self, id: usize) {
+        let Some((left, top, right, bottom)) = self.entries.remove(&id) else { return };
+        if self.oversized.remove(&id) {
+            return
+        }
+        for y in top..=bottom {
+            for x in left..=right {
+                let Some(ids) = self.cells.get_mut(&(x, y)) else { continue };
+                ids.retain(|i| *i != id);
+                if ids.is_empty() {
+                    self.cells.remove(&(x, y));
+                }
+            }
+        }
+    }
+
+    /// The instance's position or mask changed; it is updated before the next query
+    pub fn mark_dirty(&mut self, id: usize) {
+        self.dirty.insert(id);
+    }
+
+    pub fn take_dirty(&mut self) -> Vec<usize> {
+        self.dirty.drain().collect()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self { max_cells_per_entry: self.max_cells_per_entry, ..Self::default() };
+    }
+
+    /// Instances whose bounding boxes may intersect `area`, sorted by ID
+    pub fn query(&self, area: &BoundingBox) -> Vec<usize> {
+        let (left, top, right, bottom) = cell_range(area);
+        let mut found: HashSet<usize> = self.oversized.clone();
+        for y in top..=bottom {
+            for x in left..=right {
+                if let Some(ids) = self.cells.get(&(x, y)) {
+                    found.extend(ids);
+                }
+            }
+        }
+        let mut found: Vec<usize> = found.into_iter().collect();
+        found.sort_unstable();
+        found
+    }
+}
+
+
+impl App {
+    /// Brings the grid entries of moved, created and destroyed instances up to date
+    pub fn refresh_grid(&self) -> Result<(), String> {
+        let dirty: Vec<usize> = self.grid.borrow_mut().take_dirty();
+        for id in dirty {
+            let bbox: Option<BoundingBox> = if self.instances.exists(id) {
+                self.collider(id)?.map(|collider| collider.bbox)
+            } else {
+                None
+            };
+            let mut grid = self.grid.borrow_mut();
+            grid.remove(id);
+            if let Some(bbox) = bbox {
+                grid.insert(id, &bbox);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn mark_moved(&self, id: usize) {
+        self.grid.borrow_mut().mark_dirty(id);
+    }
+
+    /// Rebuilds the grid from scratch, e.g. after a room change
+    pub fn reset_grid(&self) {
+        let mut grid = self.grid.borrow_mut();
+        grid.clear();
+        for id in self.instances.living_ids() {
+            grid.mark_dirty(id);
+        }
+    }
+}
+
+
+/// A benchmark instance with the position it starts at and the speed it moves with
+#[derive(Debug, Clone, Copy)]
+struct BenchmarkBody {
+    id: usize,
+    x: f64,
+    y: f64,
+    hspeed: f64,
+    vspeed: f64,
+}
+
+impl App {
+    /// `ACORN_BENCHMARK_GRID=<count>`: fills a room with `count` moving instances of the game's objects
+    /// and times the collision queries of the Collision event through the grid and by testing all pairs
+    pub fn benchmark_grid(&mut self, count: usize) -> Result<(), String> {
+        const STEPS: usize = 60;
+        let (width, height): (f64, f64) = (4096.0, 4096.0);
+        let bodies: Vec<BenchmarkBody> = self.spawn_benchmark_instances(count, width, height)?;
+        info!("Collision benchmark: {count} instances in a {width}x{height} room, {STEPS} steps");
+        let (naive_time, naive_pairs) = self.run_benchmark(&bodies, STEPS, SpatialGrid::without_cells())?;
+        let (grid_time, grid_pairs) = self.run_benchmark(&bodies, STEPS, SpatialGrid::default())?;
+        info!("| All pairs: {naive_time:?} ({naive_pairs} collisions)");
+        info!("| Grid:      {grid_time:?} ({grid_pairs} collisions)");
+        info!("| Speedup:   {:.1}x", naive_time.as_secs_f64() / grid_time.as_secs_f64().max(f64::EPSILON));
+        if naive_pairs != grid_pairs {
+            warn!("The grid found {grid_pairs} collisions, but testing all pairs found {naive_pairs}");
+        }
+        Ok(())
+    }
+
+    /// Creates instances of every object with a collision mask in turn, without running their events
+    fn spawn_benchmark_instances(&mut self, count: usize, width: f64, height: f64) -> Result<Vec<BenchmarkBody>, String> {
+        let mut random: Random = Random::new(12345);
+        let object_count: usize = self.data.game_objects.game_objects_by_index.len();
+        // objects turn out to have no mask when their first instance is created
+        let mut has_mask: Vec<bool> = vec![true; object_count];
+        let mut bodies: Vec<BenchmarkBody> = Vec::with_capacity(count);
+        let mut object_index: usize = 0;
+        while bodies.len() < count {
+            if !has_mask.contains(&true) {
+                return Err("None of the game's objects has a collision mask".to_string())
+            }
+            object_index = (object_index + 1) % object_count;
+            if !has_mask[object_index] {
+                continue
+            }
+            let (x, y): (f64, f64) = (random.next_real() * width, random.next_real() * height);
+            let id: usize = self.instances.create(object_index, x, y)?;
+            if self.collider(id)?.is_none() {
+                has_mask[object_index] = false;
+                self.instances.destroy(id)?;
+                continue
+            }
+            let (hspeed, vspeed): (f64, f64) = (random.next_real() * 8.0 - 4.0, random.next_real() * 8.0 - 4.0);
+            bodies.push(BenchmarkBody { id, x, y, hspeed, vspeed });
+        }
+        self.instances.remove_destroyed();
+        Ok(bodies)
+    }
+
+    /// Moves the bodies every step and looks for their collisions the way `collision_events` does,
+    /// without running any events; returns the time taken and the number of collisions found
+    fn run_benchmark(&mut self, bodies: &[BenchmarkBody], steps: usize, grid: SpatialGrid) -> Result<(Duration, usize), String> {
+        for body in bodies {
+            let builtins: &mut InstanceBuiltins = &mut self.instances.get_mut(body.id)?.builtins;
+            builtins.x = body.x;
+            builtins.y = body.y;
+        }
+        *self.grid.borrow_mut() = grid;
+        self.reset_grid();
+        let mut objects: Vec<usize> = self.instances.list.iter().map(|instance| instance.object_index).collect();
+        objects.sort_unstable();
+        objects.dedup();
+
+        let start: Instant = Instant::now();
+        let mut pairs: usize = 0;
+        for _ in 0..steps {
+            for body in bodies {
+                let builtins: &mut InstanceBuiltins = &mut self.instances.get_mut(body.id)?.builtins;
+                builtins.x += body.hspeed;
+                builtins.y += body.vspeed;
+                self.mark_moved(body.id);
+            }
+            for body in bodies {
+                let Some(collider) = self.collider(body.id)? else { continue };
+                let bbox: BoundingBox = collider.bbox;
+                for other_object in &objects {
+                    for other in self.collision_event_candidates(body.id, &bbox, *other_object)? {
+                        if other > body.id && self.instances_collide(body.id, other)? {
+                            pairs += 1;
+                        }
+                    }
+                }
+            }
+        }
+        Ok((start.elapsed(), pairs))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Boxes of every size, from bullets to ones spanning more cells than the grid keeps in cells
+    fn random_boxes(count: usize, random: &mut Random) -> Vec<BoundingBox> {
+        (0..count)
+            .map(|i| {
+                let (x, y): (f64, f64) = (random.next_real() * 2048.0 - 512.0, random.next_real() * 2048.0 - 512.0);
+                let size: f64 = if i % 50 == 0 { 600.0 + random.next_real() * 400.0 } else { 1.0 + random.next_real() * 96.0 };
+                BoundingBox { left: x, top: y, right: x + size, bottom: y + size }
+            })
+            .collect()
+    }
+
+    /// Every pair of overlapping boxes, found by testing all pairs
+    fn brute_force_pairs(boxes: &[BoundingBox]) -> HashSet<(usize, usize)> {
+        let mut pairs: HashSet<(usize, usize)> = HashSet::new();
+        for a in 0..boxes.len() {
+            for b in a + 1..boxes.len() {
+                if boxes[a].intersection(&boxes[b]).is_some() {
+                    pairs.insert((a, b));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn grid_pairs(grid: &SpatialGrid, boxes: &[BoundingBox]) -> HashSet<(usize, usize)> {
+        let mut pairs: HashSet<(usize, usize)> = HashSet::new();
+        for (a, area) in boxes.iter().enumerate() {
+            for b in grid.query(area) {
+                if b > a && area.intersection(&boxes[b]).is_some() {
+                    pairs.insert((a, b));
+                }
+            }
+        }
+        pairs
+    }
+
+    #[test]
+    fn grid_candidates_match_brute_force() {
+        let mut random: Random = Random::new(42);
+        let mut boxes: Vec<BoundingBox> = random_boxes(500, &mut random);
+        let mut grid: SpatialGrid = SpatialGrid::default();
+        for (id, area) in boxes.iter().enumerate() {
+            grid.insert(id, area);
+        }
+        let expected: HashSet<(usize, usize)> = brute_force_pairs(&boxes);
+        assert!(!expected.is_empty());
+        assert_eq!(grid_pairs(&grid, &boxes), expected);
+
+        // move every other box, crossing cell borders and the edges of the room
+        for id in (0..boxes.len()).step_by(2) {
+            let (dx, dy): (f64, f64) = (random.next_real() * 300.0 - 150.0, random.next_real() * 300.0 - 150.0);
+            let area: &mut BoundingBox = &mut boxes[id];
+            *area = BoundingBox { left: area.left + dx, top: area.top + dy, right: area.right + dx, bottom: area.bottom + dy };
+            grid.remove(id);
+            grid.insert(id, area);
+        }
+        assert_eq!(grid_pairs(&grid, &boxes), brute_force_pairs(&boxes));
+    }
+
+    #[test]
+    fn grid_without_cells_returns_everything() {
+        let mut random: Random = Random::new(7);
+        let boxes: Vec<BoundingBox> = random_boxes(50, &mut random);
+        let mut grid: SpatialGrid = SpatialGrid::without_cells();
+        for (id, area) in boxes.iter().enumerate() {
+            grid.insert(id, area);
+        }
+        let far_away: BoundingBox = BoundingBox { left: 1e6, top: 1e6, right: 1e6 + 1.0, bottom: 1e6 + 1.0 };
+        assert_eq!(grid.query(&far_away), (0..boxes.len()).collect::<Vec<usize>>());
+        assert_eq!(grid_pairs(&grid, &boxes), brute_force_pairs(&boxes));
+    }
+}